@@ -0,0 +1,92 @@
+use std::error::Error;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::utils::err_msg;
+
+/// A file in an archive.
+#[derive(Debug)]
+pub struct Entry {
+    pub path: String,
+    pub contents: Vec<u8>,
+}
+
+/// Writes the entries into a new archive at the given path.
+///
+/// The archive is an uncompressed tar file so it can also be inspected with
+/// the regular `tar` tool.
+pub fn write_archive(path: &Path, entries: &[Entry]) -> Result<(), Box<dyn Error>> {
+    let mut builder = tar::Builder::new(fs::File::create(path)?);
+    for entry in entries {
+        // gnu headers support paths of any length
+        let mut header = tar::Header::new_gnu();
+        header.set_mode(0o644);
+        header.set_size(entry.contents.len() as u64);
+        builder.append_data(&mut header, &entry.path, entry.contents.as_slice())?;
+    }
+    builder.into_inner()?;
+    Ok(())
+}
+
+/// Reads all regular files from the archive at the given path.
+pub fn read_archive(path: &Path) -> Result<Vec<Entry>, Box<dyn Error>> {
+    let mut archive = tar::Archive::new(fs::File::open(path)?);
+    let mut rv = vec![];
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        // only regular files are of interest
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = entry
+            .path()?
+            .to_str()
+            .ok_or_else(|| err_msg("non utf-8 path in archive"))?
+            .replace('\\', "/");
+        let mut contents = vec![];
+        entry.read_to_end(&mut contents)?;
+        if contents.len() as u64 != entry.header().size()? {
+            return Err(err_msg("archive is truncated"));
+        }
+        rv.push(Entry { path, contents });
+    }
+    Ok(rv)
+}
+
+#[test]
+fn test_archive_roundtrip() {
+    let dir = std::env::temp_dir().join(format!("cargo-insta-archive-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("pending.tar");
+    let long_path = format!("{}snapshots/.äöü.pending-snap", "nested/".repeat(30));
+    write_archive(
+        &path,
+        &[
+            Entry {
+                path: "src/snapshots/x__ünïcode.snap.new".into(),
+                contents: b"contents".to_vec(),
+            },
+            Entry {
+                path: long_path.clone(),
+                contents: vec![b'x'; 1000],
+            },
+        ],
+    )
+    .unwrap();
+
+    let entries = read_archive(&path).unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].path, "src/snapshots/x__ünïcode.snap.new");
+    assert_eq!(entries[0].contents, b"contents");
+    assert_eq!(entries[1].path, long_path);
+    assert_eq!(entries[1].contents, vec![b'x'; 1000]);
+
+    // cut off in the middle of the contents of the last entry
+    let data = fs::read(&path).unwrap();
+    let idx = data.windows(8).rposition(|x| x == b"xxxxxxxx").unwrap() - 200;
+    fs::write(&path, &data[..idx]).unwrap();
+    assert!(read_archive(&path).is_err());
+
+    fs::remove_dir_all(&dir).unwrap();
+}
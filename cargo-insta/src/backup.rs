@@ -0,0 +1,128 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::utils::err_msg;
+
+/// The files changed by one run with `--backup`.
+///
+/// The backup is stored in `.insta/backup` in the workspace root.  Copies of
+/// the files are kept below that folder by their path relative to the
+/// workspace root and the `manifest` in it lists every backed up file.  Lines
+/// start with `copy` for files that existed before the run and with `new` for
+/// files that did not and are removed on restore.
+///
+/// A run replaces the backup of an earlier run once it backs up its first
+/// file so that restoring always undoes the last run.
+pub struct Backup {
+    workspace_root: PathBuf,
+    dir: PathBuf,
+    /// Maps the backed up paths to whether the file existed.
+    entries: BTreeMap<PathBuf, bool>,
+}
+
+impl Backup {
+    /// Returns the folder of the backup for a workspace.
+    pub fn dir_for(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(".insta").join("backup")
+    }
+
+    /// Creates an empty backup.  Nothing is written before a file is added.
+    pub fn new(workspace_root: &Path) -> Backup {
+        Backup {
+            workspace_root: workspace_root.to_path_buf(),
+            dir: Backup::dir_for(workspace_root),
+            entries: BTreeMap::new(),
+        }
+    }
+
+    /// Returns the number of backed up files.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Backs up a file before it is changed, created or removed.
+    pub fn add(&mut self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let rel_path = path
+            .strip_prefix(&self.workspace_root)
+            .map_err(|_| {
+                err_msg(format!(
+                    "cannot back up {} outside of the workspace",
+                    path.display()
+                ))
+            })?
+            .to_path_buf();
+        if self.entries.contains_key(&rel_path) {
+            return Ok(());
+        }
+        if self.entries.is_empty() && self.dir.exists() {
+            fs::remove_dir_all(&self.dir)?;
+        }
+        let exists = path.is_file();
+        if exists {
+            let backup_path = self.dir.join(&rel_path);
+            if let Some(parent) = backup_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(path, &backup_path)?;
+        }
+        self.entries.insert(rel_path, exists);
+        self.save()
+    }
+
+    /// Writes the manifest.
+    fn save(&self) -> Result<(), Box<dyn Error>> {
+        let mut contents = String::new();
+        for (rel_path, &exists) in self.entries.iter() {
+            contents.push_str(if exists { "copy " } else { "new " });
+            contents.push_str(&rel_path.to_string_lossy());
+            contents.push('\n');
+        }
+        fs::create_dir_all(&self.dir)?;
+        fs::write(self.dir.join("manifest"), contents)?;
+        Ok(())
+    }
+
+    /// Restores the files of the last backup of a workspace and removes it.
+    ///
+    /// The callback is invoked with every restored path and whether the file
+    /// was copied back (`true`) or removed (`false`).
+    pub fn restore<F>(workspace_root: &Path, mut f: F) -> Result<usize, Box<dyn Error>>
+    where
+        F: FnMut(&Path, bool),
+    {
+        let dir = Backup::dir_for(workspace_root);
+        let manifest_path = dir.join("manifest");
+        if !manifest_path.is_file() {
+            return Ok(0);
+        }
+        let mut restored = 0;
+        for line in fs::read_to_string(&manifest_path)?.lines() {
+            let mut iter = line.splitn(2, ' ');
+            let (exists, rel_path) = match (iter.next(), iter.next()) {
+                (Some("copy"), Some(rel_path)) => (true, rel_path),
+                (Some("new"), Some(rel_path)) => (false, rel_path),
+                _ => continue,
+            };
+            let path = workspace_root.join(rel_path);
+            if exists {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::copy(dir.join(rel_path), &path)?;
+            } else if path.is_file() {
+                fs::remove_file(&path)?;
+            } else {
+                continue;
+            }
+            restored += 1;
+            f(&path, exists);
+        }
+        fs::remove_dir_all(&dir)?;
+        if let Some(parent) = dir.parent() {
+            fs::remove_dir(parent).ok();
+        }
+        Ok(restored)
+    }
+}
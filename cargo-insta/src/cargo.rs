@@ -7,15 +7,21 @@ use std::process;
 
 use ignore::overrides::OverrideBuilder;
 use ignore::{DirEntry, WalkBuilder};
+use insta::_cargo_insta_support::{
+    binary_file_path, data_file_path, format_unified_diff, load_golden_file, save_golden_file,
+    save_snapshot_with_contents, snapshot_file_contents, Operation, PendingInlineSnapshot,
+    ReviewContainer,
+};
 use insta::Snapshot;
-use insta::_cargo_insta_support::PendingInlineSnapshot;
 use serde::Deserialize;
 
+use crate::backup::Backup;
 use crate::inline::FilePatcher;
 use crate::utils::err_msg;
 
 #[derive(Deserialize, Clone, Debug)]
 pub struct Target {
+    name: String,
     src_path: PathBuf,
     kind: HashSet<String>,
 }
@@ -34,19 +40,17 @@ pub struct Metadata {
     packages: Vec<Package>,
     workspace_members: Vec<String>,
     workspace_root: String,
-}
-
-#[derive(Clone, Copy, Debug)]
-pub enum Operation {
-    Accept,
-    Reject,
-    Skip,
+    target_directory: String,
 }
 
 impl Metadata {
     pub fn workspace_root(&self) -> &Path {
         Path::new(&self.workspace_root)
     }
+
+    pub fn target_directory(&self) -> &Path {
+        Path::new(&self.target_directory)
+    }
 }
 
 #[derive(Deserialize, Debug)]
@@ -67,6 +71,13 @@ pub struct PendingSnapshot {
     pub new: Snapshot,
     pub op: Operation,
     pub line: Option<u32>,
+    /// The position among the snapshots of the same line in the order the
+    /// assertions ran.  This is only non-zero for inline snapshots that
+    /// asserted more than once, for instance in a loop.
+    pub sub_index: usize,
+    /// Set if `new` was changed during the review.  The pending file still
+    /// holds the original snapshot then.
+    pub edited: bool,
 }
 
 impl PendingSnapshot {
@@ -79,6 +90,9 @@ impl PendingSnapshot {
         if let Some(line) = self.line {
             write!(&mut rv, ":{}", line).unwrap();
         }
+        if self.sub_index > 0 {
+            write!(&mut rv, "#{}", self.sub_index + 1).unwrap();
+        }
         if let Some(name) = self.new.snapshot_name() {
             write!(&mut rv, " ({})", name).unwrap();
         }
@@ -93,30 +107,25 @@ pub struct SnapshotContainer {
     kind: SnapshotContainerKind,
     snapshots: Vec<PendingSnapshot>,
     patcher: Option<FilePatcher>,
+    /// The index of the snapshot macro in the patcher for every snapshot.
+    macro_ids: Vec<usize>,
+    /// Set if the target file was edited in a way that the pending inline
+    /// snapshots could not be found again.  Nothing is written then.
+    outdated: bool,
 }
 
 impl SnapshotContainer {
     fn load(
         snapshot_path: PathBuf,
-        target_path: PathBuf,
+        mut target_path: PathBuf,
         kind: SnapshotContainerKind,
     ) -> Result<SnapshotContainer, Box<dyn Error>> {
         let mut snapshots = Vec::new();
+        let mut macro_ids = Vec::new();
         let patcher = match kind {
             SnapshotContainerKind::External => {
-                let old = if fs::metadata(&target_path).is_err() {
-                    None
-                } else {
-                    Some(Snapshot::from_file(&target_path)?)
-                };
                 let new = Snapshot::from_file(&snapshot_path)?;
-                snapshots.push(PendingSnapshot {
-                    id: 0,
-                    old,
-                    new,
-                    op: Operation::Skip,
-                    line: None,
-                });
+                snapshots.push(external_snapshot(new, &mut target_path, false)?);
                 None
             }
             SnapshotContainerKind::Inline => {
@@ -125,17 +134,31 @@ impl SnapshotContainer {
 
                 let rv = if fs::metadata(&target_path).is_ok() {
                     let mut patcher = FilePatcher::open(&target_path)?;
+                    // the sort is stable so snapshots of the same line stay
+                    // in the order the assertions ran.
                     pending_vec.sort_by_key(|pending| pending.line);
+                    let mut last_line = None;
+                    let mut sub_index = 0;
                     for (id, pending) in pending_vec.into_iter().enumerate() {
                         if let Some(new) = pending.new {
+                            // snapshots of the same line share the macro
+                            if last_line == Some(pending.line) {
+                                sub_index += 1;
+                            } else {
+                                patcher.add_snapshot_macro(pending.line as usize);
+                                last_line = Some(pending.line);
+                                sub_index = 0;
+                            }
                             snapshots.push(PendingSnapshot {
                                 id,
                                 old: pending.old,
                                 new,
                                 op: Operation::Skip,
                                 line: Some(pending.line),
+                                sub_index,
+                                edited: false,
                             });
-                            patcher.add_snapshot_macro(pending.line as usize);
+                            macro_ids.push(patcher.macro_count() - 1);
                             have_new = true;
                         }
                     }
@@ -162,6 +185,28 @@ impl SnapshotContainer {
             kind,
             snapshots,
             patcher,
+            macro_ids,
+            outdated: false,
+        })
+    }
+
+    /// Creates a container for a snapshot of a snapshot file that is not
+    /// pending on disk.  Accepting it writes the snapshot file directly.
+    pub fn from_snapshot(
+        new: Snapshot,
+        mut target_path: PathBuf,
+    ) -> Result<SnapshotContainer, Box<dyn Error>> {
+        let mut snapshot_path = target_path.clone().into_os_string();
+        snapshot_path.push(".new");
+        let snapshot = external_snapshot(new, &mut target_path, true)?;
+        Ok(SnapshotContainer {
+            snapshot_path: snapshot_path.into(),
+            target_path,
+            kind: SnapshotContainerKind::External,
+            snapshots: vec![snapshot],
+            patcher: None,
+            macro_ids: vec![],
+            outdated: false,
         })
     }
 
@@ -169,6 +214,11 @@ impl SnapshotContainer {
         &self.target_path
     }
 
+    /// Returns the file that holds the pending snapshots.
+    pub fn pending_file(&self) -> &Path {
+        &self.snapshot_path
+    }
+
     pub fn snapshot_file(&self) -> Option<&Path> {
         match self.kind {
             SnapshotContainerKind::External => Some(&self.target_path),
@@ -176,24 +226,176 @@ impl SnapshotContainer {
         }
     }
 
+    /// Returns `true` if the pending snapshot is for a golden file.
+    fn is_golden(&self) -> bool {
+        self.snapshots
+            .iter()
+            .any(|x| x.new.metadata().golden_file().is_some())
+    }
+
     pub fn len(&self) -> usize {
         self.snapshots.len()
     }
 
+    /// Reads the target file of inline snapshots again after it was edited.
+    ///
+    /// The pending snapshots move along with the lines added or removed
+    /// before them.  If an edit spans the assertions of other pending
+    /// snapshots they cannot be found anymore and the container is marked
+    /// as outdated so that committing it does not overwrite the edit.
+    pub fn reload(&mut self) -> Result<(), Box<dyn Error>> {
+        let old_contents = match self.patcher {
+            Some(ref patcher) => patcher.contents(),
+            None => return Ok(()),
+        };
+        let new_contents = fs::read_to_string(&self.target_path)?;
+        let old_lines: Vec<_> = old_contents.lines().collect();
+        let new_lines: Vec<_> = new_contents.lines().collect();
+        let prefix = old_lines
+            .iter()
+            .zip(new_lines.iter())
+            .take_while(|(a, b)| a == b)
+            .count();
+        let suffix = old_lines[prefix..]
+            .iter()
+            .rev()
+            .zip(new_lines[prefix..].iter().rev())
+            .take_while(|(a, b)| a == b)
+            .count();
+
+        // lines before the edit stay, lines after it move by the number of
+        // added lines.  The first edited line still starts an assertion that
+        // was edited in place.
+        let move_line = |line: usize| {
+            if line <= prefix + 1 {
+                Some(line)
+            } else if line > old_lines.len() - suffix {
+                Some(line + new_lines.len() - old_lines.len())
+            } else {
+                None
+            }
+        };
+
+        let mut patcher = FilePatcher::open(&self.target_path)?;
+        let mut macro_ids = Vec::new();
+        let mut lines = Vec::new();
+        let mut last_line = None;
+        for snapshot in self.snapshots.iter() {
+            let line = match snapshot.line.and_then(|x| move_line(x as usize)) {
+                Some(line) if patcher.has_snapshot_macro(line) => line,
+                _ => {
+                    self.outdated = true;
+                    return Err(err_msg(format!(
+                        "the pending snapshots of {} were changed by the edit, \
+                         decisions for this file are not written",
+                        self.target_path.display()
+                    )));
+                }
+            };
+            if last_line != Some(line) {
+                patcher.add_snapshot_macro(line);
+                last_line = Some(line);
+            }
+            macro_ids.push(patcher.macro_count() - 1);
+            lines.push(line as u32);
+        }
+        for (snapshot, line) in self.snapshots.iter_mut().zip(lines) {
+            snapshot.line = Some(line);
+        }
+        self.patcher = Some(patcher);
+        self.macro_ids = macro_ids;
+        Ok(())
+    }
+
     pub fn iter_snapshots(&mut self) -> impl Iterator<Item = &'_ mut PendingSnapshot> {
         self.snapshots.iter_mut()
     }
 
+    /// Returns the changes that committing accepted snapshots would write.
+    ///
+    /// The changes are returned as unified diff which is empty if no
+    /// snapshot in the container was accepted.
+    pub fn accepted_patch(&self) -> Result<String, Box<dyn Error>> {
+        let path = self.target_path.display().to_string();
+        if self.outdated {
+            return Ok(String::new());
+        }
+        if let Some(ref patcher) = self.patcher {
+            let mut patched = patcher.clone();
+            let mut did_accept = false;
+            for (idx, snapshot) in self.snapshots.iter().enumerate() {
+                if let Operation::Accept = snapshot.op {
+                    patched.set_new_content(self.macro_ids[idx], snapshot.new.contents());
+                    did_accept = true;
+                }
+            }
+            if !did_accept {
+                return Ok(String::new());
+            }
+            Ok(format_unified_diff(
+                &patcher.contents(),
+                &patched.contents(),
+                &path,
+                &path,
+            ))
+        } else {
+            for snapshot in self.snapshots.iter() {
+                if let Operation::Accept = snapshot.op {
+                    let old = fs::read_to_string(&self.target_path).unwrap_or_default();
+                    let new = if self.is_golden() {
+                        format!("{}\n", snapshot.new.contents_str().trim_end())
+                    } else if snapshot.edited {
+                        snapshot_file_contents(&snapshot.new)?
+                    } else {
+                        fs::read_to_string(&self.snapshot_path)?
+                    };
+                    return Ok(format_unified_diff(&old, &new, &path, &path));
+                }
+            }
+            Ok(String::new())
+        }
+    }
+
+    /// Backs up the files that committing the decisions would change.
+    ///
+    /// This covers the target file, the pending file and the sidecar files
+    /// of both.  Returns `false` if there was nothing to back up.
+    pub fn backup(&self, backup: &mut Backup) -> Result<bool, Box<dyn Error>> {
+        let did_decide = self.snapshots.iter().any(|snapshot| match snapshot.op {
+            Operation::Accept | Operation::Reject => true,
+            Operation::Skip => false,
+        });
+        if !did_decide || self.outdated {
+            return Ok(false);
+        }
+        backup.add(&self.target_path)?;
+        backup.add(&self.snapshot_path)?;
+        for sidecar_path in &[data_file_path, binary_file_path] {
+            let target_sidecar_path = sidecar_path(&self.target_path);
+            let pending_sidecar_path = sidecar_path(&self.snapshot_path);
+            if target_sidecar_path.is_file() || pending_sidecar_path.is_file() {
+                backup.add(&target_sidecar_path)?;
+                backup.add(&pending_sidecar_path)?;
+            }
+        }
+        Ok(true)
+    }
+
     pub fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.outdated {
+            return Ok(());
+        }
         if let Some(ref mut patcher) = self.patcher {
             let mut new_pending = vec![];
             let mut did_accept = false;
             let mut did_skip = false;
 
+            // if several snapshots of the same line are accepted the one
+            // that ran last wins.  Skipped snapshots keep their order.
             for (idx, snapshot) in self.snapshots.iter().enumerate() {
                 match snapshot.op {
                     Operation::Accept => {
-                        patcher.set_new_content(idx, snapshot.new.contents());
+                        patcher.set_new_content(self.macro_ids[idx], snapshot.new.contents());
                         did_accept = true;
                     }
                     Operation::Reject => {}
@@ -201,7 +403,7 @@ impl SnapshotContainer {
                         new_pending.push(PendingInlineSnapshot::new(
                             Some(snapshot.new.clone()),
                             snapshot.old.clone(),
-                            patcher.get_new_line(idx) as u32,
+                            patcher.get_new_line(self.macro_ids[idx]) as u32,
                         ));
                         did_skip = true;
                     }
@@ -217,14 +419,40 @@ impl SnapshotContainer {
                 fs::remove_file(&self.snapshot_path)?;
             }
         } else {
+            let is_golden = self.is_golden();
             // should only be one or this is weird
             for snapshot in self.snapshots.iter() {
                 match snapshot.op {
+                    Operation::Accept if is_golden => {
+                        save_golden_file(&self.target_path, snapshot.new.contents())?;
+                        fs::remove_file(&self.snapshot_path).ok();
+                    }
+                    Operation::Accept if snapshot.edited => {
+                        save_snapshot_with_contents(
+                            &snapshot.new,
+                            snapshot.new.contents_str(),
+                            &self.target_path,
+                        )?;
+                        fs::remove_file(&self.snapshot_path).ok();
+                        fs::remove_file(data_file_path(&self.snapshot_path)).ok();
+                    }
                     Operation::Accept => {
                         fs::rename(&self.snapshot_path, &self.target_path)?;
+                        // externalized contents and the bytes of binary
+                        // snapshots move along with the snapshot
+                        for sidecar_path in &[data_file_path, binary_file_path] {
+                            let pending_path = sidecar_path(&self.snapshot_path);
+                            if pending_path.is_file() {
+                                fs::rename(&pending_path, sidecar_path(&self.target_path))?;
+                            } else {
+                                fs::remove_file(sidecar_path(&self.target_path)).ok();
+                            }
+                        }
                     }
                     Operation::Reject => {
                         fs::remove_file(&self.snapshot_path)?;
+                        fs::remove_file(data_file_path(&self.snapshot_path)).ok();
+                        fs::remove_file(binary_file_path(&self.snapshot_path)).ok();
                     }
                     Operation::Skip => {}
                 }
@@ -234,6 +462,35 @@ impl SnapshotContainer {
     }
 }
 
+/// Pairs the pending snapshot of a snapshot file with the old snapshot.
+fn external_snapshot(
+    new: Snapshot,
+    target_path: &mut PathBuf,
+    edited: bool,
+) -> Result<PendingSnapshot, Box<dyn Error>> {
+    // pending snapshots of golden files target the plain file
+    if let Some(golden_file) = new.metadata().golden_file() {
+        target_path.set_file_name(golden_file);
+    }
+    let target_path = target_path.as_path();
+    let old = if fs::metadata(target_path).is_err() {
+        None
+    } else if new.metadata().golden_file().is_some() {
+        Some(load_golden_file(target_path)?)
+    } else {
+        Some(Snapshot::from_file(target_path)?)
+    };
+    Ok(PendingSnapshot {
+        id: 0,
+        old,
+        new,
+        op: Operation::Skip,
+        line: None,
+        sub_index: 0,
+        edited,
+    })
+}
+
 fn is_hidden(entry: &DirEntry) -> bool {
     entry
         .file_name()
@@ -242,6 +499,22 @@ fn is_hidden(entry: &DirEntry) -> bool {
         .unwrap_or(false)
 }
 
+impl ReviewContainer for SnapshotContainer {
+    type Snapshot = PendingSnapshot;
+
+    fn snapshots(&self) -> &[PendingSnapshot] {
+        &self.snapshots
+    }
+
+    fn set_operation(&mut self, idx: usize, op: Operation) {
+        self.snapshots[idx].op = op;
+    }
+
+    fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+        SnapshotContainer::commit(self)
+    }
+}
+
 pub fn find_snapshots<'a>(
     root: PathBuf,
     extensions: &'a [&'a str],
@@ -258,6 +531,9 @@ pub fn find_snapshots<'a>(
         .add(".*.pending-snap")
         .unwrap()
         .add("*.snap.new")
+        .unwrap()
+        // snapshots with a custom extension such as `.snap.html`
+        .add("*.snap.*.new")
         .unwrap();
 
     for ext in extensions {
@@ -303,6 +579,53 @@ impl Package {
         &self.version
     }
 
+    /// Returns the module path of a source file of the package.
+    ///
+    /// Inline modules within the file are not taken into account.
+    pub fn module_path_of(&self, file: &Path) -> Option<String> {
+        let targets: Vec<_> = self
+            .targets
+            .iter()
+            .filter_map(|x| Some((x, fs::canonicalize(&x.src_path).ok()?)))
+            .collect();
+
+        // files that are the root module of a target
+        for &(target, ref src_path) in targets.iter() {
+            if src_path == file {
+                return Some(target.name.replace('-', "_"));
+            }
+        }
+
+        // other files only belong to targets with a root module that
+        // declares the modules of its folder.  The library owns the source
+        // folder if it shares it with binaries.
+        let mut targets = targets;
+        targets.sort_by_key(|x| !x.0.kind.contains("lib"));
+        for (target, src_path) in targets {
+            let root_module = src_path.file_name().and_then(|x| x.to_str());
+            if root_module != Some("lib.rs")
+                && root_module != Some("main.rs")
+                && root_module != Some("mod.rs")
+            {
+                continue;
+            }
+            let rel_path = match file.strip_prefix(src_path.parent()?) {
+                Ok(rel_path) => rel_path,
+                Err(_) => continue,
+            };
+
+            let mut rv = vec![target.name.replace('-', "_")];
+            for component in rel_path.with_extension("").components() {
+                rv.push(component.as_os_str().to_str()?.to_string());
+            }
+            if rv.last().map(|x| x.as_str()) == Some("mod") {
+                rv.pop();
+            }
+            return Some(rv.join("::"));
+        }
+        None
+    }
+
     pub fn iter_snapshot_containers<'a>(
         &self,
         extensions: &'a [&'a str],
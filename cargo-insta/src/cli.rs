@@ -1,24 +1,37 @@
 use std::borrow::Cow;
-use std::collections::HashSet;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
 use std::ffi::OsStr;
-use std::path::{Path, PathBuf};
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
 use std::process;
+use std::thread;
+use std::time::{Duration, SystemTime};
 use std::{env, fs};
 
 use console::{set_colors_enabled, style, Key, Term};
 use ignore::{Walk, WalkBuilder};
 use insta::Snapshot;
-use insta::_cargo_insta_support::print_snapshot_diff;
+use insta::_cargo_insta_support::{
+    binary_file_path, classify_snapshot_diff, count_changed_lines, data_file_path,
+    decide_snapshots, get_diff_style, merge_line_range, print_snapshot, print_snapshot_diff,
+    review_container, save_named_snapshot, save_snapshot_with_contents, snapshot_from_str,
+    snapshot_with_contents, DiffKind, DiffStyle, Operation, PendingInlineSnapshot,
+};
+use regex::Regex;
 use serde::Serialize;
-use structopt::clap::AppSettings;
+use structopt::clap::{AppSettings, Shell};
 use structopt::StructOpt;
 use uuid::Uuid;
 
+use crate::archive::{read_archive, write_archive, Entry};
+use crate::backup::Backup;
 use crate::cargo::{
-    find_packages, find_snapshots, get_cargo, get_package_metadata, Operation, Package,
+    find_packages, find_snapshots, get_cargo, get_package_metadata, Package, PendingSnapshot,
     SnapshotContainer,
 };
+use crate::inline::{FilePatcher, SnapshotAssertion};
+use crate::queue::ReviewQueue;
 use crate::utils::{err_msg, QuietExit};
 
 /// A helper utility to work with insta snapshots.
@@ -32,10 +45,14 @@ use crate::utils::{err_msg, QuietExit};
     global_setting = AppSettings::DontCollapseArgsInUsage
 )]
 pub struct Opts {
-    /// Coloring: auto, always, never
+    /// Coloring: auto, always, never.  `auto` honors `NO_COLOR`.
     #[structopt(long, global = true, value_name = "WHEN")]
     pub color: Option<String>,
 
+    /// Caps the width used to render diffs.  Defaults to the terminal width.
+    #[structopt(long, global = true, value_name = "WIDTH")]
+    pub max_width: Option<usize>,
+
     #[structopt(subcommand)]
     pub command: Command,
 }
@@ -58,6 +75,42 @@ pub enum Command {
     /// Print a summary of all pending snapshots.
     #[structopt(name = "pending-snapshots")]
     PendingSnapshots(PendingSnapshotsCommand),
+    /// Print a single snapshot file with its metadata.
+    #[structopt(name = "show")]
+    Show(ShowCommand),
+    /// Find snapshots that no longer match their source.
+    #[structopt(name = "doctor")]
+    Doctor(DoctorCommand),
+    /// Split a snapshot into multiple snapshots.
+    #[structopt(name = "split")]
+    Split(SplitCommand),
+    /// Convert a snapshot into another format.
+    #[structopt(name = "convert")]
+    Convert(ConvertCommand),
+    /// Restore the files changed by the last run with --backup.
+    #[structopt(name = "restore")]
+    Restore(RestoreCommand),
+    /// Export pending snapshots into an archive.
+    #[structopt(name = "export")]
+    Export(ExportCommand),
+    /// Import pending snapshots from an archive.
+    #[structopt(name = "import")]
+    Import(ImportCommand),
+    /// Manage the snapshots skipped in earlier reviews.
+    #[structopt(name = "queue")]
+    Queue(QueueCommand),
+    /// Move an inline snapshot into a snapshot file.
+    #[structopt(name = "externalize")]
+    Externalize(ExternalizeCommand),
+    /// Move a snapshot from a snapshot file into the source.
+    #[structopt(name = "inline")]
+    Inline(InlineCommand),
+    /// Rename a named snapshot and its assertion.
+    #[structopt(name = "mv")]
+    Mv(MvCommand),
+    /// Print a completion script for a shell.
+    #[structopt(name = "completions")]
+    Completions(CompletionsCommand),
 }
 
 #[derive(StructOpt, Debug, Clone)]
@@ -73,7 +126,7 @@ pub struct TargetArgs {
     #[structopt(short = "e", long, value_name = "EXTENSIONS", multiple = true)]
     pub extensions: Vec<String>,
     /// Work on all packages in the workspace
-    #[structopt(long)]
+    #[structopt(long, visible_alias = "workspace")]
     pub all: bool,
     /// Also walk into ignored paths.
     #[structopt(long)]
@@ -85,15 +138,63 @@ pub struct TargetArgs {
 pub struct ProcessCommand {
     #[structopt(flatten)]
     pub target_args: TargetArgs,
+    /// Limits the operation to the snapshots of a package in the workspace.
+    #[structopt(short = "p", long, conflicts_with = "all")]
+    pub package: Option<String>,
     /// Limits the operation to one or more snapshots.
     #[structopt(long = "snapshot")]
     pub snapshot_filter: Option<Vec<String>>,
+    /// Limits the operation to snapshots whose name contains the given value.
+    #[structopt(long)]
+    pub snapshot_name: Option<Vec<String>>,
+    /// Requires --snapshot-name to match the name exactly.
+    #[structopt(long, requires = "snapshot-name")]
+    pub exact: bool,
+    /// Limits the operation to snapshots whose name or file matches the regex.
+    #[structopt(long, value_name = "REGEX")]
+    pub filter: Option<String>,
+    /// Shows the accepted changes and asks for confirmation before writing.
+    #[structopt(long)]
+    pub confirm: bool,
+    /// Backs up the changed files to `.insta/backup` before writing.
+    #[structopt(long)]
+    pub backup: bool,
+    /// Starts at the pending snapshot at the given location.
+    #[structopt(long, value_name = "FILE:LINE")]
+    pub goto: Option<String>,
+    /// Accepts snapshots but asks for the ones whose contents match the regex.
+    #[structopt(long, value_name = "REGEX")]
+    pub interactive_filter: Option<String>,
+    /// Ends the review after this many snapshots were accepted or rejected.
+    #[structopt(long, value_name = "N")]
+    pub max_snapshots: Option<usize>,
+    /// Layout of diffs: unified, side-by-side
+    #[structopt(
+        long,
+        value_name = "STYLE",
+        possible_values = &["unified", "side-by-side"],
+        parse(try_from_str = parse_diff_style)
+    )]
+    pub diff_style: Option<DiffStyle>,
+    /// Limits the operation to pending snapshots older than the duration (eg: 7d).
+    #[structopt(long, value_name = "DURATION", parse(try_from_str = parse_duration))]
+    pub older_than: Option<Duration>,
+    /// Limits the operation to new snapshots and skips changes to existing ones.
+    #[structopt(long)]
+    pub new_only: bool,
+    /// Limits the operation to snapshots changed since the branch left BASE
+    /// (defaults to the default branch of origin).
+    #[structopt(long, value_name = "BASE")]
+    pub changed: Option<Option<String>>,
+    /// Accepts the snapshot read from stdin into the given snapshot file.
+    #[structopt(long, value_name = "FILE", parse(from_os_str))]
+    pub stdin: Option<PathBuf>,
     /// Do not print to stdout.
     #[structopt(short = "q", long)]
     pub quiet: bool,
 }
 
-#[derive(StructOpt, Debug)]
+#[derive(StructOpt, Debug, Clone)]
 #[structopt(rename_all = "kebab-case")]
 pub struct TestCommand {
     #[structopt(flatten)]
@@ -101,6 +202,30 @@ pub struct TestCommand {
     /// Package to run tests for
     #[structopt(short = "p", long)]
     pub package: Option<String>,
+    /// Test only the specified binary
+    #[structopt(
+        long,
+        value_name = "NAME",
+        number_of_values = 1,
+        conflicts_with = "delete-unreferenced-snapshots"
+    )]
+    pub bin: Vec<String>,
+    /// Test only the specified test target
+    #[structopt(
+        long,
+        value_name = "NAME",
+        number_of_values = 1,
+        conflicts_with = "delete-unreferenced-snapshots"
+    )]
+    pub test: Vec<String>,
+    /// Test only the specified example
+    #[structopt(
+        long,
+        value_name = "NAME",
+        number_of_values = 1,
+        conflicts_with = "delete-unreferenced-snapshots"
+    )]
+    pub example: Vec<String>,
     /// Disable force-passing of snapshot tests
     #[structopt(long)]
     pub no_force_pass: bool,
@@ -116,12 +241,21 @@ pub struct TestCommand {
     /// Build artifacts in release mode, with optimizations
     #[structopt(long)]
     pub release: bool,
+    /// Build for the target triple
+    #[structopt(long, value_name = "TRIPLE")]
+    pub target: Option<String>,
+    /// Directory for all generated artifacts
+    #[structopt(long, value_name = "DIRECTORY", parse(from_os_str))]
+    pub target_dir: Option<PathBuf>,
     /// Activate all available features
     #[structopt(long)]
     pub all_features: bool,
     /// Do not activate the `default` feature
     #[structopt(long)]
     pub no_default_features: bool,
+    /// Compile the tests but do not run them or process snapshots.
+    #[structopt(long, conflicts_with_all = &["review", "accept", "watch"])]
+    pub no_run: bool,
     /// Follow up with review.
     #[structopt(long)]
     pub review: bool,
@@ -140,6 +274,35 @@ pub struct TestCommand {
     /// Delete unreferenced snapshots after the test run.
     #[structopt(long)]
     pub delete_unreferenced_snapshots: bool,
+    /// Print the snapshots referenced by the test run.
+    #[structopt(long)]
+    pub list_snapshots_after: bool,
+    /// Only run the tests that left pending snapshots in the last run with
+    /// this flag.  The tests are recorded in .insta/last-failed.
+    #[structopt(long, conflicts_with = "delete-unreferenced-snapshots")]
+    pub last_failed: bool,
+    /// Re-run the tests and review whenever a file in the workspace changes.
+    #[structopt(long)]
+    pub watch: bool,
+    /// Print GitHub Actions annotations for pending snapshots.
+    ///
+    /// This is enabled automatically when running in GitHub Actions.
+    #[structopt(long)]
+    pub github_annotations: bool,
+    /// Fail snapshots that only match because of redactions or filters.
+    #[structopt(long)]
+    pub require_full_match: bool,
+    /// Write a JSON file describing the test run.
+    #[structopt(long, value_name = "PATH", parse(from_os_str))]
+    pub emit_metadata: Option<PathBuf>,
+    /// Format of the pending snapshots summary: human, json
+    #[structopt(
+        long,
+        value_name = "FORMAT",
+        possible_values = &["human", "json"],
+        conflicts_with_all = &["review", "accept", "watch"]
+    )]
+    pub format: Option<String>,
     /// Options passed to cargo test
     // Sets raw to true so that `--` is required
     #[structopt(name = "cargo_options", raw(true))]
@@ -154,6 +317,190 @@ pub struct PendingSnapshotsCommand {
     /// Changes the output from human readable to JSON.
     #[structopt(long)]
     pub as_json: bool,
+    /// Only prints the number of pending snapshots and fails if there are any.
+    #[structopt(long, conflicts_with = "as-json")]
+    pub count: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ShowCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// The snapshot file to show (`.snap` or `.snap.new`).
+    #[structopt(value_name = "PATH", parse(from_os_str))]
+    pub path: PathBuf,
+    /// Changes the output from human readable to JSON.
+    #[structopt(long)]
+    pub as_json: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct DoctorCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ExportCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// The archive to write.
+    #[structopt(value_name = "ARCHIVE", parse(from_os_str))]
+    pub archive: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ImportCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// The archive to read.
+    #[structopt(value_name = "ARCHIVE", parse(from_os_str))]
+    pub archive: PathBuf,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct RestoreCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// Do not print to stdout.
+    #[structopt(short = "q", long)]
+    pub quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct MvCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// Limits the operation to the snapshots of a package in the workspace.
+    #[structopt(short = "p", long, conflicts_with = "all")]
+    pub package: Option<String>,
+    /// The current name of the snapshot.
+    pub old_name: String,
+    /// The new name of the snapshot.
+    pub new_name: String,
+    /// Do not print to stdout.
+    #[structopt(short = "q", long)]
+    pub quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct CompletionsCommand {
+    /// The shell to print the completion script for.
+    #[structopt(possible_values = &Shell::variants(), case_insensitive = true)]
+    pub shell: Shell,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct QueueCommand {
+    #[structopt(subcommand)]
+    pub action: QueueAction,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum QueueAction {
+    /// Forget skipped snapshots so that they are reviewed again.
+    #[structopt(name = "clear")]
+    Clear(QueueClearCommand),
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct QueueClearCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// Do not print to stdout.
+    #[structopt(short = "q", long)]
+    pub quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ExternalizeCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// The inline snapshot assertion to move.
+    #[structopt(long, value_name = "FILE:LINE")]
+    pub snapshot: String,
+    /// The name of the snapshot.  Defaults to the name of the test.
+    #[structopt(long)]
+    pub name: Option<String>,
+    /// Do not print to stdout.
+    #[structopt(short = "q", long)]
+    pub quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct InlineCommand {
+    #[structopt(flatten)]
+    pub target_args: TargetArgs,
+    /// The named snapshot assertion to inline.
+    #[structopt(long, value_name = "FILE:LINE")]
+    pub snapshot: String,
+    /// Do not print to stdout.
+    #[structopt(short = "q", long)]
+    pub quiet: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct SplitCommand {
+    /// The snapshot file to split.
+    #[structopt(long, value_name = "FILE", parse(from_os_str))]
+    pub snapshot: PathBuf,
+    /// The delimiter that separates the parts of the snapshot.
+    #[structopt(long, value_name = "DELIMITER", allow_hyphen_values = true)]
+    pub on: String,
+    /// Suffixes for the new snapshots.  Defaults to 1, 2, 3 and so on.
+    ///
+    /// Either one suffix per non-empty part or one per part including the
+    /// empty parts that are dropped.
+    #[structopt(long, value_name = "SUFFIX", multiple = true)]
+    pub suffixes: Vec<String>,
+    /// Remove the original snapshot after splitting.
+    #[structopt(long)]
+    pub remove: bool,
+}
+
+#[derive(StructOpt, Debug)]
+#[structopt(rename_all = "kebab-case")]
+pub struct ConvertCommand {
+    /// The snapshot file to convert.
+    #[structopt(long, value_name = "FILE", parse(from_os_str))]
+    pub snapshot: PathBuf,
+    /// The format to convert to: json or yaml.
+    #[structopt(long, value_name = "FORMAT")]
+    pub to: String,
+}
+
+/// The choice made for a snapshot in the review prompt.
+enum ReviewChoice {
+    /// Accept, reject or skip the snapshot.
+    Decide(Operation),
+    /// Accept the snapshot and all further additive-only snapshots.
+    AcceptAdditive,
+    /// Accept only the changes within a range of lines of the new snapshot.
+    AcceptLines(usize, usize),
+    /// Accept or reject the snapshot and all remaining undecided snapshots.
+    DecideRemaining(Operation),
+    /// Move to the next snapshot without deciding.
+    Next,
+    /// Move to the previous snapshot without deciding.
+    Previous,
+    /// Revert the last decision and show its snapshot again.
+    Undo,
+    /// Open the snapshot in the editor and show it again.
+    Edit,
+    /// Keep the decisions so far and leave the rest pending.
+    Stop,
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -167,7 +514,12 @@ fn query_snapshot(
     i: usize,
     n: usize,
     snapshot_file: Option<&Path>,
-) -> Result<Operation, Box<dyn Error>> {
+    decision: Option<Operation>,
+    skip_count: usize,
+    neighbours: (Option<&str>, Option<&str>),
+    can_undo: bool,
+    diff_style: DiffStyle,
+) -> Result<ReviewChoice, Box<dyn Error>> {
     term.clear_screen()?;
     println!(
         "{}{}{}",
@@ -181,8 +533,49 @@ fn query_snapshot(
     } else {
         println!();
     }
+    if n > 1 {
+        let (prev, next) = neighbours;
+        println!(
+            "{} {}  {} {}",
+            style("prev:").dim(),
+            style(prev.unwrap_or("-")).cyan(),
+            style("next:").dim(),
+            style(next.unwrap_or("-")).cyan(),
+        );
+    }
+
+    print_snapshot_diff(workspace_root, new, old, snapshot_file, line, diff_style);
 
-    print_snapshot_diff(workspace_root, new, old, snapshot_file, line);
+    let kind = classify_snapshot_diff(new, old);
+    println!(
+        "Changes: {}",
+        match kind {
+            DiffKind::Additive => style("additive only").green(),
+            DiffKind::Removed => style("removals only").red(),
+            DiffKind::Mixed => style("additions and removals").yellow(),
+        }
+    );
+    if let Some(decision) = decision {
+        println!(
+            "Decision: {}",
+            match decision {
+                Operation::Accept => style("accepted").green(),
+                Operation::Reject => style("rejected").red(),
+                Operation::Skip => style("skipped").yellow(),
+            }
+        );
+    }
+    if skip_count > 0 {
+        println!(
+            "Skipped: {}",
+            style(format!(
+                "in {} earlier review{}",
+                skip_count,
+                if skip_count == 1 { "" } else { "s" }
+            ))
+            .yellow()
+        );
+    }
 
     println!();
     println!(
@@ -190,6 +583,22 @@ fn query_snapshot(
         style("a").green().bold(),
         style("keep the new snapshot").dim()
     );
+    if kind.is_additive() {
+        println!(
+            "  {} accept+  {}",
+            style("A").green().bold(),
+            style("keep this and all further additive-only snapshots").dim()
+        );
+    }
+    // only snapshot files can be merged with the old snapshot
+    let can_accept_lines = snapshot_file.is_some() && old.is_some();
+    if can_accept_lines {
+        println!(
+            "  {} partial  {}",
+            style("p").green().bold(),
+            style("keep the changes within a range of lines only").dim()
+        );
+    }
     println!(
         "  {} reject   {}",
         style("r").red().bold(),
@@ -200,12 +609,449 @@ fn query_snapshot(
         style("s").yellow().bold(),
         style("keep both for now").dim()
     );
+    println!(
+        "  {} accept*  {}",
+        style("+").green().bold(),
+        style("keep this and all remaining new snapshots").dim()
+    );
+    println!(
+        "  {} reject*  {}",
+        style("-").red().bold(),
+        style("keep the old snapshots for this and all remaining").dim()
+    );
+    println!(
+        "  {} next     {}",
+        style("j").cyan().bold(),
+        style("move to the next snapshot without deciding").dim()
+    );
+    println!(
+        "  {} previous {}",
+        style("k").cyan().bold(),
+        style("move to the previous snapshot without deciding").dim()
+    );
+    if can_undo {
+        println!(
+            "  {} undo     {}",
+            style("u").cyan().bold(),
+            style("revert the last decision").dim()
+        );
+    }
+    #[cfg(feature = "clipboard")]
+    println!(
+        "  {} copy     {}",
+        style("c").cyan().bold(),
+        style("copy the new snapshot to the clipboard").dim()
+    );
+    println!(
+        "  {} edit     {}",
+        style("e").cyan().bold(),
+        style(if line.is_some() {
+            "open the source of the snapshot in $EDITOR"
+        } else {
+            "open the new snapshot in $EDITOR"
+        })
+        .dim()
+    );
+    println!(
+        "  {} stop     {}",
+        style("q").cyan().bold(),
+        style("save decisions so far and leave the rest pending").dim()
+    );
+
+    loop {
+        match term.read_key()? {
+            Key::Char('a') | Key::Enter => break Ok(ReviewChoice::Decide(Operation::Accept)),
+            Key::Char('A') if kind.is_additive() => break Ok(ReviewChoice::AcceptAdditive),
+            Key::Char('p') if can_accept_lines => {
+                term.write_str("Lines of the new snapshot to keep (e.g. 10-20): ")?;
+                match parse_line_range(&term.read_line()?) {
+                    Some((start, end)) => break Ok(ReviewChoice::AcceptLines(start, end)),
+                    None => println!(
+                        "{}: expected a line or a range of lines",
+                        style("error").red().bold()
+                    ),
+                }
+            }
+            Key::Char('r') | Key::Escape => break Ok(ReviewChoice::Decide(Operation::Reject)),
+            Key::Char('s') | Key::Char(' ') => break Ok(ReviewChoice::Decide(Operation::Skip)),
+            Key::Char('+') if confirm_remaining(term, "Accept", n - i + 1)? => {
+                break Ok(ReviewChoice::DecideRemaining(Operation::Accept))
+            }
+            Key::Char('-') if confirm_remaining(term, "Reject", n - i + 1)? => {
+                break Ok(ReviewChoice::DecideRemaining(Operation::Reject))
+            }
+            Key::Char('j') => break Ok(ReviewChoice::Next),
+            Key::Char('k') => break Ok(ReviewChoice::Previous),
+            Key::Char('u') if can_undo => break Ok(ReviewChoice::Undo),
+            Key::Char('e') => break Ok(ReviewChoice::Edit),
+            // copying does not change the screen so the review just goes on
+            #[cfg(feature = "clipboard")]
+            Key::Char('c') => match copy_to_clipboard(new.contents_str()) {
+                Ok(()) => println!(
+                    "{}: new snapshot copied to the clipboard",
+                    style("info").bold()
+                ),
+                Err(err) => println!(
+                    "{} could not copy to the clipboard: {}",
+                    style("warning:").bold().yellow(),
+                    err
+                ),
+            },
+            Key::Char('q') => break Ok(ReviewChoice::Stop),
+            _ => {}
+        }
+    }
+}
+
+#[cfg(feature = "clipboard")]
+thread_local!(static CLIPBOARD: std::cell::RefCell<Option<arboard::Clipboard>> = Default::default());
+
+/// Copies text to the system clipboard.
+///
+/// The clipboard is kept open for the rest of the review as on some systems
+/// the copied text is lost once it is closed.
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> Result<(), Box<dyn Error>> {
+    CLIPBOARD.with(|clipboard| {
+        let mut clipboard = clipboard.borrow_mut();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+        clipboard.as_mut().unwrap().set_text(text.to_string())?;
+        Ok(())
+    })
+}
+
+/// Asks the user to confirm a decision on all remaining snapshots.
+fn confirm_remaining(term: &Term, action: &str, count: usize) -> Result<bool, Box<dyn Error>> {
+    println!(
+        "{} this and all remaining undecided snapshots ({} left)? [y/n]",
+        action, count
+    );
+    loop {
+        match term.read_key()? {
+            Key::Char('y') | Key::Enter => break Ok(true),
+            Key::Char('n') | Key::Escape => break Ok(false),
+            _ => {}
+        }
+    }
+}
+
+/// Parses a `START-END` range of lines.  A single line is also accepted.
+fn parse_line_range(value: &str) -> Option<(usize, usize)> {
+    let mut iter = value.trim().splitn(2, '-');
+    let start = iter.next()?.trim().parse().ok()?;
+    let end = match iter.next() {
+        Some(end) => end.trim().parse().ok()?,
+        None => start,
+    };
+    if start <= end {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+/// The state of a snapshot before a decision in the review.
+struct UndoState {
+    idx: usize,
+    decided: bool,
+    op: Operation,
+    /// The pending snapshot if it was replaced by a partial accept.
+    new: Option<Snapshot>,
+    /// Whether the replaced pending snapshot was already changed before.
+    edited: bool,
+}
+
+/// Returns the summary of a snapshot by its position in the review.
+fn review_summary(
+    snapshot_containers: &mut [(SnapshotContainer, Option<&Package>)],
+    (container_idx, snapshot_idx): (usize, usize),
+) -> String {
+    snapshot_containers[container_idx]
+        .0
+        .iter_snapshots()
+        .nth(snapshot_idx)
+        .unwrap()
+        .summary()
+}
+
+/// Returns the decision for a snapshot by its position in the review.
+fn review_op(
+    snapshot_containers: &mut [(SnapshotContainer, Option<&Package>)],
+    (container_idx, snapshot_idx): (usize, usize),
+) -> Operation {
+    snapshot_containers[container_idx]
+        .0
+        .iter_snapshots()
+        .nth(snapshot_idx)
+        .unwrap()
+        .op
+}
+
+/// Asks the user to decide on the given snapshots.
+///
+/// The snapshots are given as pairs of container and snapshot indexes
+/// together with how often they were skipped in earlier reviews and the
+/// decisions are stored in the snapshots.  The user can move between
+/// the snapshots without deciding, undecided snapshots are skipped.
+/// Decisions can be undone until the snapshots are committed.
+/// Returns which of the snapshots were decided on.
+fn review_snapshots(
+    workspace_root: &Path,
+    term: &Term,
+    snapshot_containers: &mut [(SnapshotContainer, Option<&Package>)],
+    to_review: &[(usize, usize)],
+    skip_counts: &[usize],
+    max_snapshots: Option<usize>,
+    diff_style: DiffStyle,
+) -> Result<Vec<bool>, Box<dyn Error>> {
+    let mut decided = vec![false; to_review.len()];
+    let mut history = vec![];
+    let mut accept_additive = false;
+    let mut idx = 0;
+
+    while idx < to_review.len() {
+        // the remaining snapshots stay pending for the next review
+        if let Some(max_snapshots) = max_snapshots {
+            let count = (0..to_review.len())
+                .filter(|&idx| {
+                    decided[idx]
+                        && match review_op(snapshot_containers, to_review[idx]) {
+                            Operation::Accept | Operation::Reject => true,
+                            Operation::Skip => false,
+                        }
+                })
+                .count();
+            if count >= max_snapshots {
+                break;
+            }
+        }
+
+        // the neighbours in the review order help to orient in large reviews
+        let prev = idx
+            .checked_sub(1)
+            .map(|prev_idx| review_summary(snapshot_containers, to_review[prev_idx]));
+        let next = to_review
+            .get(idx + 1)
+            .map(|&pos| review_summary(snapshot_containers, pos));
+        let (container_idx, snapshot_idx) = to_review[idx];
+        let (snapshot_container, package) = &mut snapshot_containers[container_idx];
+        let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
+        let pending_file = snapshot_container.pending_file().to_path_buf();
+        let target_file = snapshot_container.target_file().to_path_buf();
+        let snapshot_ref = snapshot_container
+            .iter_snapshots()
+            .nth(snapshot_idx)
+            .unwrap();
+
+        if accept_additive
+            && !decided[idx]
+            && classify_snapshot_diff(&snapshot_ref.new, snapshot_ref.old.as_ref()).is_additive()
+        {
+            history.push(UndoState {
+                idx,
+                decided: false,
+                op: snapshot_ref.op,
+                new: None,
+                edited: snapshot_ref.edited,
+            });
+            snapshot_ref.op = Operation::Accept;
+            decided[idx] = true;
+            idx += 1;
+            continue;
+        }
+
+        let choice = query_snapshot(
+            workspace_root,
+            term,
+            &snapshot_ref.new,
+            snapshot_ref.old.as_ref(),
+            *package,
+            snapshot_ref.line,
+            idx + 1,
+            to_review.len(),
+            snapshot_file.as_ref().map(|x| x.as_path()),
+            if decided[idx] {
+                Some(snapshot_ref.op)
+            } else {
+                None
+            },
+            skip_counts[idx],
+            (prev.as_deref(), next.as_deref()),
+            !history.is_empty(),
+            diff_style,
+        )?;
+
+        // remember the state before a decision so that it can be undone
+        match choice {
+            ReviewChoice::Decide(_) | ReviewChoice::AcceptAdditive => history.push(UndoState {
+                idx,
+                decided: decided[idx],
+                op: snapshot_ref.op,
+                new: None,
+                edited: snapshot_ref.edited,
+            }),
+            ReviewChoice::AcceptLines(..) => history.push(UndoState {
+                idx,
+                decided: decided[idx],
+                op: snapshot_ref.op,
+                new: Some(snapshot_ref.new.clone()),
+                edited: snapshot_ref.edited,
+            }),
+            _ => {}
+        }
+
+        match choice {
+            ReviewChoice::Decide(op) => {
+                snapshot_ref.op = op;
+                decided[idx] = true;
+                idx += 1;
+            }
+            ReviewChoice::AcceptAdditive => {
+                accept_additive = true;
+                snapshot_ref.op = Operation::Accept;
+                decided[idx] = true;
+                idx += 1;
+            }
+            ReviewChoice::AcceptLines(start, end) => {
+                // the merged snapshot replaces the pending snapshot in memory
+                // and is only written when the decisions are committed
+                let merged = merge_line_range(
+                    snapshot_ref.old.as_ref().map_or("", |x| x.contents_str()),
+                    snapshot_ref.new.contents_str(),
+                    start..=end,
+                );
+                snapshot_ref.new = snapshot_with_contents(&snapshot_ref.new, &merged);
+                snapshot_ref.edited = true;
+                snapshot_ref.op = Operation::Accept;
+                decided[idx] = true;
+                idx += 1;
+            }
+            ReviewChoice::DecideRemaining(op) => {
+                snapshot_ref.op = op;
+                decided[idx] = true;
+                // this ends the review so there is nothing to undo
+                for idx in idx + 1..to_review.len() {
+                    if !decided[idx] {
+                        let (container_idx, snapshot_idx) = to_review[idx];
+                        snapshot_containers[container_idx]
+                            .0
+                            .iter_snapshots()
+                            .nth(snapshot_idx)
+                            .unwrap()
+                            .op = op;
+                        decided[idx] = true;
+                    }
+                }
+                break;
+            }
+            ReviewChoice::Next => idx += 1,
+            ReviewChoice::Previous => idx = idx.saturating_sub(1),
+            ReviewChoice::Undo => {
+                let state = history.pop().unwrap();
+                let (container_idx, snapshot_idx) = to_review[state.idx];
+                let snapshot_ref = snapshot_containers[container_idx]
+                    .0
+                    .iter_snapshots()
+                    .nth(snapshot_idx)
+                    .unwrap();
+                // a partial accept replaced the pending snapshot
+                if let Some(new) = state.new {
+                    snapshot_ref.new = new;
+                    snapshot_ref.edited = state.edited;
+                }
+                snapshot_ref.op = state.op;
+                decided[state.idx] = state.decided;
+                // otherwise an undone additive snapshot is accepted again
+                accept_additive = false;
+                idx = state.idx;
+            }
+            ReviewChoice::Edit => {
+                // inline snapshots are edited at their assertion, others in
+                // the pending file which is read again afterwards
+                let rv = if let Some(line) = snapshot_ref.line {
+                    open_in_editor(&target_file, Some(line))
+                        .and_then(|()| snapshot_container.reload())
+                } else {
+                    // the file is edited with the changes from the review
+                    let rv = if snapshot_ref.edited {
+                        save_snapshot_with_contents(
+                            &snapshot_ref.new,
+                            snapshot_ref.new.contents_str(),
+                            &pending_file,
+                        )
+                    } else {
+                        Ok(())
+                    };
+                    rv.and_then(|()| open_in_editor(&pending_file, None))
+                        .and_then(|()| {
+                            snapshot_ref.new = Snapshot::from_file(&pending_file)?;
+                            snapshot_ref.edited = false;
+                            Ok(())
+                        })
+                };
+                // keep the review going as the screen is redrawn afterwards
+                if let Err(err) = rv {
+                    println!("{}: {}", style("error").red().bold(), err);
+                    println!("press any key to continue");
+                    term.read_key()?;
+                }
+            }
+            ReviewChoice::Stop => break,
+        }
+    }
+
+    Ok(decided)
+}
+
+/// Opens a file in the editor of `$VISUAL` or `$EDITOR` and waits for it.
+///
+/// The line is passed as `+LINE` before the path which most editors
+/// understand.
+fn open_in_editor(path: &Path, line: Option<u32>) -> Result<(), Box<dyn Error>> {
+    let editor = env::var("VISUAL")
+        .or_else(|_| env::var("EDITOR"))
+        .map_err(|_| err_msg("neither $VISUAL nor $EDITOR is set"))?;
+    let mut args = editor.split_whitespace();
+    let mut proc = process::Command::new(
+        args.next()
+            .ok_or_else(|| err_msg("neither $VISUAL nor $EDITOR is set"))?,
+    );
+    proc.args(args);
+    if let Some(line) = line {
+        proc.arg(format!("+{}", line));
+    }
+    proc.arg(path);
+    let status = proc.status()?;
+    if !status.success() {
+        return Err(err_msg(format!("editor exited with {}", status)));
+    }
+    Ok(())
+}
+
+/// Prints a patch and asks the user whether it should be applied.
+fn confirm_patch(term: &Term, patch: &str) -> Result<bool, Box<dyn Error>> {
+    for line in patch.lines() {
+        if line.starts_with("+++") || line.starts_with("---") {
+            println!("{}", style(line).bold());
+        } else if line.starts_with('+') {
+            println!("{}", style(line).green());
+        } else if line.starts_with('-') {
+            println!("{}", style(line).red());
+        } else if line.starts_with("@@") {
+            println!("{}", style(line).cyan());
+        } else {
+            println!("{}", line);
+        }
+    }
+    println!();
+    println!("Write these changes? [y/n]");
 
     loop {
         match term.read_key()? {
-            Key::Char('a') | Key::Enter => break Ok(Operation::Accept),
-            Key::Char('r') | Key::Escape => break Ok(Operation::Reject),
-            Key::Char('s') | Key::Char(' ') => break Ok(Operation::Skip),
+            Key::Char('y') | Key::Enter => break Ok(true),
+            Key::Char('n') | Key::Escape => break Ok(false),
             _ => {}
         }
     }
@@ -214,7 +1060,12 @@ fn query_snapshot(
 fn handle_color(color: &str) -> Result<(), Box<dyn Error>> {
     match color {
         "always" => set_colors_enabled(true),
-        "auto" => {}
+        // an explicit `--color` wins over `NO_COLOR`
+        "auto" => {
+            if env::var("NO_COLOR").map_or(false, |x| !x.is_empty()) {
+                set_colors_enabled(false);
+            }
+        }
         "never" => set_colors_enabled(false),
         color => return Err(err_msg(format!("invalid value for --color: {}", color))),
     }
@@ -226,6 +1077,8 @@ fn handle_color(color: &str) -> Result<(), Box<dyn Error>> {
 enum SnapshotKey<'a> {
     NamedSnapshot {
         path: &'a Path,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<&'a str>,
     },
     InlineSnapshot {
         path: &'a Path,
@@ -234,14 +1087,54 @@ enum SnapshotKey<'a> {
         old_snapshot: Option<&'a str>,
         new_snapshot: &'a str,
         expression: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        description: Option<&'a str>,
     },
 }
 
+/// Describes a pending snapshot for `cargo insta test --format json`.
+#[derive(Serialize, Debug)]
+struct PendingSnapshotInfo<'a> {
+    #[serde(flatten)]
+    key: SnapshotKey<'a>,
+    package: Option<&'a str>,
+    has_old: bool,
+}
+
+/// Describes a snapshot for `cargo insta show --as-json`.
+#[derive(Serialize, Debug)]
+struct SnapshotInfo<'a> {
+    path: &'a Path,
+    module_name: &'a str,
+    snapshot_name: Option<&'a str>,
+    metadata: &'a insta::MetaData,
+    contents: &'a str,
+}
+
+/// Describes a test run for `--emit-metadata`.
+#[derive(Serialize, Debug)]
+struct TestRunMetadata<'a> {
+    insta_version: &'a str,
+    update_mode: &'a str,
+    force_pass: bool,
+    force_update_snapshots: bool,
+    follow_up: Option<&'a str>,
+    packages: Vec<&'a str>,
+    features: Option<&'a str>,
+    all_features: bool,
+    no_default_features: bool,
+    success: bool,
+    snapshots_created: usize,
+    snapshots_changed: usize,
+}
+
 struct LocationInfo<'a> {
     workspace_root: PathBuf,
     packages: Option<Vec<Package>>,
     exts: Vec<&'a str>,
     no_ignore: bool,
+    /// The target directory if it is known.
+    target_dir: Option<PathBuf>,
 }
 
 fn handle_target_args(target_args: &TargetArgs) -> Result<LocationInfo<'_>, Box<dyn Error>> {
@@ -281,6 +1174,7 @@ fn handle_target_args(target_args: &TargetArgs) -> Result<LocationInfo<'_>, Box<
             packages: None,
             exts,
             no_ignore: target_args.no_ignore,
+            target_dir: None,
         })
     } else {
         let metadata = get_package_metadata(manifest_path.as_ref().map(|x| x.as_path()))?;
@@ -290,6 +1184,7 @@ fn handle_target_args(target_args: &TargetArgs) -> Result<LocationInfo<'_>, Box<
             packages: Some(packages),
             exts,
             no_ignore: target_args.no_ignore,
+            target_dir: Some(metadata.target_directory().to_path_buf()),
         })
     }
 }
@@ -318,30 +1213,317 @@ fn load_snapshot_containers<'a>(
     Ok(snapshot_containers)
 }
 
-fn process_snapshots(cmd: ProcessCommand, op: Option<Operation>) -> Result<(), Box<dyn Error>> {
-    let term = Term::stdout();
+/// Parses a duration such as `30s`, `15m`, `12h` or `7d`.
+fn parse_duration(value: &str) -> Result<Duration, Box<dyn Error>> {
+    let invalid = || {
+        err_msg(format!(
+            "invalid duration '{}', expected a number followed by s, m, h or d",
+            value
+        ))
+    };
+    let split = value.len() - value.chars().last().map_or(0, |x| x.len_utf8());
+    let multiplier = match &value[split..] {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let amount: u64 = value[..split].parse().map_err(|_| invalid())?;
+    amount
+        .checked_mul(multiplier)
+        .map(Duration::from_secs)
+        .ok_or_else(invalid)
+}
 
-    let loc = handle_target_args(&cmd.target_args)?;
-    let mut snapshot_containers = load_snapshot_containers(&loc)?;
+fn parse_diff_style(value: &str) -> Result<DiffStyle, Box<dyn Error>> {
+    DiffStyle::from_name(value).ok_or_else(|| {
+        err_msg(format!(
+            "invalid diff style '{}', expected unified or side-by-side",
+            value
+        ))
+    })
+}
 
-    let snapshot_count = snapshot_containers.iter().map(|x| x.0.len()).sum();
+/// Parses a `FILE:LINE` location.  The line is optional.
+fn parse_location(value: &str) -> Result<(PathBuf, Option<u32>), Box<dyn Error>> {
+    let (path, line) = match value.rfind(':') {
+        Some(idx) => match value[idx + 1..].parse() {
+            Ok(line) => (&value[..idx], Some(line)),
+            Err(_) => (value, None),
+        },
+        None => (value, None),
+    };
+    let path =
+        fs::canonicalize(path).map_err(|_| err_msg(format!("could not find file {}", path)))?;
+    Ok((path, line))
+}
 
-    if snapshot_count == 0 {
-        if !cmd.quiet {
-            println!("{}: no snapshots to review", style("done").bold());
-        }
-        return Ok(());
+/// Checks if a pending snapshot is at a location.
+///
+/// Snapshots in separate files do not have a line so only the file is
+/// compared for them.
+fn is_at_location(
+    target_file: &Path,
+    line: Option<u32>,
+    location: &(PathBuf, Option<u32>),
+) -> bool {
+    let same_file = fs::canonicalize(target_file).map_or(false, |x| x == location.0);
+    match (line, location.1) {
+        (Some(line), Some(expected)) => same_file && line == expected,
+        _ => same_file,
     }
+}
 
-    let mut accepted = vec![];
-    let mut rejected = vec![];
+/// Returns the files changed between the merge base with `base` and `HEAD`.
+///
+/// The paths are canonicalized so that they can be compared with the paths
+/// of snapshot containers.
+fn git_changed_files(root: &Path, base: &str) -> Result<HashSet<PathBuf>, Box<dyn Error>> {
+    let git = |args: &[&str]| -> Result<String, Box<dyn Error>> {
+        let output = process::Command::new("git")
+            .args(args)
+            .current_dir(root)
+            .output()
+            .map_err(|err| err_msg(format!("failed to run git: {}", err)))?;
+        if !output.status.success() {
+            return Err(err_msg(format!(
+                "git {} failed: {}",
+                args.join(" "),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+
+    let toplevel = git(&["rev-parse", "--show-toplevel"])
+        .map_err(|_| err_msg("--changed can only be used in a git repository"))?;
+    let merge_base = git(&["merge-base", base, "HEAD"])?;
+    let range = format!("{}..HEAD", merge_base);
+    Ok(git(&["diff", "--name-only", &range])?
+        .lines()
+        .filter_map(|line| fs::canonicalize(Path::new(&toplevel).join(line)).ok())
+        .collect())
+}
+
+/// Checks if the target file or the source of a pending snapshot changed.
+fn is_changed(
+    snapshot_container: &mut SnapshotContainer,
+    workspace_root: &Path,
+    changed: &HashSet<PathBuf>,
+) -> bool {
+    let is_changed = |path: &Path| fs::canonicalize(path).map_or(false, |x| changed.contains(&x));
+    if is_changed(snapshot_container.target_file()) {
+        return true;
+    }
+    snapshot_container.iter_snapshots().any(|snapshot_ref| {
+        snapshot_ref
+            .new
+            .metadata()
+            .source()
+            .map_or(false, |source| is_changed(&workspace_root.join(source)))
+    })
+}
+
+/// Returns the key of a pending snapshot in the review queue.
+fn queue_key(workspace_root: &Path, target_file: &Path, snapshot_ref: &PendingSnapshot) -> String {
+    let path = target_file
+        .strip_prefix(workspace_root)
+        .unwrap_or(target_file);
+    match (snapshot_ref.line, snapshot_ref.sub_index) {
+        (Some(line), 0) => format!("{}:{}", path.display(), line),
+        (Some(line), sub_index) => format!("{}:{}#{}", path.display(), line, sub_index + 1),
+        (None, _) => format!("{}", path.display()),
+    }
+}
+
+/// Accepts a snapshot piped in on stdin into a snapshot file.
+///
+/// The snapshot is written as pending snapshot next to the snapshot file and
+/// accepted like any other pending snapshot.  Snapshots with a malformed
+/// metadata header are rejected.
+fn accept_from_stdin(path: &Path, quiet: bool) -> Result<(), Box<dyn Error>> {
+    if path.extension().and_then(|x| x.to_str()) != Some("snap") {
+        return Err(err_msg(format!("{} is not a .snap file", path.display())));
+    }
+    let mut contents = String::new();
+    io::stdin().read_to_string(&mut contents)?;
+    if !contents.starts_with("---") {
+        return Err(err_msg("snapshot on stdin has no metadata header"));
+    }
+
+    let snapshot = snapshot_from_str(&contents, path)
+        .map_err(|err| err_msg(format!("invalid snapshot on stdin: {}", err)))?;
+    let mut snapshot_container = SnapshotContainer::from_snapshot(snapshot, path.to_path_buf())?;
+    review_container(&mut snapshot_container, |_| Ok(Operation::Accept))?;
+
+    if !quiet {
+        println!(
+            "{} {}",
+            style("accepted").green(),
+            snapshot_container.target_file().display()
+        );
+    }
+    Ok(())
+}
+
+fn process_snapshots(cmd: ProcessCommand, op: Option<Operation>) -> Result<(), Box<dyn Error>> {
+    let term = Term::stdout();
+    let diff_style = cmd.diff_style.unwrap_or_else(get_diff_style);
+
+    // a package is looked up among all packages of the workspace
+    let mut target_args = cmd.target_args.clone();
+    target_args.all |= cmd.package.is_some();
+    let mut loc = handle_target_args(&target_args)?;
+    if let Some(ref package) = cmd.package {
+        let packages = loc
+            .packages
+            .as_mut()
+            .ok_or_else(|| err_msg("--package cannot be used without a cargo workspace"))?;
+        packages.retain(|x| x.name() == package);
+        if packages.is_empty() {
+            return Err(err_msg(format!(
+                "no package named {} in the workspace",
+                package
+            )));
+        }
+    }
+    if let Some(ref path) = cmd.stdin {
+        return match op {
+            Some(Operation::Accept) => accept_from_stdin(path, cmd.quiet),
+            _ => Err(err_msg("--stdin can only be used with cargo insta accept")),
+        };
+    }
+    let mut snapshot_containers = load_snapshot_containers(&loc)?;
+    if let Some(ref base) = cmd.changed {
+        let changed = git_changed_files(
+            &loc.workspace_root,
+            base.as_ref().map_or("origin/HEAD", |x| x.as_str()),
+        )?;
+        snapshot_containers = snapshot_containers
+            .into_iter()
+            .filter_map(|(mut snapshot_container, package)| {
+                if is_changed(&mut snapshot_container, &loc.workspace_root, &changed) {
+                    Some((snapshot_container, package))
+                } else {
+                    None
+                }
+            })
+            .collect();
+    }
+
+    let snapshot_count: usize = snapshot_containers.iter().map(|x| x.0.len()).sum();
+
+    if snapshot_count == 0 {
+        if !cmd.quiet {
+            println!("{}: no snapshots to review", style("done").bold());
+        }
+        return Ok(());
+    }
+
+    let goto = match cmd.goto {
+        Some(ref value) => Some(parse_location(value)?),
+        None => None,
+    };
+    let filter = match cmd.filter {
+        Some(ref value) => Some(
+            Regex::new(value)
+                .map_err(|err| err_msg(format!("invalid regex for --filter: {}", err)))?,
+        ),
+        None => None,
+    };
+    let interactive_filter = match cmd.interactive_filter {
+        Some(ref value) => match op {
+            Some(Operation::Accept) => Some(Regex::new(value)?),
+            _ => {
+                return Err(err_msg(
+                    "--interactive-filter can only be used with cargo insta accept",
+                ))
+            }
+        },
+        None => None,
+    };
+    if let Some(ref goto) = goto {
+        let found = snapshot_containers
+            .iter_mut()
+            .any(|(snapshot_container, _)| {
+                let target_file = snapshot_container.target_file().to_path_buf();
+                snapshot_container
+                    .iter_snapshots()
+                    .any(|snapshot_ref| is_at_location(&target_file, snapshot_ref.line, goto))
+            });
+        if !found {
+            return Err(err_msg(format!(
+                "no pending snapshot at {}",
+                cmd.goto.as_ref().unwrap()
+            )));
+        }
+    }
+
+    let mut accepted = vec![];
+    let mut rejected = vec![];
     let mut skipped = vec![];
-    let mut num = 0;
+    let mut frozen = vec![];
+    let mut existing = vec![];
+    let mut queued = vec![];
+    let mut reached = goto.is_none();
+    let mut backup = Backup::new(&loc.workspace_root);
+    let mut queue = ReviewQueue::load(&loc.workspace_root)?;
+    let mut unreviewed = 0;
 
-    for (snapshot_container, package) in snapshot_containers.iter_mut() {
+    // snapshots that need a decision from the user are reviewed after all
+    // other snapshots were decided so that the review can go back and forth.
+    let interactive = op.is_none() || interactive_filter.is_some();
+    let mut to_review = vec![];
+    let mut skip_counts = vec![];
+
+    for (container_idx, (snapshot_container, _)) in snapshot_containers.iter_mut().enumerate() {
         let target_file = snapshot_container.target_file().to_path_buf();
-        let snapshot_file = snapshot_container.snapshot_file().map(|x| x.to_path_buf());
-        for snapshot_ref in snapshot_container.iter_snapshots() {
+        // all pending snapshots of a container are written into the same file
+        let pending_age = fs::metadata(snapshot_container.pending_file())
+            .and_then(|x| x.modified())
+            .ok()
+            .and_then(|x| x.elapsed().ok());
+        let mut snapshot_idx = 0;
+        let decide = |snapshot_ref: &PendingSnapshot| -> Result<Operation, Box<dyn Error>> {
+            snapshot_idx += 1;
+
+            // frozen snapshots must never be updated
+            if snapshot_ref
+                .old
+                .as_ref()
+                .map_or(false, |x| x.metadata().frozen())
+            {
+                frozen.push(snapshot_ref.summary());
+                return Ok(Operation::Skip);
+            }
+
+            // snapshots before the requested location stay pending
+            if !reached {
+                if goto.as_ref().map_or(false, |goto| {
+                    is_at_location(&target_file, snapshot_ref.line, goto)
+                }) {
+                    reached = true;
+                } else {
+                    skipped.push(snapshot_ref.summary());
+                    return Ok(Operation::Skip);
+                }
+            }
+
+            // fresh snapshots might still be written by a running test
+            if let Some(older_than) = cmd.older_than {
+                if pending_age.map_or(true, |age| age <= older_than) {
+                    skipped.push(snapshot_ref.summary());
+                    return Ok(Operation::Skip);
+                }
+            }
+
+            // changes to existing snapshots are left for a later review
+            if cmd.new_only && snapshot_ref.old.is_some() {
+                existing.push(snapshot_ref.summary());
+                return Ok(Operation::Skip);
+            }
+
             // if a filter is provided, check if the snapshot reference is included
             if let Some(ref filter) = cmd.snapshot_filter {
                 let key = if let Some(line) = snapshot_ref.line {
@@ -351,50 +1533,156 @@ fn process_snapshots(cmd: ProcessCommand, op: Option<Operation>) -> Result<(), B
                 };
                 if !filter.contains(&key) {
                     skipped.push(snapshot_ref.summary());
-                    continue;
+                    return Ok(Operation::Skip);
+                }
+            }
+
+            if let Some(ref filter) = filter {
+                let rel_path = target_file
+                    .strip_prefix(&loc.workspace_root)
+                    .unwrap_or(&target_file);
+                let matches = snapshot_ref
+                    .new
+                    .snapshot_name()
+                    .map_or(false, |name| filter.is_match(name))
+                    || filter.is_match(&rel_path.to_string_lossy());
+                if !matches {
+                    skipped.push(snapshot_ref.summary());
+                    return Ok(Operation::Skip);
+                }
+            }
+
+            // if a name filter is provided, check if the snapshot name matches
+            if let Some(ref names) = cmd.snapshot_name {
+                let matches = snapshot_ref.new.snapshot_name().map_or(false, |name| {
+                    names.iter().any(|x| {
+                        if cmd.exact {
+                            name == x
+                        } else {
+                            name.contains(x.as_str())
+                        }
+                    })
+                });
+                if !matches {
+                    skipped.push(snapshot_ref.summary());
+                    return Ok(Operation::Skip);
                 }
             }
 
-            num += 1;
+            // snapshots skipped in an earlier review are only shown again
+            // once their contents change
+            let key = queue_key(&loc.workspace_root, &target_file, snapshot_ref);
+            if op.is_none() && queue.is_skipped(&key, &snapshot_ref.new.contents().hash()) {
+                queued.push(snapshot_ref.summary());
+                return Ok(Operation::Skip);
+            }
+
+            // snapshots matching the interactive filter are reviewed
+            let matches_filter = interactive_filter
+                .as_ref()
+                .map_or(false, |x| x.is_match(snapshot_ref.new.contents_str()));
             let op = match op {
-                Some(op) => op,
-                None => query_snapshot(
-                    &loc.workspace_root,
-                    &term,
-                    &snapshot_ref.new,
-                    snapshot_ref.old.as_ref(),
-                    *package,
-                    snapshot_ref.line,
-                    num,
-                    snapshot_count,
-                    snapshot_file.as_ref().map(|x| x.as_path()),
-                )?,
+                Some(op) if !matches_filter => op,
+                _ => {
+                    to_review.push((container_idx, snapshot_idx - 1));
+                    skip_counts.push(queue.skip_count(&key));
+                    return Ok(Operation::Skip);
+                }
             };
             match op {
-                Operation::Accept => {
-                    snapshot_ref.op = Operation::Accept;
-                    accepted.push(snapshot_ref.summary());
-                }
-                Operation::Reject => {
-                    snapshot_ref.op = Operation::Reject;
-                    rejected.push(snapshot_ref.summary());
-                }
-                Operation::Skip => {
-                    skipped.push(snapshot_ref.summary());
+                Operation::Accept => accepted.push(snapshot_ref.summary()),
+                Operation::Reject => rejected.push(snapshot_ref.summary()),
+                Operation::Skip => skipped.push(snapshot_ref.summary()),
+            }
+            if let Operation::Accept | Operation::Reject = op {
+                queue.remove(&key);
+            }
+            Ok(op)
+        };
+        // with confirmation or review all decisions are committed together
+        // at the end
+        if cmd.confirm || interactive {
+            decide_snapshots(snapshot_container, decide)?;
+        } else if cmd.backup {
+            decide_snapshots(snapshot_container, decide)?;
+            snapshot_container.backup(&mut backup)?;
+            snapshot_container.commit()?;
+        } else {
+            review_container(snapshot_container, decide)?;
+        }
+    }
+
+    if !to_review.is_empty() {
+        let decided = review_snapshots(
+            &loc.workspace_root,
+            &term,
+            &mut snapshot_containers,
+            &to_review,
+            &skip_counts,
+            cmd.max_snapshots,
+            diff_style,
+        )?;
+        unreviewed = decided.iter().filter(|x| !**x).count();
+        for (&(container_idx, snapshot_idx), decided) in to_review.iter().zip(decided) {
+            let snapshot_container = &mut snapshot_containers[container_idx].0;
+            let target_file = snapshot_container.target_file().to_path_buf();
+            let snapshot_ref = snapshot_container
+                .iter_snapshots()
+                .nth(snapshot_idx)
+                .unwrap();
+            let key = queue_key(&loc.workspace_root, &target_file, snapshot_ref);
+            match snapshot_ref.op {
+                Operation::Accept => accepted.push(snapshot_ref.summary()),
+                Operation::Reject => rejected.push(snapshot_ref.summary()),
+                Operation::Skip => skipped.push(snapshot_ref.summary()),
+            }
+            // only explicit skips are remembered for the next review
+            match snapshot_ref.op {
+                Operation::Skip if decided && op.is_none() => {
+                    queue.skip(key, snapshot_ref.new.contents().hash())
                 }
+                Operation::Skip => {}
+                _ => queue.remove(&key),
             }
         }
-        snapshot_container.commit()?;
     }
 
-    if op.is_none() {
+    if op.is_none() || !to_review.is_empty() {
         term.clear_screen()?;
     }
 
+    if cmd.confirm {
+        let mut patch = String::new();
+        for (snapshot_container, _) in snapshot_containers.iter() {
+            patch.push_str(&snapshot_container.accepted_patch()?);
+        }
+        if !patch.is_empty() && !confirm_patch(&term, &patch)? {
+            println!("{}: no changes were written", style("aborted").bold());
+            return Ok(());
+        }
+    }
+    if cmd.confirm || interactive {
+        for (snapshot_container, _) in snapshot_containers.iter_mut() {
+            if cmd.backup {
+                snapshot_container.backup(&mut backup)?;
+            }
+            snapshot_container.commit()?;
+        }
+    }
+    queue.save()?;
+
     if !cmd.quiet {
         println!("{}", style("insta review finished").bold());
         if !accepted.is_empty() {
-            println!("{}:", style("accepted").green());
+            println!(
+                "{}:",
+                style(if cmd.new_only {
+                    "accepted new"
+                } else {
+                    "accepted"
+                })
+                .green()
+            );
             for item in accepted {
                 println!("  {}", item);
             }
@@ -411,12 +1699,415 @@ fn process_snapshots(cmd: ProcessCommand, op: Option<Operation>) -> Result<(), B
                 println!("  {}", item);
             }
         }
+        if !frozen.is_empty() {
+            println!("{}:", style("skipped frozen").cyan());
+            for item in frozen {
+                println!("  {}", item);
+            }
+        }
+        if !existing.is_empty() {
+            println!("{}:", style("skipped existing").yellow());
+            for item in existing {
+                println!("  {}", item);
+            }
+        }
+        if !queued.is_empty() {
+            println!(
+                "{}: (use `cargo insta queue clear` to review them again)",
+                style("skipped before").yellow()
+            );
+            for item in queued {
+                println!("  {}", item);
+            }
+        }
+        if unreviewed > 0 && cmd.max_snapshots.is_some() {
+            println!(
+                "{}: {} snapshot(s) left for the next review",
+                style("remaining").yellow(),
+                unreviewed
+            );
+        }
+        if backup.len() > 0 {
+            println!(
+                "{}: {} file(s), use `cargo insta restore` to undo",
+                style("backed up").cyan(),
+                backup.len()
+            );
+        }
     }
 
     Ok(())
 }
 
+fn export_cmd(cmd: ExportCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let mut entries = vec![];
+    for (snapshot_container, _) in load_snapshot_containers(&loc)? {
+        let pending_file = snapshot_container.pending_file();
+        let data_file = data_file_path(pending_file);
+        let binary_file = binary_file_path(pending_file);
+        for path in Some(pending_file).into_iter().chain(
+            // externalized contents and bytes of binary pending snapshots
+            vec![data_file.as_path(), binary_file.as_path()]
+                .into_iter()
+                .filter(|x| x.is_file()),
+        ) {
+            let rel_path = path.strip_prefix(&loc.workspace_root).map_err(|_| {
+                err_msg(format!(
+                    "pending snapshot outside of workspace: {}",
+                    path.display()
+                ))
+            })?;
+            entries.push(Entry {
+                path: rel_path
+                    .to_str()
+                    .ok_or_else(|| err_msg("non utf-8 path"))?
+                    .replace('\\', "/"),
+                contents: fs::read(path)?,
+            });
+        }
+    }
+    write_archive(&cmd.archive, &entries)?;
+    println!(
+        "{}: exported {} file(s) with pending snapshots to {}",
+        style("done").bold(),
+        entries.len(),
+        cmd.archive.display()
+    );
+    Ok(())
+}
+
+/// Checks that a path does not leave the workspace through a symlink.
+fn is_inside(workspace_root: &Path, path: &Path) -> bool {
+    path.ancestors()
+        .find(|x| x.exists())
+        .and_then(|x| x.canonicalize().ok())
+        .map_or(false, |x| x.starts_with(workspace_root))
+}
+
+fn import_cmd(cmd: ImportCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let workspace_root = loc.workspace_root.canonicalize()?;
+    let entries = read_archive(&cmd.archive)?;
+
+    // validate everything before writing so that a bad archive does not
+    // leave a partial import behind.
+    let mut files = vec![];
+    for entry in entries {
+        let rel_path = Path::new(&entry.path);
+        let fname = rel_path.file_name().and_then(|x| x.to_str()).unwrap_or("");
+        let is_pending = fname.ends_with(".snap.new")
+            || fname.ends_with(".snap.new.data")
+            || fname.ends_with(".snap.new.bin")
+            || (fname.starts_with('.') && fname.ends_with(".pending-snap"));
+        let is_relative = rel_path.components().all(|x| match x {
+            Component::Normal(_) | Component::CurDir => true,
+            _ => false,
+        });
+        let path = workspace_root.join(rel_path);
+        if !is_pending || !is_relative || !is_inside(&workspace_root, &path) {
+            return Err(err_msg(format!(
+                "refusing to import {}: not a pending snapshot in the workspace",
+                entry.path
+            )));
+        }
+        files.push((path, entry.contents));
+    }
+
+    for (path, contents) in &files {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
+    }
+
+    let snapshot_count: usize = load_snapshot_containers(&loc)?
+        .iter()
+        .map(|x| x.0.len())
+        .sum();
+    println!(
+        "{}: imported {} file(s), {} snapshot{} to review",
+        style("done").bold(),
+        files.len(),
+        style(snapshot_count).yellow(),
+        if snapshot_count != 1 { "s" } else { "" }
+    );
+    Ok(())
+}
+
+fn restore_cmd(cmd: RestoreCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let restored = Backup::restore(&loc.workspace_root, |path, copied| {
+        if !cmd.quiet {
+            if copied {
+                println!("{} {}", style("restored").green(), path.display());
+            } else {
+                println!("{} {}", style("removed").red(), path.display());
+            }
+        }
+    })?;
+    if !cmd.quiet {
+        println!("{}: {} file(s) restored", style("done").bold(), restored);
+    }
+    Ok(())
+}
+
+fn queue_clear_cmd(cmd: QueueClearCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let path = ReviewQueue::path_for(&loc.workspace_root);
+    let cleared = path.is_file();
+    ReviewQueue::clear(&path)?;
+    if !cmd.quiet {
+        if cleared {
+            println!("{}: cleared the review queue", style("done").bold());
+        } else {
+            println!("{}: the review queue is empty", style("done").bold());
+        }
+    }
+    Ok(())
+}
+
+/// Finds the snapshot assertion at a `FILE:LINE` location.
+fn open_assertion(
+    value: &str,
+) -> Result<(PathBuf, FilePatcher, SnapshotAssertion), Box<dyn Error>> {
+    let (path, line) = parse_location(value)?;
+    let line = line.ok_or_else(|| err_msg("the snapshot location needs a line"))?;
+    let patcher = FilePatcher::open(&path)?;
+    let assertion = patcher
+        .find_assertion(line as usize)
+        .ok_or_else(|| err_msg(format!("no snapshot assertion found at {}", value)))?;
+    Ok((path, patcher, assertion))
+}
+
+fn externalize_cmd(cmd: ExternalizeCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let (path, mut patcher, assertion) = open_assertion(&cmd.snapshot)?;
+
+    let name = match cmd.name {
+        Some(name) => name,
+        // like for automatic names the test prefix is dropped
+        None => match assertion.function {
+            Some(ref function) if function.starts_with("test_") => function[5..].to_string(),
+            Some(ref function) => function.clone(),
+            None => return Err(err_msg("could not determine a snapshot name, use --name")),
+        },
+    };
+    let mut module_path = loc
+        .packages
+        .iter()
+        .flatten()
+        .find_map(|package| package.module_path_of(&path))
+        .ok_or_else(|| {
+            err_msg(format!(
+                "could not determine the module of {}",
+                path.display()
+            ))
+        })?;
+    for module in assertion.modules.iter() {
+        module_path.push_str("::");
+        module_path.push_str(module);
+    }
+    let module_name = module_path.replace("::", "__");
+
+    let snapshot_file = path.parent().unwrap().join("snapshots").join(format!(
+        "{}__{}.snap",
+        module_name,
+        name.replace('/', "__").replace('\\', "__")
+    ));
+    if snapshot_file.exists() {
+        return Err(err_msg(format!(
+            "{} already exists, pick another name with --name",
+            snapshot_file.display()
+        )));
+    }
+
+    let (contents, expression) = patcher.externalize_snapshot(&assertion, &name)?;
+    let source = path.strip_prefix(&loc.workspace_root).unwrap_or(&path);
+    save_named_snapshot(
+        &snapshot_file,
+        &module_name,
+        &name,
+        source,
+        &expression,
+        contents,
+    )?;
+    patcher.save()?;
+
+    if !cmd.quiet {
+        println!(
+            "{}: moved the snapshot to {}",
+            style("done").bold(),
+            snapshot_file
+                .strip_prefix(&loc.workspace_root)
+                .unwrap_or(&snapshot_file)
+                .display()
+        );
+    }
+    Ok(())
+}
+
+fn inline_cmd(cmd: InlineCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let (path, mut patcher, assertion) = open_assertion(&cmd.snapshot)?;
+    let name = patcher
+        .snapshot_name(&assertion)
+        .ok_or_else(|| err_msg("the assertion is not named with a string literal"))?;
+
+    // the snapshot file is found by the name and the source of the snapshot
+    let mut found = None;
+    for entry in fs::read_dir(path.parent().unwrap().join("snapshots"))? {
+        let snapshot_file = entry?.path();
+        if snapshot_file.extension().and_then(|x| x.to_str()) != Some("snap") {
+            continue;
+        }
+        let snapshot = match Snapshot::from_file(&snapshot_file) {
+            Ok(snapshot) => snapshot,
+            Err(_) => continue,
+        };
+        let same_source = snapshot.metadata().source().map_or(false, |source| {
+            fs::canonicalize(loc.workspace_root.join(source))
+                .ok()
+                .as_ref()
+                == Some(&path)
+        });
+        if same_source && snapshot.snapshot_name() == Some(name.as_str()) {
+            found = Some((snapshot_file, snapshot));
+            break;
+        }
+    }
+    let (snapshot_file, snapshot) =
+        found.ok_or_else(|| err_msg(format!("no snapshot file found for {}", name)))?;
+    if snapshot.metadata().binary_hash().is_some() {
+        return Err(err_msg("binary snapshots cannot be inlined"));
+    }
+
+    patcher.inline_snapshot(&assertion, snapshot.contents())?;
+    patcher.save()?;
+    fs::remove_file(data_file_path(&snapshot_file)).ok();
+    fs::remove_file(&snapshot_file)?;
+
+    if !cmd.quiet {
+        println!(
+            "{}: moved the snapshot into {}",
+            style("done").bold(),
+            path.strip_prefix(&loc.workspace_root)
+                .unwrap_or(&path)
+                .display()
+        );
+    }
+    Ok(())
+}
+
+fn mv_cmd(cmd: MvCommand) -> Result<(), Box<dyn Error>> {
+    let mut target_args = cmd.target_args.clone();
+    target_args.all |= cmd.package.is_some();
+    let mut loc = handle_target_args(&target_args)?;
+    if let Some(ref package) = cmd.package {
+        let packages = loc
+            .packages
+            .as_mut()
+            .ok_or_else(|| err_msg("--package cannot be used without a cargo workspace"))?;
+        packages.retain(|x| x.name() == package);
+        if packages.is_empty() {
+            return Err(err_msg(format!(
+                "no package named {} in the workspace",
+                package
+            )));
+        }
+    }
+
+    // the module path is separated by `__` as well so the file name only
+    // has to end in the name
+    let suffix = format!("__{}", cmd.old_name);
+    let mut found = vec![];
+    for entry in make_workspace_walker(&loc, false).filter_map(|e| e.ok()) {
+        let path = entry.path();
+        if path.extension().and_then(|x| x.to_str()) != Some("snap") {
+            continue;
+        }
+        let stem = path.file_stem().and_then(|x| x.to_str()).unwrap_or("");
+        if stem != cmd.old_name && !stem.ends_with(&suffix) {
+            continue;
+        }
+        if let Ok(snapshot) = Snapshot::from_file(path) {
+            let assertion = find_source_assertion(&loc.workspace_root, &snapshot, &cmd.old_name);
+            found.push((path.to_path_buf(), assertion));
+        }
+    }
+    // a file that only ends in the name is told apart by its assertion
+    if found.len() > 1 {
+        found.retain(|x| x.1.is_some());
+    }
+    let (snapshot_file, assertion) = match found.len() {
+        0 => return Err(err_msg(format!("no snapshot named {} found", cmd.old_name))),
+        1 => found.pop().unwrap(),
+        _ => {
+            return Err(err_msg(format!(
+                "more than one snapshot is named {}: {}",
+                cmd.old_name,
+                found
+                    .iter()
+                    .map(|x| x.0.display().to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )))
+        }
+    };
+
+    let new_snapshot_file = Snapshot::rename(&snapshot_file, &cmd.old_name, &cmd.new_name)?;
+    if !cmd.quiet {
+        println!(
+            "{} {} to {}",
+            style("renamed").green(),
+            snapshot_file.display(),
+            new_snapshot_file.display()
+        );
+    }
+
+    // the assertion can only be updated if it names the snapshot literally
+    match assertion {
+        Some((source, mut patcher, assertion)) => {
+            patcher.rename_snapshot(&assertion, &cmd.new_name)?;
+            patcher.save()?;
+            if !cmd.quiet {
+                println!(
+                    "{} the assertion in {}",
+                    style("updated").green(),
+                    source.display()
+                );
+            }
+        }
+        None => {
+            if !cmd.quiet {
+                println!(
+                    "{} no assertion named {:?} found, update the test by hand",
+                    style("warning:").bold().yellow(),
+                    cmd.old_name
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Finds the assertion that names a snapshot in the source of the snapshot.
+fn find_source_assertion(
+    workspace_root: &Path,
+    snapshot: &Snapshot,
+    name: &str,
+) -> Option<(PathBuf, FilePatcher, SnapshotAssertion)> {
+    let source = workspace_root.join(snapshot.metadata().source()?);
+    let patcher = FilePatcher::open(&source).ok()?;
+    let assertion = patcher.find_named_assertion(name)?;
+    Some((source, patcher, assertion))
+}
+
 fn make_deletion_walker(loc: &LocationInfo) -> Walk {
+    make_workspace_walker(loc, false)
+}
+
+fn make_workspace_walker(loc: &LocationInfo, include_hidden_files: bool) -> Walk {
     let roots: HashSet<_> = match loc.packages {
         Some(ref packages) => packages
             .iter()
@@ -429,25 +2120,41 @@ fn make_deletion_walker(loc: &LocationInfo) -> Walk {
         }
     };
 
+    let target_dir = loc.target_dir.as_ref().and_then(|x| x.canonicalize().ok());
+
     WalkBuilder::new(&loc.workspace_root)
+        .hidden(!include_hidden_files)
         .filter_entry(move |entry| {
             // we only filter down for directories
             if !entry.file_type().map_or(false, |x| x.is_dir()) {
                 return true;
             }
 
+            // hidden files might have been requested but never hidden folders
+            if entry.depth() > 0
+                && entry
+                    .file_name()
+                    .to_str()
+                    .map_or(false, |x| x.starts_with('.'))
+            {
+                return false;
+            }
+
             let canonicalized = match entry.path().canonicalize() {
                 Ok(path) => path,
                 Err(_) => return true,
             };
 
             // We always want to skip target even if it was not excluded by
-            // ignore files.
+            // ignore files.  The same goes for a custom target directory.
             if entry.path().file_name() == Some(&OsStr::new("target"))
                 && roots.contains(canonicalized.parent().unwrap())
             {
                 return false;
             }
+            if target_dir.as_ref() == Some(&canonicalized) {
+                return false;
+            }
 
             // do not enter crates which are not in the list of known roots
             // of the workspace.
@@ -466,13 +2173,223 @@ fn make_deletion_walker(loc: &LocationInfo) -> Walk {
         .build()
 }
 
-fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
+/// Escapes a value for a GitHub Actions workflow command.
+///
+/// Properties like the file name need more escaping than the message.
+fn escape_annotation(value: &str, property: bool) -> String {
+    let mut rv = value
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A");
+    if property {
+        rv = rv.replace(':', "%3A").replace(',', "%2C");
+    }
+    rv
+}
+
+/// Prints an error annotation for every pending snapshot.
+fn print_github_annotations(loc: &LocationInfo) -> Result<(), Box<dyn Error>> {
+    for (mut snapshot_container, _) in load_snapshot_containers(loc)? {
+        // snapshot files are annotated at the assertion in their source
+        let source = if snapshot_container.snapshot_file().is_none() {
+            Some(snapshot_container.target_file().to_path_buf())
+        } else {
+            snapshot_container
+                .iter_snapshots()
+                .next()
+                .and_then(|x| x.new.metadata().source())
+                .map(|source| loc.workspace_root.join(source))
+        };
+        let source = source.unwrap_or_else(|| snapshot_container.target_file().to_path_buf());
+        let patcher = FilePatcher::open(&source).ok();
+        let rel_path = source
+            .strip_prefix(&loc.workspace_root)
+            .unwrap_or(&source)
+            .to_path_buf();
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            let name = snapshot_ref.new.snapshot_name().unwrap_or("<inline>");
+            let snapshot_name = snapshot_ref
+                .new
+                .snapshot_name()
+                .map(snapshot_name_in_source);
+            let message = match snapshot_ref.old {
+                Some(ref old) => {
+                    let (added, removed) =
+                        count_changed_lines(old.contents_str(), snapshot_ref.new.contents_str());
+                    format!(
+                        "snapshot {} does not match (+{} lines, -{} lines)",
+                        name, added, removed
+                    )
+                }
+                None => format!("new snapshot {}", name),
+            };
+            let line = match (snapshot_ref.line, patcher.as_ref(), snapshot_name) {
+                (Some(line), _, _) => Some(line as usize),
+                // snapshot files are either explicitly named or named after
+                // their function
+                (None, Some(patcher), Some(name)) => patcher
+                    .find_named_assertion(name)
+                    .map(|assertion| assertion.line)
+                    .or_else(|| patcher.find_function(&format!("test_{}", name)))
+                    .or_else(|| patcher.find_function(name)),
+                _ => None,
+            };
+            let mut location = format!(
+                "file={}",
+                escape_annotation(&rel_path.display().to_string(), true)
+            );
+            if let Some(line) = line {
+                location.push_str(&format!(",line={}", line));
+            }
+            println!(
+                "::error {}::{}, run `cargo insta review` to review it",
+                location,
+                escape_annotation(&message, false)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Returns the path of the file that records the tests that failed last.
+fn last_failed_path(workspace_root: &Path) -> PathBuf {
+    workspace_root.join(".insta").join("last-failed")
+}
+
+/// Remembers the tests that left pending snapshots behind.
+///
+/// The tests are recorded as test name filters for `cargo test`.  The
+/// function around the assertion is used which is found by its line for
+/// inline snapshots and by the name of explicitly named snapshots in the
+/// source of snapshot files.  Other snapshot files are named after their
+/// function already.
+fn record_last_failed(loc: &LocationInfo) -> Result<(), Box<dyn Error>> {
+    let mut names = BTreeSet::new();
+    for (mut snapshot_container, _) in load_snapshot_containers(loc)? {
+        // snapshot files hold a single snapshot
+        let source = if snapshot_container.snapshot_file().is_none() {
+            Some(snapshot_container.target_file().to_path_buf())
+        } else {
+            snapshot_container
+                .iter_snapshots()
+                .next()
+                .and_then(|x| x.new.metadata().source())
+                .map(|source| loc.workspace_root.join(source))
+        };
+        let patcher = source.and_then(|source| FilePatcher::open(source).ok());
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            let snapshot_name = snapshot_ref
+                .new
+                .snapshot_name()
+                .map(snapshot_name_in_source);
+            let assertion = match (patcher.as_ref(), snapshot_ref.line, snapshot_name) {
+                (Some(patcher), Some(line), _) => patcher.find_assertion(line as usize),
+                (Some(patcher), None, Some(name)) => patcher.find_named_assertion(name),
+                _ => None,
+            };
+            let name = match assertion.and_then(|assertion| assertion.function) {
+                Some(function) => Some(function),
+                None => snapshot_name.map(|name| name.to_string()),
+            };
+            names.extend(name);
+        }
+    }
+
+    let path = last_failed_path(&loc.workspace_root);
+    if names.is_empty() {
+        if path.is_file() {
+            fs::remove_file(&path)?;
+        }
+        return Ok(());
+    }
+    let mut contents = String::new();
+    for name in names {
+        contents.push_str(&name);
+        contents.push('\n');
+    }
+    fs::create_dir_all(path.parent().unwrap())?;
+    fs::write(&path, contents)?;
+    Ok(())
+}
+
+fn test_run(cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
+    if cmd.watch {
+        watch_tests(cmd, color)
+    } else {
+        run_tests(cmd, color)
+    }
+}
+
+/// Runs the tests and reviews the results every time files change.
+///
+/// `cargo test` runs in the same process group so Ctrl-C stops it together
+/// with the watcher.
+fn watch_tests(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
+    if !cmd.accept {
+        cmd.review = true;
+    }
+    let loc = handle_target_args(&cmd.target_args)?;
+    loop {
+        if let Err(err) = run_tests(cmd.clone(), color) {
+            if err.downcast_ref::<QuietExit>().is_none() {
+                println!("{} {}", style("error:").red().bold(), err);
+            }
+        }
+        eprintln!(
+            "{}: watching for changes, press Ctrl-C to stop",
+            style("info").bold()
+        );
+        wait_for_changes(&loc);
+    }
+}
+
+/// Blocks until files in the workspace changed and stopped changing.
+fn wait_for_changes(loc: &LocationInfo) {
+    let interval = Duration::from_millis(500);
+    let initial = workspace_fingerprint(loc);
+    let mut current = initial.clone();
+    loop {
+        thread::sleep(interval);
+        let next = workspace_fingerprint(loc);
+        // wait for one quiet interval so that a save of many files causes
+        // a single run.
+        if next == current && current != initial {
+            return;
+        }
+        current = next;
+    }
+}
+
+/// Records the modification time and size of all files in the workspace.
+///
+/// Ignored, hidden and pending snapshot files are skipped as the latter are
+/// written by the test run itself.
+fn workspace_fingerprint(loc: &LocationInfo) -> BTreeMap<PathBuf, (Option<SystemTime>, u64)> {
+    let mut rv = BTreeMap::new();
+    for entry in make_workspace_walker(loc, false).filter_map(|x| x.ok()) {
+        let fname = entry.file_name().to_string_lossy();
+        if fname.ends_with(".new") || fname.ends_with(".new.data") {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            if metadata.is_file() {
+                rv.insert(
+                    entry.path().to_path_buf(),
+                    (metadata.modified().ok(), metadata.len()),
+                );
+            }
+        }
+    }
+    rv
+}
+
+fn run_tests(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
     let mut proc = process::Command::new(get_cargo());
     proc.arg("test");
 
-    // when unreferenced snapshots should be deleted we need to instruct
-    // insta to dump referenced snapshots somewhere.
-    let snapshot_ref_file = if cmd.delete_unreferenced_snapshots {
+    // when unreferenced snapshots should be deleted or referenced snapshots
+    // listed we need to instruct insta to dump referenced snapshots somewhere.
+    let snapshot_ref_file = if cmd.delete_unreferenced_snapshots || cmd.list_snapshots_after {
         let snapshot_ref_file = env::temp_dir().join(Uuid::new_v4().to_string());
         proc.env("INSTA_SNAPSHOT_REFERENCES_FILE", &snapshot_ref_file);
         Some(snapshot_ref_file)
@@ -507,12 +2424,24 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
     }
 
     if cmd.target_args.all {
-        proc.arg("--all");
+        proc.arg("--workspace");
     }
     if let Some(ref pkg) = cmd.package {
         proc.arg("--package");
         proc.arg(pkg);
     }
+    for name in &cmd.bin {
+        proc.arg("--bin");
+        proc.arg(name);
+    }
+    for name in &cmd.test {
+        proc.arg("--test");
+        proc.arg(name);
+    }
+    for name in &cmd.example {
+        proc.arg("--example");
+        proc.arg(name);
+    }
     if let Some(ref manifest_path) = cmd.target_args.manifest_path {
         proc.arg("--manifest-path");
         proc.arg(manifest_path);
@@ -530,9 +2459,20 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
     if cmd.force_update_snapshots {
         proc.env("INSTA_FORCE_UPDATE_SNAPSHOTS", "1");
     }
+    if cmd.require_full_match {
+        proc.env("INSTA_REQUIRE_FULL_MATCH", "1");
+    }
     if cmd.release {
         proc.arg("--release");
     }
+    if let Some(ref target) = cmd.target {
+        proc.arg("--target");
+        proc.arg(target);
+    }
+    if let Some(ref target_dir) = cmd.target_dir {
+        proc.arg("--target-dir");
+        proc.arg(target_dir);
+    }
     if let Some(n) = cmd.jobs {
         proc.arg(format!("--jobs={}", n));
     }
@@ -548,24 +2488,134 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
     }
     proc.arg("--color");
     proc.arg(color);
-    proc.args(cmd.cargo_options);
+    // insta inside the tests honors `NO_COLOR` too unless colors are forced
+    if color == "always" {
+        proc.env_remove("NO_COLOR");
+    }
+    proc.args(&cmd.cargo_options);
+
+    // only building leaves the snapshots alone
+    if cmd.no_run {
+        proc.arg("--no-run");
+        if !proc.status()?.success() {
+            return Err(QuietExit(1).into());
+        }
+        return Ok(());
+    }
+
     proc.arg("--");
     proc.arg("-q");
 
+    if cmd.last_failed {
+        let loc = handle_target_args(&cmd.target_args)?;
+        let filters = fs::read_to_string(last_failed_path(&loc.workspace_root)).unwrap_or_default();
+        if filters.trim().is_empty() {
+            eprintln!(
+                "{}: no tests left pending snapshots in the last run, running all tests",
+                style("info").bold()
+            );
+        }
+        proc.args(filters.lines());
+    }
+
     if !cmd.keep_pending {
         process_snapshots(
             ProcessCommand {
                 target_args: cmd.target_args.clone(),
+                package: None,
                 snapshot_filter: None,
+                snapshot_name: None,
+                exact: false,
+                filter: None,
+                confirm: false,
+                backup: false,
+                goto: None,
+                interactive_filter: None,
+                max_snapshots: None,
+                older_than: None,
+                new_only: false,
+                changed: None,
+                stdin: None,
+                diff_style: None,
                 quiet: true,
             },
             Some(Operation::Reject),
         )?;
     }
 
-    let status = proc.status()?;
+    // the output of the tests goes to stderr so that stdout only holds the
+    // JSON lines
+    let as_json = cmd.format.as_deref() == Some("json");
+    let status = if as_json {
+        proc.stdout(process::Stdio::piped());
+        let mut child = proc.spawn()?;
+        io::copy(child.stdout.as_mut().unwrap(), &mut io::stderr())?;
+        child.wait()?
+    } else {
+        proc.status()?
+    };
+
+    // failures are only recorded for the next run with --last-failed
+    if cmd.last_failed {
+        if let Err(err) =
+            handle_target_args(&cmd.target_args).and_then(|loc| record_last_failed(&loc))
+        {
+            eprintln!(
+                "{} could not record the failed tests: {}",
+                style("warning:").bold().yellow(),
+                err
+            );
+        }
+    }
+
+    if let Some(ref path) = cmd.emit_metadata {
+        let loc = handle_target_args(&cmd.target_args)?;
+        let mut snapshots_created = 0;
+        let mut snapshots_changed = 0;
+        for (mut snapshot_container, _) in load_snapshot_containers(&loc)? {
+            for snapshot_ref in snapshot_container.iter_snapshots() {
+                if snapshot_ref.old.is_some() {
+                    snapshots_changed += 1;
+                } else {
+                    snapshots_created += 1;
+                }
+            }
+        }
+        let metadata = TestRunMetadata {
+            insta_version: env!("CARGO_PKG_VERSION"),
+            update_mode: if cmd.accept_unseen { "unseen" } else { "new" },
+            force_pass: !cmd.no_force_pass,
+            force_update_snapshots: cmd.force_update_snapshots,
+            follow_up: if cmd.accept {
+                Some("accept")
+            } else if cmd.review {
+                Some("review")
+            } else {
+                None
+            },
+            packages: match loc.packages {
+                Some(ref packages) => packages.iter().map(|x| x.name()).collect(),
+                None => vec![],
+            },
+            features: cmd.features.as_deref(),
+            all_features: cmd.all_features,
+            no_default_features: cmd.no_default_features,
+            success: status.success(),
+            snapshots_created,
+            snapshots_changed,
+        };
+        fs::write(path, serde_json::to_string_pretty(&metadata)?)?;
+    }
+
+    if cmd.github_annotations || env::var("GITHUB_ACTIONS").ok().as_deref() == Some("true") {
+        let loc = handle_target_args(&cmd.target_args)?;
+        print_github_annotations(&loc)?;
+    }
 
     if !status.success() {
+        if as_json {
+            print_pending_snapshots_json(&handle_target_args(&cmd.target_args)?)?;
+        }
         if cmd.review {
             eprintln!(
                 "{} non snapshot tests failed, skipping review",
@@ -580,18 +2630,57 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
         return Err(QuietExit(1).into());
     }
 
-    // delete unreferenced snapshots if we were instructed to do so
     if let Some(ref path) = snapshot_ref_file {
+        // the file is not written if no snapshot was asserted
+        let contents = fs::read_to_string(path).ok();
         let mut files = HashSet::new();
-        for line in fs::read_to_string(path).unwrap().lines() {
+        for line in contents.iter().flat_map(|x| x.lines()) {
             if let Ok(path) = fs::canonicalize(line) {
                 files.insert(path);
             }
         }
 
-        if let Ok(loc) = handle_target_args(&cmd.target_args) {
+        if cmd.list_snapshots_after {
+            let workspace_root = handle_target_args(&cmd.target_args)
+                .ok()
+                .and_then(|loc| fs::canonicalize(loc.workspace_root).ok());
+            let mut referenced = files
+                .iter()
+                .map(|path| {
+                    workspace_root
+                        .as_ref()
+                        .and_then(|root| path.strip_prefix(root).ok())
+                        .unwrap_or(path)
+                })
+                .collect::<Vec<_>>();
+            referenced.sort();
+            if referenced.is_empty() {
+                eprintln!("{}: no snapshots referenced", style("info").bold());
+            } else {
+                eprintln!("{}: referenced snapshots:", style("info").bold());
+                for path in referenced {
+                    eprintln!("  {}", path.display());
+                }
+            }
+        }
+
+        // delete unreferenced snapshots if we were instructed to do so.  If
+        // no snapshot was asserted at all we rather keep all of them.
+        let loc = if cmd.delete_unreferenced_snapshots && contents.is_some() {
+            handle_target_args(&cmd.target_args).ok().map(|mut loc| {
+                if let Some(ref target_dir) = cmd.target_dir {
+                    loc.target_dir = Some(target_dir.clone());
+                }
+                loc
+            })
+        } else {
+            None
+        };
+        if let Some(loc) = loc {
             let mut deleted_any = false;
+            let mut progress = DeletionProgress::new(!as_json);
             for entry in make_deletion_walker(&loc) {
+                progress.tick();
                 let rel_path = match entry {
                     Ok(ref entry) => entry.path(),
                     _ => continue,
@@ -606,15 +2695,20 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
 
                 if let Ok(path) = fs::canonicalize(rel_path) {
                     if !files.contains(&path) {
+                        progress.clear();
                         if !deleted_any {
                             eprintln!("{}: deleted unreferenced snapshots:", style("info").bold());
                             deleted_any = true;
                         }
                         eprintln!("  {}", rel_path.display());
+                        progress.deleted += 1;
+                        fs::remove_file(data_file_path(&path)).ok();
+                        fs::remove_file(binary_file_path(&path)).ok();
                         fs::remove_file(path).ok();
                     }
                 }
             }
+            progress.clear();
             if !deleted_any {
                 eprintln!("{}: no unreferenced snapshots found", style("info").bold());
             }
@@ -627,7 +2721,21 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
         process_snapshots(
             ProcessCommand {
                 target_args: cmd.target_args.clone(),
+                package: None,
                 snapshot_filter: None,
+                snapshot_name: None,
+                exact: false,
+                filter: None,
+                confirm: false,
+                backup: false,
+                goto: None,
+                interactive_filter: None,
+                max_snapshots: None,
+                older_than: None,
+                new_only: false,
+                changed: None,
+                stdin: None,
+                diff_style: None,
                 quiet: false,
             },
             if cmd.accept {
@@ -636,6 +2744,8 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
                 None
             },
         )?
+    } else if as_json {
+        print_pending_snapshots_json(&handle_target_args(&cmd.target_args)?)?;
     } else {
         let loc = handle_target_args(&cmd.target_args)?;
         let snapshot_containers = load_snapshot_containers(&loc)?;
@@ -656,30 +2766,128 @@ fn test_run(mut cmd: TestCommand, color: &str) -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+/// Shows a spinner while unreferenced snapshots are deleted.
+///
+/// Walking a large workspace can take a while so the number of scanned
+/// files and deleted snapshots is updated in place.  Nothing is shown if
+/// stderr is not a terminal.
+struct DeletionProgress {
+    term: Option<Term>,
+    scanned: usize,
+    deleted: usize,
+}
+
+impl DeletionProgress {
+    fn new(enabled: bool) -> DeletionProgress {
+        let term = Term::stderr();
+        DeletionProgress {
+            term: if enabled && term.is_term() {
+                Some(term)
+            } else {
+                None
+            },
+            scanned: 0,
+            deleted: 0,
+        }
+    }
+
+    /// Counts a scanned file and redraws the spinner every 100 files.
+    fn tick(&mut self) {
+        self.scanned += 1;
+        if self.scanned % 100 != 0 {
+            return;
+        }
+        if let Some(ref term) = self.term {
+            let spinner = ['|', '/', '-', '\\'][self.scanned / 100 % 4];
+            term.clear_line().ok();
+            term.write_str(&format!(
+                "{} scanning for unreferenced snapshots: {} files scanned, {} deleted",
+                style(spinner).cyan(),
+                self.scanned,
+                self.deleted
+            ))
+            .ok();
+        }
+    }
+
+    /// Removes the spinner so that other output can be printed.
+    fn clear(&self) {
+        if let Some(ref term) = self.term {
+            term.clear_line().ok();
+        }
+    }
+}
+
+/// Prints a JSON line for every pending snapshot.
+fn print_pending_snapshots_json(loc: &LocationInfo) -> Result<(), Box<dyn Error>> {
+    for (mut snapshot_container, package) in load_snapshot_containers(loc)? {
+        let target_file = snapshot_container.target_file().to_path_buf();
+        let is_inline = snapshot_container.snapshot_file().is_none();
+        for snapshot_ref in snapshot_container.iter_snapshots() {
+            let info = PendingSnapshotInfo {
+                key: make_snapshot_key(&target_file, is_inline, snapshot_ref),
+                package: package.as_ref().map(|x| x.name()),
+                has_old: snapshot_ref.old.is_some(),
+            };
+            println!("{}", serde_json::to_string(&info)?);
+        }
+    }
+    Ok(())
+}
+
+/// Describes a pending snapshot for the JSON output.
+fn make_snapshot_key<'a>(
+    target_file: &'a Path,
+    is_inline: bool,
+    snapshot_ref: &'a PendingSnapshot,
+) -> SnapshotKey<'a> {
+    if is_inline {
+        SnapshotKey::InlineSnapshot {
+            path: target_file,
+            line: snapshot_ref.line.unwrap(),
+            name: snapshot_ref.new.snapshot_name(),
+            old_snapshot: snapshot_ref.old.as_ref().map(|x| x.contents_str()),
+            new_snapshot: snapshot_ref.new.contents_str(),
+            expression: snapshot_ref.new.metadata().expression(),
+            description: snapshot_ref.new.metadata().description(),
+        }
+    } else {
+        SnapshotKey::NamedSnapshot {
+            path: target_file,
+            description: snapshot_ref.new.metadata().description(),
+        }
+    }
+}
+
 fn pending_snapshots_cmd(cmd: PendingSnapshotsCommand) -> Result<(), Box<dyn Error>> {
     let loc = handle_target_args(&cmd.target_args)?;
     let mut snapshot_containers = load_snapshot_containers(&loc)?;
 
+    if cmd.count {
+        let count = snapshot_containers.iter().map(|x| x.0.len()).sum::<usize>();
+        println!("{}", count);
+        if count > 0 {
+            return Err(QuietExit(1).into());
+        }
+        return Ok(());
+    }
+
     for (snapshot_container, _package) in snapshot_containers.iter_mut() {
         let target_file = snapshot_container.target_file().to_path_buf();
         let is_inline = snapshot_container.snapshot_file().is_none();
         for snapshot_ref in snapshot_container.iter_snapshots() {
             if cmd.as_json {
-                let info = if is_inline {
-                    SnapshotKey::InlineSnapshot {
-                        path: &target_file,
-                        line: snapshot_ref.line.unwrap(),
-                        name: snapshot_ref.new.snapshot_name(),
-                        old_snapshot: snapshot_ref.old.as_ref().map(|x| x.contents_str()),
-                        new_snapshot: snapshot_ref.new.contents_str(),
-                        expression: snapshot_ref.new.metadata().expression(),
-                    }
-                } else {
-                    SnapshotKey::NamedSnapshot { path: &target_file }
-                };
+                let info = make_snapshot_key(&target_file, is_inline, snapshot_ref);
                 println!("{}", serde_json::to_string(&info).unwrap());
             } else {
-                if is_inline {
+                if is_inline && snapshot_ref.sub_index > 0 {
+                    println!(
+                        "{}:{}#{}",
+                        target_file.display(),
+                        snapshot_ref.line.unwrap(),
+                        snapshot_ref.sub_index + 1
+                    );
+                } else if is_inline {
                     println!("{}:{}", target_file.display(), snapshot_ref.line.unwrap());
                 } else {
                     println!("{}", target_file.display());
@@ -691,6 +2899,279 @@ fn pending_snapshots_cmd(cmd: PendingSnapshotsCommand) -> Result<(), Box<dyn Err
     Ok(())
 }
 
+fn show_cmd(cmd: ShowCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let snapshot = Snapshot::from_file(&cmd.path)?;
+
+    if cmd.as_json {
+        let info = SnapshotInfo {
+            path: &cmd.path,
+            module_name: snapshot.module_name(),
+            snapshot_name: snapshot.snapshot_name(),
+            metadata: snapshot.metadata(),
+            contents: snapshot.contents_str(),
+        };
+        println!("{}", serde_json::to_string(&info)?);
+    } else {
+        let path = env::current_dir()?.join(&cmd.path);
+        let path = path.strip_prefix(&loc.workspace_root).unwrap_or(&path);
+        print_snapshot(&loc.workspace_root, &snapshot, Some(path));
+    }
+
+    Ok(())
+}
+
+/// Returns the name a snapshot is likely referred to by in the source.
+///
+/// This strips nested module prefixes as well as the suffixes that insta
+/// adds for suffixed (`@foo`) and repeated (`-2`) assertions.
+fn snapshot_name_in_source(name: &str) -> &str {
+    let name = name.rsplit("__").next().unwrap_or(name);
+    let name = name.split('@').next().unwrap_or(name);
+    match name.rfind('-') {
+        Some(idx) if name[idx + 1..].bytes().all(|x| x.is_ascii_digit()) => &name[..idx],
+        _ => name,
+    }
+}
+
+fn doctor_cmd(cmd: DoctorCommand) -> Result<(), Box<dyn Error>> {
+    let loc = handle_target_args(&cmd.target_args)?;
+    let mut problems = vec![];
+    let mut seen_names: HashMap<(String, String), PathBuf> = HashMap::new();
+    let mut source_cache: HashMap<PathBuf, Option<String>> = HashMap::new();
+
+    for entry in make_workspace_walker(&loc, true) {
+        let path = match entry {
+            Ok(ref entry) => entry.path(),
+            _ => continue,
+        };
+        let fname = match path.file_name().and_then(|x| x.to_str()) {
+            Some(fname) => fname,
+            None => continue,
+        };
+        if !path.is_file() {
+            continue;
+        }
+        let rel_path = path.strip_prefix(&loc.workspace_root).unwrap_or(path);
+
+        if fname.starts_with('.') && fname.ends_with(".pending-snap") {
+            let mut target_path = path.to_path_buf();
+            target_path.set_file_name(&fname[1..fname.len() - 13]);
+            let rel_target_path = target_path
+                .strip_prefix(&loc.workspace_root)
+                .unwrap_or(&target_path)
+                .to_path_buf();
+            let patcher = FilePatcher::open(&target_path).ok();
+            let batch = match PendingInlineSnapshot::load_batch(path) {
+                Ok(batch) => batch,
+                Err(err) => {
+                    problems.push(format!("{}: cannot be read: {}", rel_path.display(), err));
+                    continue;
+                }
+            };
+            for pending in batch {
+                let new = match pending.new {
+                    Some(ref new) => new,
+                    None => continue,
+                };
+                if let Some(source) = new.metadata().source() {
+                    if Path::new(source) != rel_target_path {
+                        problems.push(format!(
+                            "{}:{}: inline snapshot refers to source {}",
+                            rel_target_path.display(),
+                            pending.line,
+                            source
+                        ));
+                    }
+                }
+                if !patcher
+                    .as_ref()
+                    .map_or(false, |x| x.has_snapshot_macro(pending.line as usize))
+                {
+                    problems.push(format!(
+                        "{}:{}: no inline snapshot assertion found at this location",
+                        rel_target_path.display(),
+                        pending.line
+                    ));
+                }
+            }
+        } else if loc.exts.iter().any(|ext| {
+            fname.len() > ext.len() + 1
+                && fname.ends_with(ext)
+                && fname[..fname.len() - ext.len()].ends_with('.')
+        }) {
+            let snapshot = match Snapshot::from_file(path) {
+                Ok(snapshot) => snapshot,
+                Err(err) => {
+                    problems.push(format!("{}: cannot be read: {}", rel_path.display(), err));
+                    continue;
+                }
+            };
+            let (source, name) = match (snapshot.metadata().source(), snapshot.snapshot_name()) {
+                (Some(source), Some(name)) => (source, name),
+                // without a source or name there is nothing we can check
+                _ => continue,
+            };
+
+            if let Some(other) = seen_names.insert(
+                (source.to_string(), name.to_string()),
+                rel_path.to_path_buf(),
+            ) {
+                problems.push(format!(
+                    "{}: duplicate snapshot name {} (also in {})",
+                    rel_path.display(),
+                    name,
+                    other.display()
+                ));
+            }
+
+            let source_path = loc.workspace_root.join(source);
+            let contents = source_cache
+                .entry(source_path.clone())
+                .or_insert_with(|| fs::read_to_string(&source_path).ok());
+            match contents {
+                None => problems.push(format!(
+                    "{}: source file {} does not exist",
+                    rel_path.display(),
+                    source
+                )),
+                Some(contents) => {
+                    if !contents.contains(snapshot_name_in_source(name)) {
+                        problems.push(format!(
+                            "{}: no test for snapshot {} found in {}",
+                            rel_path.display(),
+                            name,
+                            source
+                        ));
+                    }
+                }
+            }
+        }
+    }
+
+    if problems.is_empty() {
+        println!("{}: no problems found", style("done").bold());
+        return Ok(());
+    }
+
+    for problem in &problems {
+        println!("{}: {}", style("problem").red().bold(), problem);
+    }
+    println!(
+        "{}: found {} problem{}",
+        style("done").bold(),
+        style(problems.len()).yellow(),
+        if problems.len() != 1 { "s" } else { "" }
+    );
+    Err(QuietExit(1).into())
+}
+
+fn split_cmd(cmd: SplitCommand) -> Result<(), Box<dyn Error>> {
+    let snapshot = Snapshot::from_file(&cmd.snapshot)?;
+    let stem = cmd
+        .snapshot
+        .file_name()
+        .and_then(|x| x.to_str())
+        .filter(|x| x.ends_with(".snap"))
+        .map(|x| &x[..x.len() - 5])
+        .ok_or_else(|| err_msg("snapshot file needs to have a .snap extension"))?;
+
+    // empty parts are dropped but suffixes can also be given for them so
+    // that they line up with the delimiters in the snapshot
+    let all_parts = snapshot
+        .contents_str()
+        .split(cmd.on.as_str())
+        .map(|x| x.trim_matches(|c| c == '\r' || c == '\n'))
+        .collect::<Vec<_>>();
+    let parts = all_parts
+        .iter()
+        .enumerate()
+        .filter(|(_, part)| !part.is_empty())
+        .collect::<Vec<_>>();
+    if parts.len() < 2 {
+        return Err(err_msg(format!(
+            "delimiter {:?} does not split the snapshot",
+            cmd.on
+        )));
+    }
+    if !cmd.suffixes.is_empty()
+        && cmd.suffixes.len() != parts.len()
+        && cmd.suffixes.len() != all_parts.len()
+    {
+        return Err(err_msg(format!(
+            "snapshot splits into {} parts but {} suffixes were given",
+            parts.len(),
+            cmd.suffixes.len()
+        )));
+    }
+
+    // check all new files before any is written
+    let mut new_snapshots = vec![];
+    for (idx, &(raw_idx, part)) in parts.iter().enumerate() {
+        let suffix = if cmd.suffixes.is_empty() {
+            (idx + 1).to_string()
+        } else if cmd.suffixes.len() == all_parts.len() {
+            cmd.suffixes[raw_idx].clone()
+        } else {
+            cmd.suffixes[idx].clone()
+        };
+        let path = cmd
+            .snapshot
+            .with_file_name(format!("{}@{}.snap", stem, suffix));
+        if path.exists() {
+            return Err(err_msg(format!("{} already exists", path.display())));
+        }
+        if new_snapshots.iter().any(|(other, _)| other == &path) {
+            return Err(err_msg(format!("suffix {} is used twice", suffix)));
+        }
+        new_snapshots.push((path, *part));
+    }
+
+    for (path, part) in &new_snapshots {
+        save_snapshot_with_contents(&snapshot, part, path)?;
+        println!("{} {}", style("created").green(), path.display());
+    }
+
+    if cmd.remove {
+        fs::remove_file(&cmd.snapshot)?;
+        println!("{} {}", style("removed").red(), cmd.snapshot.display());
+    }
+
+    Ok(())
+}
+
+fn convert_cmd(cmd: ConvertCommand) -> Result<(), Box<dyn Error>> {
+    let format = insta::SnapshotFormat::from_name(&cmd.to)
+        .ok_or_else(|| err_msg(format!("unknown snapshot format '{}'", cmd.to)))?;
+    let snapshot = Snapshot::from_file(&cmd.snapshot)?;
+    let current_format = snapshot.format();
+    if current_format == format {
+        println!(
+            "{}: {} is already {}",
+            style("info").bold(),
+            cmd.snapshot.display(),
+            format.name()
+        );
+        return Ok(());
+    }
+    let converted = snapshot.convert_to(format).map_err(|err| {
+        err_msg(format!(
+            "cannot convert {}: {}",
+            cmd.snapshot.display(),
+            err
+        ))
+    })?;
+    save_snapshot_with_contents(&converted, converted.contents_str(), &cmd.snapshot)?;
+    println!(
+        "{} {} from {} to {}",
+        style("converted").green(),
+        cmd.snapshot.display(),
+        current_format.name(),
+        format.name()
+    );
+    Ok(())
+}
+
 pub fn run() -> Result<(), Box<dyn Error>> {
     // chop off cargo
     let mut args: Vec<_> = env::args_os().collect();
@@ -702,11 +3183,33 @@ pub fn run() -> Result<(), Box<dyn Error>> {
 
     let color = opts.color.as_ref().map(|x| x.as_str()).unwrap_or("auto");
     handle_color(color)?;
+    // the width is passed on through the environment so that it also
+    // applies to the output of tests run by `cargo insta test`.
+    if let Some(max_width) = opts.max_width {
+        env::set_var("INSTA_MAX_WIDTH", max_width.to_string());
+    }
     match opts.command {
         Command::Review(cmd) => process_snapshots(cmd, None),
         Command::Accept(cmd) => process_snapshots(cmd, Some(Operation::Accept)),
         Command::Reject(cmd) => process_snapshots(cmd, Some(Operation::Reject)),
         Command::Test(cmd) => test_run(cmd, color),
         Command::PendingSnapshots(cmd) => pending_snapshots_cmd(cmd),
+        Command::Show(cmd) => show_cmd(cmd),
+        Command::Doctor(cmd) => doctor_cmd(cmd),
+        Command::Split(cmd) => split_cmd(cmd),
+        Command::Convert(cmd) => convert_cmd(cmd),
+        Command::Restore(cmd) => restore_cmd(cmd),
+        Command::Export(cmd) => export_cmd(cmd),
+        Command::Import(cmd) => import_cmd(cmd),
+        Command::Queue(cmd) => match cmd.action {
+            QueueAction::Clear(cmd) => queue_clear_cmd(cmd),
+        },
+        Command::Externalize(cmd) => externalize_cmd(cmd),
+        Command::Inline(cmd) => inline_cmd(cmd),
+        Command::Mv(cmd) => mv_cmd(cmd),
+        Command::Completions(cmd) => {
+            Opts::clap().gen_completions_to("cargo-insta", cmd.shell, &mut io::stdout());
+            Ok(())
+        }
     }
 }
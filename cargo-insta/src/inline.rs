@@ -5,17 +5,72 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 
 use insta::_cargo_insta_support::SnapshotContents;
-use proc_macro2::TokenTree;
+use proc_macro2::{LineColumn, TokenTree};
 use syn;
 use syn::spanned::Spanned;
 
-#[derive(Debug)]
+use crate::utils::err_msg;
+
+fn scan_for_path_start(tokens: &[TokenTree], pos: usize) -> usize {
+    let mut rev_tokens = tokens[..=pos].iter().rev();
+    let mut start = rev_tokens.next().unwrap();
+    loop {
+        if let Some(TokenTree::Punct(ref punct)) = rev_tokens.next() {
+            if punct.as_char() == ':' {
+                if let Some(TokenTree::Punct(ref punct)) = rev_tokens.next() {
+                    if punct.as_char() == ':' {
+                        if let Some(ident @ TokenTree::Ident(_)) = rev_tokens.next() {
+                            start = ident;
+                            continue;
+                        }
+                    }
+                }
+            }
+        }
+        break;
+    }
+    start.span().start().column
+}
+
+fn is_punct(token: Option<&TokenTree>, c: char) -> bool {
+    match token {
+        Some(TokenTree::Punct(punct)) => punct.as_char() == c,
+        _ => false,
+    }
+}
+
+fn parse_string_literal(token: &TokenTree) -> Option<String> {
+    match token {
+        TokenTree::Literal(lit) => syn::parse_str::<syn::LitStr>(&lit.to_string())
+            .ok()
+            .map(|x| x.value()),
+        _ => None,
+    }
+}
+
+/// A snapshot assertion macro in a source file.
+#[derive(Debug, Default)]
+pub struct SnapshotAssertion {
+    /// The column the macro starts at.
+    indentation: usize,
+    /// The arguments of the macro.
+    args: Vec<TokenTree>,
+    /// The inline modules the macro is in.
+    pub modules: Vec<String>,
+    /// The name of the function the macro is in.
+    pub function: Option<String>,
+    /// The line the macro starts at.
+    pub line: usize,
+}
+
+#[derive(Debug, Clone)]
 pub struct InlineSnapshot {
     start: (usize, usize),
     end: (usize, usize),
     indentation: usize,
 }
 
+#[derive(Clone)]
 pub struct FilePatcher {
     filename: PathBuf,
     lines: Vec<String>,
@@ -48,10 +103,18 @@ impl FilePatcher {
 
     pub fn save(&self) -> Result<(), Box<dyn Error>> {
         let mut f = fs::File::create(&self.filename)?;
+        f.write_all(self.contents().as_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the contents of the file as they would be saved.
+    pub fn contents(&self) -> String {
+        let mut rv = String::new();
         for line in &self.lines {
-            writeln!(&mut f, "{}", line)?;
+            rv.push_str(line);
+            rv.push('\n');
         }
-        Ok(())
+        rv
     }
 
     pub fn add_snapshot_macro(&mut self, line: usize) {
@@ -67,6 +130,15 @@ impl FilePatcher {
         }
     }
 
+    pub fn has_snapshot_macro(&self, line: usize) -> bool {
+        self.find_snapshot_macro(line).is_some()
+    }
+
+    /// Returns the number of snapshot macros added.
+    pub fn macro_count(&self) -> usize {
+        self.inline_snapshots.len()
+    }
+
     pub fn get_new_line(&self, id: usize) -> usize {
         self.inline_snapshots[id].start.0 + 1
     }
@@ -84,6 +156,8 @@ impl FilePatcher {
             .skip(inline.end.1)
             .collect();
 
+        let suffix_len = suffix.chars().count();
+
         // replace lines
         let snapshot_line_contents =
             vec![prefix, snapshot.to_inline(inline.indentation), suffix].join("");
@@ -93,40 +167,245 @@ impl FilePatcher {
             snapshot_line_contents.lines().map(|l| l.to_string()),
         );
 
-        // update other snapshot locations
+        // update the end of the snapshot so that it can be set again
         let old_lines_count = inline.end.0 - inline.start.0 + 1;
-        let line_count_diff =
-            (snapshot_line_contents.lines().count() as isize) - (old_lines_count as isize);
-        for inl in &mut self.inline_snapshots[id..] {
+        let new_lines_count = snapshot_line_contents.lines().count();
+        inline.end.0 = inline.start.0 + new_lines_count - 1;
+        inline.end.1 = self.lines[inline.end.0].chars().count() - suffix_len;
+
+        // update other snapshot locations
+        let line_count_diff = (new_lines_count as isize) - (old_lines_count as isize);
+        for inl in &mut self.inline_snapshots[id + 1..] {
             inl.start.0 = ((inl.start.0 as isize) + line_count_diff) as usize;
             inl.end.0 = ((inl.end.0 as isize) + line_count_diff) as usize;
         }
     }
 
-    fn find_snapshot_macro(&self, line: usize) -> Option<InlineSnapshot> {
-        struct Visitor(usize, Option<InlineSnapshot>);
+    /// Returns the text between two positions.
+    fn text_between(&self, start: LineColumn, end: LineColumn) -> String {
+        let mut rv = String::new();
+        for line in start.line - 1..end.line {
+            let text = &self.lines[line];
+            let from = if line == start.line - 1 {
+                start.column
+            } else {
+                rv.push('\n');
+                0
+            };
+            let to = if line == end.line - 1 {
+                end.column
+            } else {
+                text.chars().count()
+            };
+            rv.extend(text.chars().skip(from).take(to - from));
+        }
+        rv
+    }
 
-        fn scan_for_path_start(tokens: &[TokenTree], pos: usize) -> usize {
-            let mut rev_tokens = tokens[..=pos].iter().rev();
-            let mut start = rev_tokens.next().unwrap();
-            loop {
-                if let Some(TokenTree::Punct(ref punct)) = rev_tokens.next() {
-                    if punct.as_char() == ':' {
-                        if let Some(TokenTree::Punct(ref punct)) = rev_tokens.next() {
-                            if punct.as_char() == ':' {
-                                if let Some(ident @ TokenTree::Ident(_)) = rev_tokens.next() {
-                                    start = ident;
-                                    continue;
-                                }
-                            }
+    /// Replaces the text between two positions.
+    fn replace_between(&mut self, start: LineColumn, end: LineColumn, text: &str) {
+        let prefix: String = self.lines[start.line - 1]
+            .chars()
+            .take(start.column)
+            .collect();
+        let suffix: String = self.lines[end.line - 1].chars().skip(end.column).collect();
+        let new_contents = format!("{}{}{}", prefix, text, suffix);
+        self.lines.splice(
+            start.line - 1..end.line,
+            new_contents.lines().map(|l| l.to_string()),
+        );
+    }
+
+    /// Turns the inline snapshot assertion in a line into a named assertion.
+    ///
+    /// Returns the contents of the inline snapshot and the asserted
+    /// expression.
+    pub fn externalize_snapshot(
+        &mut self,
+        assertion: &SnapshotAssertion,
+        name: &str,
+    ) -> Result<(SnapshotContents, String), Box<dyn Error>> {
+        let args = &assertion.args;
+        let n = args.len();
+        let value = if n >= 4 && is_punct(args.get(n - 3), ',') && is_punct(args.get(n - 2), '@') {
+            parse_string_literal(&args[n - 1])
+        } else {
+            None
+        }
+        .ok_or_else(|| err_msg("the assertion has no inline snapshot"))?;
+        let expression = self.text_between(args[0].span().start(), args[n - 4].span().end());
+        self.replace_between(
+            args[0].span().start(),
+            args[n - 1].span().end(),
+            &format!("{:?}, {}", name, expression),
+        );
+        Ok((SnapshotContents::from_inline(&value), expression))
+    }
+
+    /// Returns the name of the snapshot of a named assertion.
+    pub fn snapshot_name(&self, assertion: &SnapshotAssertion) -> Option<String> {
+        if !is_punct(assertion.args.get(1), ',') {
+            return None;
+        }
+        assertion.args.first().and_then(parse_string_literal)
+    }
+
+    /// Finds the assertion that is named with the given string literal.
+    pub fn find_named_assertion(&self, name: &str) -> Option<SnapshotAssertion> {
+        (0..self.lines.len())
+            .filter(|&idx| self.lines[idx].contains(name))
+            .filter_map(|idx| self.find_assertion(idx + 1))
+            .find(|assertion| self.snapshot_name(assertion).as_deref() == Some(name))
+    }
+
+    /// Changes the name of a named assertion.
+    pub fn rename_snapshot(
+        &mut self,
+        assertion: &SnapshotAssertion,
+        name: &str,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.snapshot_name(assertion).is_none() {
+            return Err(err_msg("the assertion is not named with a string literal"));
+        }
+        let span = assertion.args[0].span();
+        self.replace_between(span.start(), span.end(), &format!("{:?}", name));
+        Ok(())
+    }
+
+    /// Turns a named assertion into an inline snapshot assertion.
+    pub fn inline_snapshot(
+        &mut self,
+        assertion: &SnapshotAssertion,
+        contents: &SnapshotContents,
+    ) -> Result<(), Box<dyn Error>> {
+        if self.snapshot_name(assertion).is_none() {
+            return Err(err_msg("the assertion is not named with a string literal"));
+        }
+        let args = &assertion.args;
+        // a trailing comma is dropped as the snapshot is appended
+        let last = if is_punct(args.last(), ',') {
+            args.len() - 2
+        } else {
+            args.len() - 1
+        };
+        if last < 2 {
+            return Err(err_msg("the assertion has no value"));
+        }
+        let rest = self.text_between(args[2].span().start(), args[last].span().end());
+        self.replace_between(
+            args[0].span().start(),
+            args[args.len() - 1].span().end(),
+            &format!("{}, @{}", rest, contents.to_inline(assertion.indentation)),
+        );
+        Ok(())
+    }
+
+    /// Finds the line of a function by its name.
+    pub fn find_function(&self, name: &str) -> Option<usize> {
+        struct Visitor<'a>(&'a str, Option<usize>);
+
+        impl<'a, 'ast> syn::visit::Visit<'ast> for Visitor<'a> {
+            fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+                if self.1.is_none() && i.sig.ident == self.0 {
+                    self.1 = Some(i.sig.ident.span().start().line);
+                }
+                syn::visit::visit_item_fn(self, i);
+            }
+        }
+
+        let mut visitor = Visitor(name, None);
+        syn::visit::visit_file(&mut visitor, &self.source);
+        visitor.1
+    }
+
+    /// Finds the innermost snapshot assertion macro in a line.
+    pub fn find_assertion(&self, line: usize) -> Option<SnapshotAssertion> {
+        struct Visitor(usize, SnapshotAssertion, bool);
+
+        fn is_snapshot_macro(name: &str) -> bool {
+            name.starts_with("assert_") && name.ends_with("_snapshot")
+        }
+
+        impl Visitor {
+            fn contains(&self, span: proc_macro2::Span) -> bool {
+                span.start().line <= self.0 && span.end().line >= self.0
+            }
+
+            fn scan_nested_macros(&mut self, tokens: &[TokenTree]) {
+                for idx in 0..tokens.len() {
+                    if let (Some(TokenTree::Ident(ident)), Some(TokenTree::Group(group))) =
+                        (tokens.get(idx), tokens.get(idx + 2))
+                    {
+                        if is_punct(tokens.get(idx + 1), '!')
+                            && is_snapshot_macro(&ident.to_string())
+                            && ident.span().start().line <= self.0
+                            && group.span().end().line >= self.0
+                        {
+                            self.1.indentation = scan_for_path_start(tokens, idx);
+                            self.1.line = ident.span().start().line;
+                            self.1.args = group.stream().into_iter().collect();
+                            self.2 = true;
+                        }
+                    }
+                }
+
+                for token in tokens {
+                    if let TokenTree::Group(group) = token {
+                        if self.contains(group.span()) {
+                            let tokens: Vec<_> = group.stream().into_iter().collect();
+                            self.scan_nested_macros(&tokens);
                         }
                     }
                 }
-                break;
             }
-            start.span().start().column
         }
 
+        impl<'ast> syn::visit::Visit<'ast> for Visitor {
+            fn visit_item_mod(&mut self, i: &'ast syn::ItemMod) {
+                if i.content.is_some() && self.contains(i.span()) {
+                    self.1.modules.push(i.ident.to_string());
+                    syn::visit::visit_item_mod(self, i);
+                }
+            }
+
+            fn visit_item_fn(&mut self, i: &'ast syn::ItemFn) {
+                if self.contains(i.span()) {
+                    self.1.function = Some(i.sig.ident.to_string());
+                    syn::visit::visit_item_fn(self, i);
+                }
+            }
+
+            fn visit_macro(&mut self, i: &'ast syn::Macro) {
+                if !self.contains(i.span()) {
+                    return;
+                }
+                let tokens: Vec<_> = i.tokens.clone().into_iter().collect();
+                if i.path
+                    .segments
+                    .last()
+                    .map_or(false, |x| is_snapshot_macro(&x.ident.to_string()))
+                {
+                    self.1.indentation = i.span().start().column;
+                    self.1.line = i.span().start().line;
+                    self.1.args = tokens.clone();
+                    self.2 = true;
+                }
+                self.scan_nested_macros(&tokens);
+            }
+        }
+
+        let mut visitor = Visitor(line, SnapshotAssertion::default(), false);
+        syn::visit::visit_file(&mut visitor, &self.source);
+        if visitor.2 && !visitor.1.args.is_empty() {
+            Some(visitor.1)
+        } else {
+            None
+        }
+    }
+
+    fn find_snapshot_macro(&self, line: usize) -> Option<InlineSnapshot> {
+        struct Visitor(usize, Option<InlineSnapshot>);
+
         impl Visitor {
             pub fn scan_nested_macros(&mut self, tokens: &[TokenTree]) {
                 for idx in 0..tokens.len() {
@@ -157,12 +436,19 @@ impl FilePatcher {
                 tokens: &[TokenTree],
                 indentation: usize,
             ) -> bool {
-                match &tokens[tokens.len() - 2] {
-                    TokenTree::Punct(ref punct) if punct.as_char() == '@' => {}
+                let at = match &tokens[tokens.len() - 2] {
+                    TokenTree::Punct(ref punct) if punct.as_char() == '@' => punct.span(),
                     _ => {
                         return false;
                     }
-                }
+                };
+
+                // if the snapshot starts on its own line the contents are
+                // indented like that line instead of like the macro.
+                let indentation = match tokens.len().checked_sub(3).map(|idx| &tokens[idx]) {
+                    Some(prev) if prev.span().end().line < at.start().line => at.start().column,
+                    _ => indentation,
+                };
 
                 let (start, end) = match &tokens[tokens.len() - 1] {
                     TokenTree::Literal(lit) => {
@@ -11,9 +11,12 @@
 //! ```
 //!
 //! For more information see [the insta crate documentation](https://docs.rs/insta).
+mod archive;
+mod backup;
 mod cargo;
 mod cli;
 mod inline;
+mod queue;
 mod utils;
 
 use console::style;
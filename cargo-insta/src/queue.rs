@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A snapshot in the review queue.
+struct QueueEntry {
+    /// The hash of the skipped contents.
+    hash: String,
+    /// How many reviews skipped the snapshot.
+    count: usize,
+}
+
+/// The snapshots that were skipped in earlier reviews.
+///
+/// The queue is stored in `.insta/queue` in the workspace root.  Every line
+/// holds the hash of the skipped contents and how many reviews skipped the
+/// snapshot followed by the snapshot location.
+pub struct ReviewQueue {
+    path: PathBuf,
+    entries: BTreeMap<String, QueueEntry>,
+    changed: bool,
+}
+
+impl ReviewQueue {
+    /// Returns the path of the queue for a workspace.
+    pub fn path_for(workspace_root: &Path) -> PathBuf {
+        workspace_root.join(".insta").join("queue")
+    }
+
+    /// Loads the queue of a workspace.  A missing queue is empty.
+    pub fn load(workspace_root: &Path) -> Result<ReviewQueue, Box<dyn Error>> {
+        let path = ReviewQueue::path_for(workspace_root);
+        let mut entries = BTreeMap::new();
+        if path.is_file() {
+            for line in fs::read_to_string(&path)?.lines() {
+                let mut iter = line.splitn(3, ' ');
+                if let (Some(hash), Some(count), Some(key)) =
+                    (iter.next(), iter.next(), iter.next())
+                {
+                    entries.insert(
+                        key.to_string(),
+                        QueueEntry {
+                            hash: hash.to_string(),
+                            count: count.parse().unwrap_or(1),
+                        },
+                    );
+                }
+            }
+        }
+        Ok(ReviewQueue {
+            path,
+            entries,
+            changed: false,
+        })
+    }
+
+    /// Checks if the snapshot was skipped with the same contents before.
+    pub fn is_skipped(&self, key: &str, hash: &str) -> bool {
+        self.entries.get(key).map_or(false, |x| x.hash == hash)
+    }
+
+    /// Returns how many earlier reviews skipped the snapshot.
+    pub fn skip_count(&self, key: &str) -> usize {
+        self.entries.get(key).map_or(0, |x| x.count)
+    }
+
+    /// Remembers that a snapshot was skipped.
+    pub fn skip(&mut self, key: String, hash: String) {
+        let count = self.skip_count(&key) + 1;
+        self.entries.insert(key, QueueEntry { hash, count });
+        self.changed = true;
+    }
+
+    /// Forgets about a snapshot once it was decided on.
+    pub fn remove(&mut self, key: &str) {
+        if self.entries.remove(key).is_some() {
+            self.changed = true;
+        }
+    }
+
+    /// Removes the queue at the given path and its folder if it is empty.
+    pub fn clear(path: &Path) -> Result<(), Box<dyn Error>> {
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+        if let Some(parent) = path.parent() {
+            fs::remove_dir(parent).ok();
+        }
+        Ok(())
+    }
+
+    /// Writes the queue back if it was changed.
+    pub fn save(&self) -> Result<(), Box<dyn Error>> {
+        if !self.changed {
+            return Ok(());
+        }
+        if self.entries.is_empty() {
+            return ReviewQueue::clear(&self.path);
+        }
+        let mut contents = String::new();
+        for (key, entry) in self.entries.iter() {
+            contents.push_str(&format!("{} {} {}\n", entry.hash, entry.count, key));
+        }
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(&self.path, contents)?;
+        Ok(())
+    }
+}
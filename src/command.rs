@@ -0,0 +1,111 @@
+use std::borrow::BorrowMut;
+use std::env;
+use std::fmt::Write;
+use std::process::Command;
+
+use crate::settings::Settings;
+
+/// Removes ANSI escape sequences such as colors from a string.
+fn strip_ansi_escapes(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            rv.push(c);
+            continue;
+        }
+        match chars.next() {
+            // control sequences end with a character in the range `@` to `~`
+            Some('[') => {
+                for c in &mut chars {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // operating system commands end with BEL or `ESC \`
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek() == Some(&'\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    rv
+}
+
+/// Replaces the temporary directory with a placeholder.
+fn redact_temp_dir(s: &str) -> String {
+    let temp_dir = env::temp_dir();
+    let mut paths = vec![temp_dir.display().to_string()];
+    if let Ok(canonicalized) = temp_dir.canonicalize() {
+        paths.push(canonicalized.display().to_string());
+    }
+    // longer paths first so that a path is not partially replaced
+    paths.sort_by_key(|x| std::cmp::Reverse(x.len()));
+    let mut rv = s.to_string();
+    for path in paths {
+        let path = path.trim_end_matches(|c| c == '/' || c == '\\');
+        if !path.is_empty() {
+            rv = rv.replace(path, "[TEMP_DIR]");
+        }
+    }
+    rv
+}
+
+/// Applies the normalizations of the settings to the output of a command.
+fn normalize_output(output: &[u8]) -> String {
+    let mut rv = String::from_utf8_lossy(output).replace("\r\n", "\n");
+    Settings::with(|settings| {
+        if settings.strip_ansi_escapes() {
+            rv = strip_ansi_escapes(&rv);
+        }
+        if settings.redact_temp_dir() {
+            rv = redact_temp_dir(&rv);
+        }
+        if settings.normalize_path_separators() {
+            rv = rv.replace('\\', "/");
+        }
+    });
+    rv
+}
+
+/// Runs a command and describes its exit status and output.
+///
+/// Panics if the command cannot be spawned.
+pub fn run_command<C: BorrowMut<Command>>(mut cmd: C) -> String {
+    let cmd = cmd.borrow_mut();
+    let output = cmd
+        .output()
+        .unwrap_or_else(|err| panic!("failed to run {:?}: {}", cmd, err));
+    let mut rv = String::new();
+    writeln!(rv, "success: {}", output.status.success()).unwrap();
+    match output.status.code() {
+        Some(code) => writeln!(rv, "exit_code: {}", code).unwrap(),
+        None => writeln!(rv, "exit_code: none").unwrap(),
+    }
+    for (title, output) in &[("stdout", &output.stdout), ("stderr", &output.stderr)] {
+        writeln!(rv, "----- {} -----", title).unwrap();
+        let output = normalize_output(output);
+        rv.push_str(&output);
+        if !output.is_empty() && !output.ends_with('\n') {
+            rv.push('\n');
+        }
+    }
+    rv
+}
+
+#[test]
+fn test_strip_ansi_escapes() {
+    assert_eq!(
+        strip_ansi_escapes("\x1b[1;31merror\x1b[0m: \x1b]8;;url\x07link\x1b]8;;\x1b\\"),
+        "error: link"
+    );
+}
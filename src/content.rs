@@ -336,10 +336,93 @@ impl Content {
         }
     }
 
-    pub(crate) fn sort_maps(&mut self) {
+    /// Sorts the keys of maps.
+    ///
+    /// If a maximum depth is given only maps nested in at most that many
+    /// maps (counting the map itself) are sorted.
+    pub(crate) fn sort_maps(&mut self, max_depth: Option<usize>) {
+        if max_depth == Some(0) {
+            return;
+        }
+        if let Content::Map(ref mut items) = *self {
+            items.sort_by(|a, b| a.0.as_key().cmp(&b.0.as_key()));
+            let max_depth = max_depth.map(|x| x - 1);
+            for item in items.iter_mut() {
+                item.0.sort_maps(max_depth);
+                item.1.sort_maps(max_depth);
+            }
+            return;
+        }
         self.walk(&mut |content| {
-            if let Content::Map(ref mut items) = content {
-                items.sort_by(|a, b| a.0.as_key().cmp(&b.0.as_key()));
+            if let Content::Map(_) = content {
+                content.sort_maps(max_depth);
+                false
+            } else {
+                true
+            }
+        })
+    }
+
+    /// Checks if the content is rendered as a table (or array of tables) in TOML.
+    #[cfg(feature = "toml")]
+    fn is_toml_table(&self) -> bool {
+        match *self.resolve_inner() {
+            Content::Map(_) | Content::Struct(..) | Content::StructVariant(..) => true,
+            Content::Seq(ref items) | Content::Tuple(ref items) => {
+                items.first().map_or(false, |x| x.is_toml_table())
+            }
+            _ => false,
+        }
+    }
+
+    /// Moves entries holding tables behind all other entries.
+    ///
+    /// TOML requires the plain values of a table to come before its nested
+    /// tables.  Otherwise the order of the entries is kept.
+    #[cfg(feature = "toml")]
+    pub(crate) fn move_toml_tables_last(&mut self) {
+        self.walk(&mut |content| {
+            match content {
+                Content::Map(ref mut items) => items.sort_by_key(|x| x.1.is_toml_table()),
+                Content::Struct(_, ref mut fields)
+                | Content::StructVariant(_, _, _, ref mut fields) => {
+                    fields.sort_by_key(|x| x.1.is_toml_table())
+                }
+                _ => {}
+            }
+            true
+        })
+    }
+
+    fn is_none(&self) -> bool {
+        match *self {
+            Content::None => true,
+            _ => false,
+        }
+    }
+
+    pub(crate) fn remove_none_fields(&mut self) {
+        self.walk(&mut |content| {
+            match content {
+                Content::Map(ref mut items) => {
+                    items.retain(|(_, value)| !value.is_none());
+                }
+                Content::Struct(_, ref mut fields)
+                | Content::StructVariant(_, _, _, ref mut fields) => {
+                    fields.retain(|(_, value)| !value.is_none());
+                }
+                _ => {}
+            }
+            true
+        })
+    }
+
+    pub(crate) fn normalize_path_separators(&mut self) {
+        self.walk(&mut |content| {
+            if let Content::String(ref mut s) = content {
+                if s.contains('\\') {
+                    *s = s.replace('\\', "/");
+                }
             }
             true
         })
@@ -1,6 +1,7 @@
 use std::collections::BTreeMap;
 use std::io::Write;
 use std::path::{Path, PathBuf};
+use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::{env, fs};
 
@@ -11,6 +12,12 @@ use crate::utils::is_ci;
 
 lazy_static! {
     static ref WORKSPACES: Mutex<BTreeMap<String, Arc<PathBuf>>> = Mutex::new(BTreeMap::new());
+    static ref RUST_VERSION: Option<String> = {
+        let rustc = env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+        let output = Command::new(rustc).arg("--version").output().ok()?;
+        let version = String::from_utf8(output.stdout).ok()?;
+        Some(version.trim().trim_start_matches("rustc ").to_string())
+    };
 }
 
 /// How snapshots are supposed to be updated
@@ -37,6 +44,52 @@ pub enum OutputBehavior {
     Nothing,
 }
 
+/// How diffs are laid out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DiffStyle {
+    /// Removed and added lines below each other
+    Unified,
+    /// Old lines on the left and new lines on the right
+    SideBySide,
+}
+
+impl DiffStyle {
+    /// Returns the diff style for a name as accepted by `INSTA_DIFF_STYLE`.
+    pub fn from_name(name: &str) -> Option<DiffStyle> {
+        Some(match name {
+            "unified" => DiffStyle::Unified,
+            "side-by-side" => DiffStyle::SideBySide,
+            _ => return None,
+        })
+    }
+}
+
+/// Returns the version reported by `rustc --version` (or `$RUSTC`).
+///
+/// This is the compiler found when the tests run which is not necessarily
+/// the one that built them.  It is only run the first time the version is
+/// requested.
+pub fn get_rust_version() -> Option<String> {
+    RUST_VERSION.clone()
+}
+
+/// Returns the architecture, operating system and environment the tests were
+/// built for, for instance `x86_64-linux-gnu`.
+///
+/// This is not a target triple as the vendor is not known.
+pub fn get_platform() -> String {
+    let target_env = if cfg!(target_env = "gnu") {
+        "-gnu"
+    } else if cfg!(target_env = "msvc") {
+        "-msvc"
+    } else if cfg!(target_env = "musl") {
+        "-musl"
+    } else {
+        ""
+    };
+    format!("{}-{}{}", env::consts::ARCH, env::consts::OS, target_env)
+}
+
 /// Is insta told to force update snapshots?
 pub fn force_update_snapshots() -> bool {
     match env::var("INSTA_FORCE_UPDATE_SNAPSHOTS").ok().as_deref() {
@@ -55,6 +108,15 @@ pub fn force_pass() -> bool {
     }
 }
 
+/// Should snapshots that only match because of redactions fail?
+pub fn require_full_match() -> bool {
+    match env::var("INSTA_REQUIRE_FULL_MATCH").ok().as_deref() {
+        None | Some("") | Some("0") => false,
+        Some("1") => true,
+        _ => panic!("invalid value for INSTA_REQUIRE_FULL_MATCH"),
+    }
+}
+
 /// Returns the intended output behavior for insta.
 pub fn get_output_behavior() -> OutputBehavior {
     match env::var("INSTA_OUTPUT").ok().as_deref() {
@@ -66,6 +128,48 @@ pub fn get_output_behavior() -> OutputBehavior {
     }
 }
 
+/// Returns the snapshot format requested for format agnostic assertions.
+pub fn get_default_format() -> Option<String> {
+    match env::var("INSTA_DEFAULT_FORMAT").ok() {
+        None => None,
+        Some(ref value) if value.is_empty() => None,
+        Some(value) => Some(value),
+    }
+}
+
+/// Returns the maximum width for rendering diffs.
+pub fn get_max_width() -> Option<usize> {
+    match env::var("INSTA_MAX_WIDTH").ok().as_deref() {
+        None | Some("") => None,
+        Some(value) => Some(value.parse().expect("invalid value for INSTA_MAX_WIDTH")),
+    }
+}
+
+/// Returns the external tool to show diffs with.
+pub fn get_diff_tool() -> Option<String> {
+    match env::var("INSTA_DIFF_TOOL").ok() {
+        None => None,
+        Some(value) if value.trim().is_empty() => None,
+        Some(value) => Some(value),
+    }
+}
+
+/// Returns the requested layout of diffs.
+///
+/// Invalid values fall back to unified diffs with a warning.
+pub fn get_diff_style() -> DiffStyle {
+    match env::var("INSTA_DIFF_STYLE").ok().as_deref() {
+        None | Some("") => DiffStyle::Unified,
+        Some(name) => DiffStyle::from_name(name).unwrap_or_else(|| {
+            eprintln!(
+                "warning: invalid value for INSTA_DIFF_STYLE: {}, using unified diffs",
+                name
+            );
+            DiffStyle::Unified
+        }),
+    }
+}
+
 /// Returns the intended snapshot update behavior.
 pub fn get_snapshot_update_behavior(unseen: bool) -> SnapshotUpdate {
     match env::var("INSTA_UPDATE").ok().as_deref() {
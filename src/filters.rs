@@ -0,0 +1,65 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+
+/// Represents stored filters.
+#[derive(Debug, Default, Clone)]
+pub struct Filters {
+    rules: Vec<(Regex, String)>,
+}
+
+impl<'a> From<Vec<(&'a str, &'a str)>> for Filters {
+    fn from(value: Vec<(&'a str, &'a str)>) -> Filters {
+        let mut rv = Filters::default();
+        for (regex, replacement) in value {
+            rv.add(regex, replacement);
+        }
+        rv
+    }
+}
+
+impl Filters {
+    /// Adds a new filter.
+    ///
+    /// Panics if the regex is invalid.
+    pub(crate) fn add<S: Into<String>>(&mut self, regex: &str, replacement: S) {
+        self.rules.push((
+            Regex::new(regex).expect("invalid regex for snapshot filter"),
+            replacement.into(),
+        ));
+    }
+
+    /// Removes all filters.
+    pub(crate) fn clear(&mut self) {
+        self.rules.clear();
+    }
+
+    /// Returns `true` if no filters are set.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Applies all filters in the order they were added.
+    pub(crate) fn apply_to<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        let mut rv = Cow::Borrowed(s);
+        for (regex, replacement) in &self.rules {
+            if let Cow::Owned(value) = regex.replace_all(&rv, replacement.as_str()) {
+                rv = Cow::Owned(value);
+            }
+        }
+        rv
+    }
+}
+
+#[test]
+fn test_filters() {
+    let filters = Filters::from(vec![(r"/Users/\w+", "[HOME]"), (r"\[HOME\]/src", "[SRC]")]);
+    assert_eq!(
+        filters.apply_to("/Users/jane/src/lib.rs and /Users/joe/x"),
+        "[SRC]/lib.rs and [HOME]/x"
+    );
+    match filters.apply_to("nothing") {
+        Cow::Borrowed(value) => assert_eq!(value, "nothing"),
+        Cow::Owned(_) => panic!("unchanged value was copied"),
+    }
+}
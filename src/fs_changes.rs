@@ -0,0 +1,84 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::snapshot::hash_bytes;
+
+/// Collects the hashes of all files below a folder by relative path.
+fn hash_files(root: &Path, dir: &Path, rv: &mut BTreeMap<PathBuf, String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(_) => return,
+    };
+    for entry in entries.filter_map(|x| x.ok()) {
+        let path = entry.path();
+        let file_type = match entry.file_type() {
+            Ok(file_type) => file_type,
+            Err(_) => continue,
+        };
+        if file_type.is_dir() {
+            hash_files(root, &path, rv);
+        } else if let Ok(contents) = fs::read(&path) {
+            rv.insert(
+                path.strip_prefix(root).unwrap().to_path_buf(),
+                hash_bytes(&contents),
+            );
+        }
+    }
+}
+
+/// Runs a closure and describes the files it created or modified in a folder.
+///
+/// Every changed file is listed by its path relative to the folder followed
+/// by its indented contents.  Files that are not valid UTF-8 are described
+/// by their size and hash instead.  Files that existed before and were left
+/// unchanged are not included.
+pub fn capture_fs_changes<P: AsRef<Path>, F: FnOnce()>(dir: P, f: F) -> String {
+    let dir = dir.as_ref();
+    let mut before = BTreeMap::new();
+    hash_files(dir, dir, &mut before);
+    f();
+    let mut after = BTreeMap::new();
+    hash_files(dir, dir, &mut after);
+
+    let mut rv = String::new();
+    for (path, hash) in after {
+        let action = match before.get(&path) {
+            None => "created",
+            Some(old_hash) if *old_hash != hash => "modified",
+            Some(_) => continue,
+        };
+        let storage_path = path
+            .components()
+            .map(|x| x.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        writeln!(rv, "{} {}", action, storage_path).unwrap();
+        let contents = fs::read(dir.join(&path)).unwrap_or_default();
+        match String::from_utf8(contents) {
+            Ok(text) => {
+                for line in text.replace("\r\n", "\n").lines() {
+                    if line.is_empty() {
+                        rv.push('\n');
+                    } else {
+                        writeln!(rv, "    {}", line).unwrap();
+                    }
+                }
+            }
+            Err(err) => {
+                writeln!(
+                    rv,
+                    "    [binary, {} bytes, hash {}]",
+                    err.as_bytes().len(),
+                    hash
+                )
+                .unwrap();
+            }
+        }
+    }
+    if rv.is_empty() {
+        rv.push_str("no changes\n");
+    }
+    rv
+}
@@ -1,11 +1,39 @@
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
 
 use globset::GlobBuilder;
 use walkdir::WalkDir;
 
+use crate::runtime::assertion_count;
 use crate::settings::Settings;
 
 pub fn glob_exec<F: FnMut(&Path)>(base: &Path, pattern: &str, mut f: F) {
+    glob_exec_with_relative_path(base, pattern, |path, _| f(path));
+}
+
+/// Returns the snapshot suffix for a matched file.
+///
+/// This is the file name unless another matched file has the same name in
+/// which case the path relative to the base is used.
+fn snapshot_suffix(stripped_path: &Path, matches: &[PathBuf]) -> String {
+    let file_name = stripped_path.file_name();
+    let is_unique = matches
+        .iter()
+        .filter(|x| x.file_name() == file_name)
+        .count()
+        <= 1;
+    if is_unique {
+        file_name.unwrap().to_str().unwrap().to_string()
+    } else {
+        stripped_path
+            .components()
+            .map(|x| x.as_os_str().to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("__")
+    }
+}
+
+pub fn glob_exec_with_relative_path<F: FnMut(&Path, &Path)>(base: &Path, pattern: &str, mut f: F) {
     let glob = GlobBuilder::new(pattern)
         .case_insensitive(true)
         .literal_separator(true)
@@ -14,27 +42,58 @@ pub fn glob_exec<F: FnMut(&Path)>(base: &Path, pattern: &str, mut f: F) {
         .compile_matcher();
 
     let walker = WalkDir::new(base).follow_links(true);
-    let mut glob_found_matches = false;
     let mut settings = Settings::clone_current();
+    let mut unasserted = vec![];
 
+    let mut matches = vec![];
     for file in walker {
         let file = file.unwrap();
         let path = file.path();
         let stripped_path = path.strip_prefix(base).unwrap_or(path);
-        if !glob.is_match(stripped_path) {
-            continue;
+        if glob.is_match(stripped_path) {
+            matches.push(path.to_path_buf());
         }
+    }
+    matches.sort();
+    let glob_found_matches = !matches.is_empty();
+
+    for path in &matches {
+        let stripped_path = path.strip_prefix(base).unwrap_or(path);
 
         settings.set_input_file(&path);
-        settings.set_snapshot_suffix(path.file_name().unwrap().to_str().unwrap());
+        settings.set_snapshot_suffix(snapshot_suffix(stripped_path, &matches));
 
-        glob_found_matches = true;
+        let assertions_before = assertion_count();
         settings.bind(|| {
-            f(path);
+            f(path, stripped_path);
         });
+        if assertion_count() == assertions_before {
+            unasserted.push(stripped_path.to_path_buf());
+        }
     }
 
     if !glob_found_matches && !settings.allow_empty_glob() {
         panic!("the glob! macro did not match any files.");
     }
+
+    if !unasserted.is_empty() {
+        report_unasserted(&unasserted, settings.require_glob_assertions());
+    }
+}
+
+fn report_unasserted(inputs: &[PathBuf], fail: bool) {
+    let inputs = inputs
+        .iter()
+        .map(|x| x.display().to_string())
+        .collect::<Vec<_>>()
+        .join(", ");
+    if fail {
+        panic!("the glob! macro ran no snapshot assertions for: {}", inputs);
+    }
+    writeln!(
+        std::io::stderr(),
+        "warning: the glob! macro ran no snapshot assertions for: {}",
+        inputs
+    )
+    .ok();
 }
@@ -63,6 +63,22 @@
 //! - `assert_snapshot!` for comparing basic string snapshots.
 //! - `assert_debug_snapshot!` for comparing `Debug` outputs of values.
 //! - `assert_display_snapshot!` for comparing `Display` outputs of values.
+//! - `assert_lines_snapshot!` for comparing the `Debug` outputs of the items
+//!   of an iterator with one item per line.
+//! - `assert_error_chain_snapshot!` for comparing the chain of sources of
+//!   values implementing `std::error::Error`.
+//! - `assert_typed_snapshot!` for comparing `Debug` outputs of values
+//!   together with the name of their type.
+//! - `assert_stdout_snapshot!` for comparing what a closure writes to
+//!   stdout. (requires the `stdout` feature, unix only)
+//! - `assert_log_snapshot!` for comparing the records a closure emits
+//!   through the `log` crate. (requires the `log` feature)
+//! - `assert_fs_snapshot!` for comparing the files a closure creates or
+//!   modifies in a folder.
+//! - `assert_command_snapshot!` for comparing the exit status and output of
+//!   a `std::process::Command`.
+//! - `assert_binary_snapshot!` for comparing bytes which are stored in a
+//!   separate file.
 //! - `assert_csv_snapshot!` for comparing CSV serialized output of
 //!   types implementing `serde::Serialize`. (requires the `csv` feature)
 //! - `assert_toml_snapshot!` for comparing TOML serialized output of
@@ -71,12 +87,28 @@
 //!   output of types implementing `serde::Serialize`.
 //! - `assert_ron_snapshot!` for comparing RON serialized output of
 //!   types implementing `serde::Serialize`. (requires the `ron` feature)
+//! - `assert_proto_snapshot!` for comparing the protobuf text format of
+//!   `prost` messages. (requires the `prost` feature)
 //! - `assert_json_snapshot!` for comparing JSON serialized output of
 //!   types implementing `serde::Serialize`.
+//! - `assert_schema_snapshot!` for comparing the JSON schema of types
+//!   implementing `schemars::JsonSchema`. (requires the `schemars` feature)
+//! - `assert_snapshot_against!` for comparing a string against a golden file
+//!   included with `include_str!`.  The file is rewritten when snapshots are
+//!   updated in-place (`INSTA_UPDATE=always`).
+//! - `assert_snapshot_matches_file!` for comparing a string against a golden
+//!   file read at runtime.  Changes can be reviewed with `cargo insta review`.
+//! - `assert_serialized_snapshot!` for comparing serialized output of types
+//!   implementing `serde::Serialize` in a configurable format.  The format
+//!   is picked from [`Settings::set_default_format`] or the
+//!   `INSTA_DEFAULT_FORMAT` environment variable and defaults to YAML.  Only
+//!   this macro is affected by the default format.
 //!
 //! For macros that work with `serde::Serialize` this crate also permits
 //! redacting of partial values.  See [redactions in the documentation](https://insta.rs/docs/redactions/)
-//! for more information.
+//! for more information.  Normalizations that are shared by many tests can
+//! be registered by name with [`register_normalizer`] and enabled per
+//! assertion with `with_settings!({normalizers => vec!["name"]}, ...)`.
 //!
 //! # Snapshot updating
 //!
@@ -102,10 +134,54 @@
 //! ```
 //!
 //! "enter" or "a" accepts a new snapshot, "escape" or "r" rejects,
-//! "space" or "s" skips the snapshot for now.
+//! "space" or "s" skips the snapshot for now.  "q" keeps the decisions made
+//! so far and leaves the remaining snapshots pending.  If a change only adds
+//! lines, "A" accepts it together with all further additive-only changes.
+//! "+" and "-" accept or reject the current and all remaining undecided
+//! snapshots after asking for confirmation.
+//! "j" and "k" move to the next and previous snapshot without deciding
+//! and "u" undoes the last decision.
+//! For snapshot files "p" asks for a range of lines and only accepts the
+//! changes within it, the rest is reverted to the old snapshot.
+//! Skipped snapshots are remembered in `.insta/queue` in the workspace root
+//! and are not shown again until their contents change or the queue is
+//! cleared with `cargo insta queue clear`.  The review shows
+//! how often a snapshot was skipped before.
+//!
+//! `cargo insta test --last-failed` records the tests that left pending
+//! snapshots in `.insta/last-failed` and only runs these tests the next time
+//! it is used.  As the `.insta` folder only holds such local state it should
+//! be added to `.gitignore`.
+//!
+//! Diffs are rendered to the width of the terminal.  `INSTA_MAX_WIDTH` (or
+//! `--max-width` for `cargo insta`) caps that width and wraps longer lines.
+//! `INSTA_DIFF_STYLE=side-by-side` (or `--diff-style side-by-side`) shows the
+//! old and new lines next to each other if the terminal is wide enough.
+//! Unknown values of `INSTA_DIFF_STYLE` fall back to unified diffs.
+//! `INSTA_DIFF_TOOL` hands diffs to an external tool such as `delta` or
+//! `difft` instead which is invoked with the paths of temporary files holding
+//! the old and the new contents.
+//! Colors are disabled if `NO_COLOR` is set to a non-empty value unless
+//! `cargo insta` is invoked with `--color always`.
 //!
 //! For more information [read the cargo insta docs](https://insta.rs/docs/cli/).
 //!
+//! Snapshots that are known to be wrong can be marked by adding
+//! `expected_failure: true` to the metadata header of the snapshot file.  A
+//! mismatch is then reported as still failing as expected while a match fails
+//! the assertion as a reminder to remove the marker again.
+//!
+//! Snapshots that must never change can be frozen by adding `frozen: true`
+//! to their metadata or with [`Settings::set_frozen`].  A frozen snapshot
+//! that does not match always fails the assertion, no matter the
+//! `INSTA_UPDATE` mode, and `cargo insta review` skips it.
+//!
+//! With `INSTA_REQUIRE_FULL_MATCH=1` (or `cargo insta test
+//! --require-full-match` or [`Settings::set_require_full_match`]) snapshots
+//! whose value is changed by redactions or filters record hashes of the
+//! value before and after they were applied in their metadata.  A snapshot
+//! that still matches but whose unredacted value changed then fails.
+//!
 //! # Inline Snapshots
 //!
 //! Additionally snapshots can also be stored inline.  In that case the format
@@ -132,26 +208,76 @@
 //! `cargo insta review` to accept the change.  The file will then be updated
 //! automatically.
 //!
+//! `cargo insta externalize --snapshot FILE:LINE` moves an inline snapshot
+//! into a snapshot file named after the test and `cargo insta inline` moves
+//! it back into the source.
+//!
 //! # Features
 //!
 //! The following features exist:
 //!
 //! * `csv`: enables CSV support ([`assert_csv_snapshot!`])
 //! * `ron`: enables RON support ([`assert_ron_snapshot!`])
+//! * `prost`: enables protobuf support ([`assert_proto_snapshot!`])
 //! * `toml`: enables TOML support ([`assert_toml_snapshot!`])
 //! * `redactions`: enables support for redactions
+//! * `filters`: enables support for regex filters ([`Settings::add_filter`])
 //! * `glob`: enables support for globbing ([`glob!`])
+//! * `stdout`: enables capturing of stdout ([`assert_stdout_snapshot!`], unix only)
+//! * `log`: enables capturing of `log` records ([`assert_log_snapshot!`])
+//! * `schemars`: enables JSON schema snapshots ([`assert_schema_snapshot!`])
 //! * `colors`: enables color output (enabled by default)
 //!
 //! # Settings
 //!
 //! There are some settings that can be changed on a per-thread (and thus
 //! per-test) basis.  For more information see [Settings].
+//!
+//! Defaults for some settings can be configured for the entire workspace in
+//! an `insta.yaml` file in the workspace root.  Individual tests can still
+//! override them:
+//!
+//! ```yaml
+//! settings:
+//!   sort_maps: true
+//!   strip_ansi: true
+//!   filters:
+//!     - ['\d{4}-\d{2}-\d{2}', '[DATE]']
+//!   snapshot_path: snapshots
+//!   prepend_module_to_snapshot: true
+//!   omit_metadata: false
+//!   omit_expression: false
+//!   externalize_over_bytes: 0
+//!   normalize_path_separators: false
+//!   skip_none_fields: false
+//!   structural_comparison: false
+//!   unordered_sequences: false
+//!   min_similarity: 1.0
+//!   substitute_workspace_root: false
+//!   snapshot_namespace: ns
+//!   default_format: yaml
+//! ```
+//!
+//! With the `toml` feature the same keys are also read from the
+//! `[tool.insta]` or `[workspace.metadata.insta]` table of the workspace
+//! `Cargo.toml` where other keys are ignored.  Values from `insta.yaml` take
+//! precedence.  Filters require the `filters` feature and are added to those
+//! of the manifest.  Invalid configs are reported and ignored:
+//!
+//! ```toml
+//! [workspace.metadata.insta]
+//! sort_maps = true
+//! snapshot_path = "snapshots"
+//! ```
 #[macro_use]
 mod macros;
+mod command;
 mod content;
 mod env;
+mod fs_changes;
+mod normalizer;
 mod output;
+mod review;
 mod runtime;
 mod serialization;
 mod settings;
@@ -164,11 +290,25 @@ mod redaction;
 #[cfg(feature = "glob")]
 mod glob;
 
+#[cfg(feature = "filters")]
+mod filters;
+
+#[cfg(feature = "prost")]
+mod proto;
+
+#[cfg(all(feature = "stdout", unix))]
+mod stdout;
+
+#[cfg(feature = "log")]
+mod logger;
+
 #[cfg(test)]
 mod test;
 
-pub use crate::settings::Settings;
-pub use crate::snapshot::{MetaData, Snapshot};
+pub use crate::normalizer::register_normalizer;
+pub use crate::output::{DiffHunk, DiffHunkKind};
+pub use crate::settings::{with_captured_settings, Settings};
+pub use crate::snapshot::{MetaData, Snapshot, SnapshotFormat};
 
 /// Exposes some library internals.
 ///
@@ -176,7 +316,10 @@ pub use crate::snapshot::{MetaData, Snapshot};
 /// are exposed for documentation primarily.
 pub mod internals {
     pub use crate::content::Content;
+    #[cfg(feature = "filters")]
+    pub use crate::filters::Filters;
     pub use crate::runtime::AutoName;
+    pub use crate::settings::Normalizers;
     pub use crate::snapshot::{MetaData, SnapshotContents};
     #[cfg(feature = "redactions")]
     pub use crate::{
@@ -189,24 +332,53 @@ pub mod internals {
 #[doc(hidden)]
 pub mod _cargo_insta_support {
     pub use crate::{
-        output::print_snapshot_diff, snapshot::PendingInlineSnapshot, snapshot::SnapshotContents,
+        env::get_diff_style, env::DiffStyle, output::classify_snapshot_diff,
+        output::count_changed_lines, output::format_unified_diff, output::merge_line_range,
+        output::print_snapshot, output::print_snapshot_diff, output::DiffKind,
+        review::decide_snapshots, review::review_container, review::Operation,
+        review::ReviewContainer, snapshot::binary_file_path, snapshot::data_file_path,
+        snapshot::load_golden_file, snapshot::save_golden_file, snapshot::save_named_snapshot,
+        snapshot::save_snapshot_with_contents, snapshot::snapshot_file_contents,
+        snapshot::snapshot_from_str, snapshot::snapshot_with_contents,
+        snapshot::PendingInlineSnapshot, snapshot::SnapshotContents,
     };
 }
 
 // useful for redactions
 #[cfg(feature = "redactions")]
-pub use crate::redaction::dynamic_redaction;
+pub use crate::redaction::{dynamic_redaction, rounded_redaction, sorted_redaction};
 
 // these are here to make the macros work
 #[doc(hidden)]
 pub mod _macro_support {
+    pub use crate::command::run_command;
     pub use crate::content::Content;
     pub use crate::env::get_cargo_workspace;
-    pub use crate::runtime::{assert_snapshot, AutoName, ReferenceValue};
-    pub use crate::serialization::{serialize_value, SerializationFormat, SnapshotLocation};
+    pub use crate::fs_changes::capture_fs_changes;
+    pub use crate::runtime::{
+        assert_binary_snapshot, assert_golden_file, assert_matches_golden_file,
+        assert_serialized_snapshot, assert_serialized_snapshot_lazy, assert_snapshot,
+        assert_snapshot_lazy, AutoName, ReferenceValue,
+    };
+    pub use crate::serialization::{
+        format_error_chain, format_typed_value, serialize_value, SerializationFormat,
+        SerializedValue, SnapshotLocation,
+    };
 
     #[cfg(feature = "glob")]
-    pub use crate::glob::glob_exec;
+    pub use crate::glob::{glob_exec, glob_exec_with_relative_path};
+
+    #[cfg(feature = "prost")]
+    pub use crate::proto::proto_message;
+
+    #[cfg(all(feature = "stdout", unix))]
+    pub use crate::stdout::capture_stdout;
+
+    #[cfg(feature = "log")]
+    pub use crate::logger::capture_log;
+
+    #[cfg(feature = "schemars")]
+    pub use crate::serialization::serialize_value_sorted;
 
     #[cfg(feature = "redactions")]
     pub use crate::{
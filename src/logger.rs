@@ -0,0 +1,73 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::sync::Once;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+thread_local!(static CAPTURED_RECORDS: RefCell<Option<String>> = RefCell::new(None));
+
+static INSTALL_LOGGER: Once = Once::new();
+
+/// Records log messages for threads that are capturing.
+struct CaptureLogger;
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        CAPTURED_RECORDS.with(|x| x.try_borrow().map_or(false, |x| x.is_some()))
+    }
+
+    fn log(&self, record: &Record) {
+        // formatting the message could log again, such records are dropped
+        CAPTURED_RECORDS.with(|x| {
+            if let Ok(mut captured) = x.try_borrow_mut() {
+                if let Some(ref mut out) = *captured {
+                    writeln!(
+                        out,
+                        "{:<5} {}: {}",
+                        record.level(),
+                        record.target(),
+                        record.args()
+                    )
+                    .unwrap();
+                }
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Stops capturing on the current thread when dropped.
+struct CaptureGuard {
+    previous: Option<String>,
+}
+
+impl Drop for CaptureGuard {
+    fn drop(&mut self) {
+        CAPTURED_RECORDS.with(|x| *x.borrow_mut() = self.previous.take());
+    }
+}
+
+fn install_logger() {
+    INSTALL_LOGGER.call_once(|| {
+        static LOGGER: CaptureLogger = CaptureLogger;
+        log::set_logger(&LOGGER)
+            .expect("failed to install the insta logger, another logger was already installed");
+        log::set_max_level(LevelFilter::Trace);
+    });
+}
+
+/// Runs a closure and returns the log records it emitted.
+///
+/// Each record is rendered on its own line as level, target and message.
+/// Only records emitted on the current thread are captured.
+pub fn capture_log<F: FnOnce()>(f: F) -> String {
+    install_logger();
+    let guard = CaptureGuard {
+        previous: CAPTURED_RECORDS.with(|x| x.borrow_mut().replace(String::new())),
+    };
+    f();
+    let rv = CAPTURED_RECORDS.with(|x| x.borrow_mut().take().unwrap_or_default());
+    drop(guard);
+    rv
+}
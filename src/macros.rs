@@ -1,3 +1,12 @@
+// This macro is basically eprintln but without being captured and
+// hidden by the test runner.
+macro_rules! elog {
+    () => (write!(std::io::stderr()).ok());
+    ($($arg:tt)*) => ({
+        writeln!(std::io::stderr(), $($arg)*).ok();
+    })
+}
+
 /// Asserts a `Serialize` snapshot in CSV format.
 ///
 /// **Feature:** `csv` (disabled by default)
@@ -6,6 +15,9 @@
 /// but serializes in [CSV](https://github.com/burntsushi/rust-csv) format instead of
 /// YAML.
 ///
+/// The snapshot is marked with `content_type: csv` in its metadata and diffs
+/// show the columns aligned.
+///
 /// Example:
 ///
 /// ```no_run,ignore
@@ -20,6 +32,15 @@
 #[cfg(feature = "csv")]
 #[macro_export]
 macro_rules! assert_csv_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!(|| $value, Csv, @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, || $value, Csv);
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), || $value, Csv);
+    }};
     ($value:expr, @$snapshot:literal) => {{
         $crate::_assert_serialized_snapshot!($value, Csv, @$snapshot);
     }};
@@ -47,7 +68,10 @@ macro_rules! assert_csv_snapshot {
 /// This works exactly like [`assert_yaml_snapshot!`]
 /// but serializes in [TOML](https://github.com/alexcrichton/toml-rs) format instead of
 /// YAML.  Note that TOML cannot represent all values due to limitations in the
-/// format.
+/// format.  Nested tables are always written after the plain values of a
+/// table, also if [`Settings::set_sort_maps`](crate::Settings::set_sort_maps)
+/// would order them first.  The snapshot is marked with `content_type: toml`
+/// in its metadata.
 ///
 /// Example:
 ///
@@ -63,6 +87,15 @@ macro_rules! assert_csv_snapshot {
 #[cfg(feature = "toml")]
 #[macro_export]
 macro_rules! assert_toml_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!(|| $value, Toml, @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, || $value, Toml);
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), || $value, Toml);
+    }};
     ($value:expr, @$snapshot:literal) => {{
         $crate::_assert_serialized_snapshot!($value, Toml, @$snapshot);
     }};
@@ -83,6 +116,83 @@ macro_rules! assert_toml_snapshot {
     }};
 }
 
+/// Asserts a protobuf message snapshot in text format.
+///
+/// **Feature:** `prost` (disabled by default)
+///
+/// The value needs to implement both `prost::Message` and `serde::Serialize`
+/// (which prost can derive through `type_attribute` in `prost-build`).  The
+/// message is rendered in the protobuf text format with one field per line
+/// so that diffs show changes per field.  Unset optional fields are left out
+/// and map fields are sorted by key.
+///
+/// Example:
+///
+/// ```no_run,ignore
+/// assert_proto_snapshot!(message);
+/// ```
+///
+/// The third argument to the macro can be an object expression for redaction.
+/// It's in the form `{ selector => replacement }` where the selectors refer
+/// to the field names of the message.  For more information about redactions
+/// refer to the [redactions feature in the guide](https://insta.rs/docs/redactions/).
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[cfg(feature = "prost")]
+#[macro_export]
+macro_rules! assert_proto_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::serialize_value(
+            $crate::_macro_support::proto_message(&$value),
+            $crate::_macro_support::SerializationFormat::Proto,
+            $crate::_macro_support::SnapshotLocation::Inline
+        );
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            value,
+            stringify!($value)
+        );
+    }};
+    ($value:expr, {$($k:expr => $v:expr),*$(,)?}, @$snapshot:literal) => {{
+        let (vec, value) = $crate::_prepare_snapshot_for_redaction!(
+            *$crate::_macro_support::proto_message(&$value), {$($k => $v),*}, Proto, Inline
+        );
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            value,
+            stringify!($value)
+        );
+    }};
+    ($value:expr, {$($k:expr => $v:expr),*$(,)?}) => {{
+        let (vec, value) = $crate::_prepare_snapshot_for_redaction!(
+            *$crate::_macro_support::proto_message(&$value), {$($k => $v),*}, Proto, File
+        );
+        $crate::_assert_serialized_value!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::serialize_value(
+            $crate::_macro_support::proto_message(&$value),
+            $crate::_macro_support::SerializationFormat::Proto,
+            $crate::_macro_support::SnapshotLocation::File
+        );
+        $crate::_assert_serialized_value!(Some($name), value, stringify!($value));
+    }};
+    ($name:expr, $value:expr, {$($k:expr => $v:expr),*$(,)?}) => {{
+        let (vec, value) = $crate::_prepare_snapshot_for_redaction!(
+            *$crate::_macro_support::proto_message(&$value), {$($k => $v),*}, Proto, File
+        );
+        $crate::_assert_serialized_value!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::serialize_value(
+            $crate::_macro_support::proto_message(&$value),
+            $crate::_macro_support::SerializationFormat::Proto,
+            $crate::_macro_support::SnapshotLocation::File
+        );
+        $crate::_assert_serialized_value!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
 /// Asserts a `Serialize` snapshot in YAML format.
 ///
 /// The value needs to implement the `serde::Serialize` trait and the snapshot
@@ -127,6 +237,15 @@ macro_rules! assert_toml_snapshot {
 /// The snapshot name is optional but can be provided as first argument.
 #[macro_export]
 macro_rules! assert_yaml_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!(|| $value, Yaml, @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, || $value, Yaml);
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), || $value, Yaml);
+    }};
     ($value:expr, @$snapshot:literal) => {{
         $crate::_assert_serialized_snapshot!($value, Yaml, @$snapshot);
     }};
@@ -147,6 +266,46 @@ macro_rules! assert_yaml_snapshot {
     }};
 }
 
+/// Asserts a `Serialize` snapshot in the default format.
+///
+/// This works exactly like [`assert_yaml_snapshot!`] but instead of a fixed
+/// format the snapshot is serialized in the format configured with
+/// [`Settings::set_default_format`](crate::Settings::set_default_format) or
+/// the `INSTA_DEFAULT_FORMAT` environment variable, in that order.  If
+/// neither is set YAML is used.  This makes it possible to try out a
+/// different format across a test suite without changing code.  The other
+/// assertion macros always use their own format and are not affected.
+///
+/// Example:
+///
+/// ```no_run
+/// # use insta::*;
+/// assert_serialized_snapshot!(vec![1, 2, 3]);
+/// ```
+///
+/// The snapshot name is optional but can be provided as first argument.
+#[macro_export]
+macro_rules! assert_serialized_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!($value, Default, @$snapshot);
+    }};
+    ($value:expr, {$($k:expr => $v:expr),*$(,)?}, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!($value, {$($k => $v),*}, Default, @$snapshot);
+    }};
+    ($value:expr, {$($k:expr => $v:expr),*$(,)?}) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, $value, {$($k => $v),*}, Default);
+    }};
+    ($name:expr, $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), $value, Default);
+    }};
+    ($name:expr, $value:expr, {$($k:expr => $v:expr),*$(,)?}) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), $value, {$($k => $v),*}, Default);
+    }};
+    ($value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, $value, Default);
+    }};
+}
+
 /// Asserts a `Serialize` snapshot in RON format.
 ///
 /// **Feature:** `ron` (disabled by default)
@@ -154,6 +313,7 @@ macro_rules! assert_yaml_snapshot {
 /// This works exactly like [`assert_yaml_snapshot!`]
 /// but serializes in [RON](https://github.com/ron-rs/ron/) format instead of
 /// YAML which retains some type information for more accurate comparisions.
+/// The snapshot is marked with `content_type: ron` in its metadata.
 ///
 /// Example:
 ///
@@ -170,6 +330,15 @@ macro_rules! assert_yaml_snapshot {
 #[cfg(feature = "ron")]
 #[macro_export]
 macro_rules! assert_ron_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!(|| $value, Ron, @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, || $value, Ron);
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), || $value, Ron);
+    }};
     ($value:expr, @$snapshot:literal) => {{
         $crate::_assert_serialized_snapshot!($value, Ron, @$snapshot);
     }};
@@ -210,6 +379,15 @@ macro_rules! assert_ron_snapshot {
 /// The snapshot name is optional but can be provided as first argument.
 #[macro_export]
 macro_rules! assert_json_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::_assert_serialized_snapshot!(|| $value, Json, @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::_assert_serialized_snapshot!($crate::_macro_support::AutoName, || $value, Json);
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::_assert_serialized_snapshot!(Some($name), || $value, Json);
+    }};
     ($value:expr, @$snapshot:literal) => {{
         $crate::_assert_serialized_snapshot!($value, Json, @$snapshot);
     }};
@@ -233,21 +411,47 @@ macro_rules! assert_json_snapshot {
 #[doc(hidden)]
 #[macro_export]
 macro_rules! _assert_serialized_snapshot {
+    (|| $value:expr, $format:ident, @$snapshot:literal) => {{
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            || $crate::_macro_support::serialize_value(
+                &$value,
+                $crate::_macro_support::SerializationFormat::$format,
+                $crate::_macro_support::SnapshotLocation::Inline
+            ),
+            stringify!($value)
+        );
+    }};
+    ($name:expr, || $value:expr, $format:ident) => {{
+        $crate::_assert_serialized_value!(
+            $name,
+            || $crate::_macro_support::serialize_value(
+                &$value,
+                $crate::_macro_support::SerializationFormat::$format,
+                $crate::_macro_support::SnapshotLocation::File
+            ),
+            stringify!($value)
+        );
+    }};
     ($value:expr, $format:ident, @$snapshot:literal) => {{
         let value = $crate::_macro_support::serialize_value(
             &$value,
             $crate::_macro_support::SerializationFormat::$format,
             $crate::_macro_support::SnapshotLocation::Inline
         );
-        $crate::assert_snapshot!(
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
             value,
-            stringify!($value),
-            @$snapshot
+            stringify!($value)
         );
     }};
     ($value:expr, {$($k:expr => $v:expr),*$(,)?}, $format:ident, @$snapshot:literal) => {{
         let (vec, value) = $crate::_prepare_snapshot_for_redaction!($value, {$($k => $v),*}, $format, Inline);
-        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            value,
+            stringify!($value)
+        );
     }};
     ($name:expr, $value:expr, $format:ident) => {{
         let value = $crate::_macro_support::serialize_value(
@@ -255,7 +459,7 @@ macro_rules! _assert_serialized_snapshot {
             $crate::_macro_support::SerializationFormat::$format,
             $crate::_macro_support::SnapshotLocation::File
         );
-        $crate::assert_snapshot!(
+        $crate::_assert_serialized_value!(
             $name,
             value,
             stringify!($value)
@@ -263,10 +467,39 @@ macro_rules! _assert_serialized_snapshot {
     }};
     ($name:expr, $value:expr, {$($k:expr => $v:expr),*$(,)?}, $format:ident) => {{
         let (vec, value) = $crate::_prepare_snapshot_for_redaction!($value, {$($k => $v),*}, $format, File);
-        $crate::assert_snapshot!($name, value, stringify!($value));
+        $crate::_assert_serialized_value!($name, value, stringify!($value));
     }}
 }
 
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _assert_serialized_value {
+    ($refval:expr, || $body:expr, $debug_expr:expr) => {
+        $crate::_macro_support::assert_serialized_snapshot_lazy(
+            $refval.into(),
+            || $body,
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            $debug_expr,
+        )
+        .unwrap()
+    };
+    ($refval:expr, $value:expr, $debug_expr:expr) => {
+        $crate::_macro_support::assert_serialized_snapshot(
+            $refval.into(),
+            $value,
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            $debug_expr,
+        )
+        .unwrap()
+    };
+}
+
 #[cfg(feature = "redactions")]
 #[doc(hidden)]
 #[macro_export]
@@ -308,6 +541,19 @@ macro_rules! _prepare_snapshot_for_redaction {
 /// The snapshot name is optional.
 #[macro_export]
 macro_rules! assert_debug_snapshot {
+    (|| $value:expr, @$snapshot:literal) => {{
+        $crate::assert_snapshot!(|| format!("{:#?}", $value), stringify!($value), @$snapshot);
+    }};
+    (|| $value:expr) => {{
+        $crate::assert_snapshot!(
+            $crate::_macro_support::AutoName,
+            || format!("{:#?}", $value),
+            stringify!($value)
+        );
+    }};
+    ($name:expr, || $value:expr) => {{
+        $crate::assert_snapshot!(Some($name), || format!("{:#?}", $value), stringify!($value));
+    }};
     ($value:expr, @$snapshot:literal) => {{
         let value = format!("{:#?}", $value);
         $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
@@ -343,6 +589,352 @@ macro_rules! assert_display_snapshot {
     }};
 }
 
+/// Asserts a snapshot of an iterator with one element per line.
+///
+/// The value needs to implement `IntoIterator` and the items need to
+/// implement the `fmt::Debug` trait.  Unlike [`assert_debug_snapshot!`] each
+/// item is rendered with the compact debug representation on its own line
+/// which keeps diffs small when a single element changes.
+///
+/// ```no_run
+/// # use insta::*;
+/// assert_lines_snapshot!(vec![1, 2, 3]);
+/// ```
+///
+/// The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_lines_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_format_lines!($value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_format_lines!($value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_format_lines!($value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _format_lines {
+    ($value:expr) => {
+        ::std::iter::IntoIterator::into_iter($value)
+            .map(|item| format!("{:?}", item))
+            .collect::<::std::vec::Vec<_>>()
+            .join("\n")
+    };
+}
+
+/// Asserts a snapshot of an error and its chain of sources.
+///
+/// The value needs to implement the `std::error::Error` trait.  The error
+/// and every error returned by walking [`source`](std::error::Error::source)
+/// are rendered with `fmt::Display` as numbered lines.  Hexadecimal numbers
+/// that look like memory addresses are replaced by `0x[addr]`.
+///
+/// ```no_run
+/// # use insta::*;
+/// let err = "x".parse::<i32>().unwrap_err();
+/// assert_error_chain_snapshot!(err, @"0: invalid digit found in string");
+/// ```
+///
+/// For boxed errors pass a reference to the inner error (`&*err`).
+///
+/// The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_error_chain_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::format_error_chain(&$value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::format_error_chain(&$value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::format_error_chain(&$value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a `Debug` snapshot of a value together with the name of its type.
+///
+/// The type name is obtained from [`std::any::type_name`] and written in
+/// front of the pretty printed `Debug` output.  Symbol hashes in the type
+/// name are removed so that the snapshot stays stable between builds.
+///
+/// ```no_run
+/// # use insta::*;
+/// assert_typed_snapshot!(Some(42u8), @r###"
+/// core::option::Option<u8>: Some(
+///     42,
+/// )
+/// "###);
+/// ```
+///
+/// Note that the exact type names are not guaranteed to be stable across
+/// Rust versions.
+///
+/// The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_typed_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::format_typed_value(&$value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::format_typed_value(&$value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::format_typed_value(&$value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a snapshot of what a closure writes to stdout.
+///
+/// **Feature:** `stdout` (disabled by default, unix only)
+///
+/// The closure is invoked while the stdout of the process is redirected and
+/// everything written to it is snapshotted.  This also captures the output
+/// of child processes that inherit stdout.  If the closure panics stdout is
+/// restored before the panic continues.
+///
+/// ```no_run,ignore
+/// assert_stdout_snapshot!(|| {
+///     std::io::stdout().write_all(b"Hello World!\n").unwrap();
+/// });
+/// ```
+///
+/// Note that the test harness captures output written with `print!` and
+/// `println!` before it reaches the process stdout.  Such output is only
+/// seen by this macro if tests are run with `--nocapture`, otherwise write
+/// to [`std::io::stdout`] directly.  As stdout is shared by the whole
+/// process, output of tests running in parallel, including the progress of
+/// the test harness, can end up in the snapshot.  Run such tests with
+/// `--test-threads=1` or keep them in their own test binary to avoid this.
+///
+/// The snapshot name is optional.
+#[cfg(all(feature = "stdout", unix))]
+#[macro_export]
+macro_rules! assert_stdout_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::capture_stdout($value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::capture_stdout($value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::capture_stdout($value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a snapshot of binary data.
+///
+/// The bytes are written into a file next to the snapshot file with an
+/// added `.bin` extension while the snapshot itself records the length and
+/// a hash of the bytes.  This way `cargo insta review` shows how the length
+/// and hash changed instead of a text diff.  The value can be anything that
+/// implements `AsRef<[u8]>`.
+///
+/// ```no_run,ignore
+/// assert_binary_snapshot!("thumbnail", render_thumbnail());
+/// ```
+///
+/// Binary snapshots cannot be inline.  The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_binary_snapshot {
+    ($name:expr, $value:expr) => {
+        $crate::_macro_support::assert_binary_snapshot(
+            Some($name).into(),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$value),
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            stringify!($value),
+        )
+        .unwrap()
+    };
+    ($value:expr) => {
+        $crate::_macro_support::assert_binary_snapshot(
+            $crate::_macro_support::AutoName.into(),
+            ::std::convert::AsRef::<[u8]>::as_ref(&$value),
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            stringify!($value),
+        )
+        .unwrap()
+    };
+}
+
+/// Asserts a snapshot of the exit status and output of a command.
+///
+/// The `std::process::Command` is run to completion and its exit status,
+/// stdout and stderr are snapshotted together.  The command can be passed
+/// by value or as a mutable reference.
+///
+/// ```no_run,ignore
+/// assert_command_snapshot!(Command::new("ls").arg("-l"));
+/// ```
+///
+/// The output is normalized before it is compared: ANSI escape sequences
+/// are removed and the temporary directory is replaced with `[TEMP_DIR]`.
+/// Both can be turned off with
+/// [`Settings::set_strip_ansi_escapes`](crate::Settings::set_strip_ansi_escapes)
+/// and [`Settings::set_redact_temp_dir`](crate::Settings::set_redact_temp_dir).
+/// If path separator normalization is enabled backslashes are rewritten to
+/// forward slashes.
+///
+/// The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_command_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::run_command($value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::run_command($value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::run_command($value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a snapshot of the files a closure writes into a folder.
+///
+/// The folder is scanned before and after the closure runs and every file
+/// that was created or modified is snapshotted with its path relative to the
+/// folder and its contents.  Files that are not valid UTF-8 are recorded by
+/// size and hash.  Files that existed before and were not changed are left
+/// out, which makes this useful for testing code generators and scaffolding
+/// tools.
+///
+/// ```no_run,ignore
+/// assert_fs_snapshot!("target/generated", || {
+///     generate_project("target/generated").unwrap();
+/// });
+/// ```
+///
+/// The snapshot name is optional.
+#[macro_export]
+macro_rules! assert_fs_snapshot {
+    ($dir:expr, $value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::capture_fs_changes($dir, $value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $dir:expr, $value:expr) => {{
+        let value = $crate::_macro_support::capture_fs_changes($dir, $value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($dir:expr, $value:expr) => {{
+        let value = $crate::_macro_support::capture_fs_changes($dir, $value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a snapshot of the `log` records a closure emits.
+///
+/// **Feature:** `log` (disabled by default)
+///
+/// A logger for the `log` crate is installed on first use and all records
+/// emitted on the current thread while the closure runs are snapshotted.
+/// Every record is written on its own line with level, target and message.
+/// Timestamps are not included so the output is stable.
+///
+/// ```no_run,ignore
+/// assert_log_snapshot!(|| {
+///     log::info!("Hello World!");
+/// }, @"INFO  my_crate: Hello World!");
+/// ```
+///
+/// As only one logger can be installed per process this cannot be combined
+/// with another logger in the same test binary.  Records emitted on other
+/// threads are not captured.
+///
+/// The snapshot name is optional.
+#[cfg(feature = "log")]
+#[macro_export]
+macro_rules! assert_log_snapshot {
+    ($value:expr, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::capture_log($value);
+        $crate::assert_snapshot!(value, stringify!($value), @$snapshot);
+    }};
+    ($name:expr, $value:expr) => {{
+        let value = $crate::_macro_support::capture_log($value);
+        $crate::assert_snapshot!(Some($name), value, stringify!($value));
+    }};
+    ($value:expr) => {{
+        let value = $crate::_macro_support::capture_log($value);
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, value, stringify!($value));
+    }};
+}
+
+/// Asserts a snapshot of the JSON schema of a type.
+///
+/// **Feature:** `schemars` (disabled by default)
+///
+/// The schema is generated with `schemars::schema_for!` so the type needs to
+/// implement `schemars::JsonSchema` and the crate using the macro needs to
+/// depend on `schemars`.  Unlike the other macros this snapshots the contract
+/// of a type rather than a value so that accidental schema changes show up
+/// in review.  The schema is stored as JSON with all maps sorted.
+///
+/// ```no_run,ignore
+/// assert_schema_snapshot!(User);
+/// ```
+///
+/// The snapshot name is optional but can be provided as a literal first
+/// argument.
+#[cfg(feature = "schemars")]
+#[macro_export]
+macro_rules! assert_schema_snapshot {
+    ($name:literal, $type:ty) => {{
+        let value = $crate::_macro_support::serialize_value_sorted(
+            &schemars::schema_for!($type),
+            $crate::_macro_support::SerializationFormat::Json,
+            $crate::_macro_support::SnapshotLocation::File,
+        );
+        $crate::_assert_serialized_value!(Some($name), value, stringify!($type));
+    }};
+    ($type:ty, @$snapshot:literal) => {{
+        let value = $crate::_macro_support::serialize_value_sorted(
+            &schemars::schema_for!($type),
+            $crate::_macro_support::SerializationFormat::Json,
+            $crate::_macro_support::SnapshotLocation::Inline,
+        );
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            value,
+            stringify!($type)
+        );
+    }};
+    ($type:ty) => {{
+        let value = $crate::_macro_support::serialize_value_sorted(
+            &schemars::schema_for!($type),
+            $crate::_macro_support::SerializationFormat::Json,
+            $crate::_macro_support::SnapshotLocation::File,
+        );
+        $crate::_assert_serialized_value!(
+            $crate::_macro_support::AutoName,
+            value,
+            stringify!($type)
+        );
+    }};
+}
+
 /// Asserts a string snapshot.
 ///
 /// This is the most simplistic of all assertion methods.  It just accepts
@@ -358,9 +950,80 @@ macro_rules! assert_display_snapshot {
 /// stringified as debug expression.  For more information on this look at the
 /// source of this macro and other assertion macros.
 ///
+/// Instead of a value a format string with arguments can be given after
+/// `format =`.  The snapshot is the result of `format!` and the format
+/// string is stored as expression:
+///
+/// ```no_run
+/// # use insta::*; let value = 42;
+/// assert_snapshot!(format = "label: {}", value);
+/// assert_snapshot!("named", format = "label: {}", value);
+/// ```
+///
+/// The format string needs the `format =` prefix because a bare format
+/// string is indistinguishable from a snapshot name to the macro:
+/// `assert_snapshot!("label: {}", value)` has the same shape as the named
+/// `assert_snapshot!("name", value)` and with more arguments it clashes with
+/// the form that passes the expression to record.  Such calls keep snapshotting
+/// `value` under the name `"label: {}"`.
+///
+/// Values that are expensive to compute can be given as closure without
+/// arguments instead.  The closure is only invoked if the outcome of the
+/// assertion matters which is not the case if assertions are forced to pass
+/// (`INSTA_FORCE_PASS=1`) and no snapshots are written (`INSTA_UPDATE=no`).
+/// This also works with [`assert_debug_snapshot!`] and the macros of the
+/// serialization formats but not together with redactions:
+///
+/// ```no_run
+/// # use insta::*; fn render_report() -> String { String::new() }
+/// assert_snapshot!("report", || render_report());
+/// ```
+///
 /// The snapshot name is optional.
 #[macro_export]
 macro_rules! assert_snapshot {
+    (|| $body:expr, @$snapshot:literal) => {
+        $crate::assert_snapshot!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            || $body,
+            stringify!($body)
+        )
+    };
+    (|| $body:expr, $debug_expr:expr, @$snapshot:literal) => {
+        $crate::assert_snapshot!(
+            $crate::_macro_support::ReferenceValue::Inline($snapshot),
+            || $body,
+            $debug_expr
+        )
+    };
+    (|| $body:expr) => {
+        $crate::assert_snapshot!($crate::_macro_support::AutoName, || $body, stringify!($body))
+    };
+    ($name:expr, || $body:expr) => {
+        $crate::assert_snapshot!($name, || $body, stringify!($body))
+    };
+    ($name:expr, || $body:expr, $debug_expr:expr) => {
+        $crate::_macro_support::assert_snapshot_lazy(
+            $name.into(),
+            || ::std::string::String::from(&*$body),
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            $debug_expr,
+        )
+        .unwrap()
+    };
+    (format = $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::assert_snapshot!(
+            $crate::_macro_support::AutoName,
+            format!($fmt $(, $arg)*),
+            $fmt
+        )
+    };
+    ($name:expr, format = $fmt:literal $(, $arg:expr)* $(,)?) => {
+        $crate::assert_snapshot!(Some($name), format!($fmt $(, $arg)*), $fmt)
+    };
     ($value:expr, @$snapshot:literal) => {
         $crate::assert_snapshot!(
             $crate::_macro_support::ReferenceValue::Inline($snapshot),
@@ -396,6 +1059,66 @@ macro_rules! assert_snapshot {
     };
 }
 
+/// Asserts a string against a golden file.
+///
+/// The first argument must be an `include_str!` of the golden file so that
+/// the path of the file is known.  The value is compared against the
+/// included string and mismatches are handled like in
+/// [`assert_snapshot_matches_file!`]: a pending snapshot is written next to
+/// the golden file for `cargo insta review` or with `INSTA_UPDATE=always`
+/// the file is rewritten.
+///
+/// ```ignore
+/// # use insta::*;
+/// assert_snapshot_against!(include_str!("golden.txt"), "reference value");
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot_against {
+    (include_str!($golden_file:literal), $value:expr $(,)?) => {
+        $crate::_macro_support::assert_golden_file(
+            include_str!($golden_file),
+            $golden_file,
+            &$value,
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            stringify!($value),
+        )
+        .unwrap()
+    };
+}
+
+/// Asserts a string against a golden file read at runtime.
+///
+/// This is useful for large reference outputs that are kept outside of the
+/// `snapshots` folder.  The path of the golden file is relative to the file
+/// of the assertion, like the base of `glob!`.  The file contains the
+/// plain value without a metadata header.  On a mismatch a pending snapshot
+/// is written next to the golden file so that the change can be reviewed
+/// with `cargo insta review`, accepting it rewrites the golden file.  With
+/// `INSTA_UPDATE=always` the golden file is rewritten directly.
+///
+/// ```no_run
+/// # use insta::*;
+/// assert_snapshot_matches_file!("reference value", "golden/reference.txt");
+/// ```
+#[macro_export]
+macro_rules! assert_snapshot_matches_file {
+    ($value:expr, $golden_file:expr $(,)?) => {
+        $crate::_macro_support::assert_matches_golden_file(
+            $golden_file,
+            &$value,
+            env!("CARGO_MANIFEST_DIR"),
+            module_path!(),
+            file!(),
+            line!(),
+            stringify!($value),
+        )
+        .unwrap()
+    };
+}
+
 /// Settings configuration macro.
 ///
 /// This macro lets you bind some settings temporarily.  The first argument
@@ -421,17 +1144,45 @@ macro_rules! with_settings {
 
 /// Executes a closure for all input files matching a glob.
 ///
-/// The closure is passed the path to the file.
+/// The closure is passed the path to the file.  A closure with two
+/// arguments is also passed the path relative to the folder of the source
+/// file which the glob is resolved against:
+///
+/// ```rust,ignore
+/// insta::glob!("inputs/**/*.txt", |path, rel_path| {
+///     let contents = std::fs::read_to_string(path).unwrap();
+///     insta::assert_snapshot!(contents);
+/// });
+/// ```
+///
+/// The file name is used as snapshot suffix so that every file gets its own
+/// snapshot.  If files in different folders share a name the relative path
+/// is used instead.
 #[cfg(feature = "glob")]
 #[macro_export]
 macro_rules! glob {
+    ($glob:expr, |$path:pat, $rel_path:pat| $body:expr) => {{
+        let base = $crate::_glob_base!();
+        $crate::_macro_support::glob_exec_with_relative_path(&base, $glob, |$path, $rel_path| {
+            $body
+        });
+    }};
     ($glob:expr, $closure:expr) => {{
-        let base = $crate::_macro_support::get_cargo_workspace(env!("CARGO_MANIFEST_DIR"))
+        let base = $crate::_glob_base!();
+        $crate::_macro_support::glob_exec(&base, $glob, $closure);
+    }};
+}
+
+#[cfg(feature = "glob")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! _glob_base {
+    () => {
+        $crate::_macro_support::get_cargo_workspace(env!("CARGO_MANIFEST_DIR"))
             .join(file!())
             .parent()
             .unwrap()
             .canonicalize()
-            .unwrap_or_else(|e| panic!("failed to canonicalize insta::glob! base path: {}", e));
-        $crate::_macro_support::glob_exec(&base, $glob, $closure);
-    }};
+            .unwrap_or_else(|e| panic!("failed to canonicalize insta::glob! base path: {}", e))
+    };
 }
@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, RwLock};
+
+use lazy_static::lazy_static;
+
+use crate::content::Content;
+
+type Normalizer = Arc<dyn Fn(Content) -> Content + Send + Sync>;
+
+lazy_static! {
+    static ref NORMALIZERS: RwLock<BTreeMap<String, Normalizer>> = RwLock::new(BTreeMap::new());
+}
+
+/// Registers a normalizer under a name.
+///
+/// Normalizers change the value of a snapshot before it is serialized and
+/// compared.  Once registered they can be enabled by name for individual
+/// assertions with [`Settings::add_normalizer`](crate::Settings::add_normalizer)
+/// or the `normalizers` key of [`with_settings!`].  Registering a name again
+/// replaces the previous normalizer.
+///
+/// Note that this only applies to snapshots that undergo serialization
+/// (eg: does not work for `assert_debug_snapshot!`.)
+///
+/// ```no_run
+/// use insta::internals::Content;
+///
+/// insta::register_normalizer("strip-timestamps", |content| {
+///     match content {
+///         Content::Map(entries) => Content::Map(
+///             entries
+///                 .into_iter()
+///                 .filter(|(key, _)| key.as_str() != Some("timestamp"))
+///                 .collect(),
+///         ),
+///         other => other,
+///     }
+/// });
+/// ```
+pub fn register_normalizer<F>(name: &str, func: F)
+where
+    F: Fn(Content) -> Content + Send + Sync + 'static,
+{
+    NORMALIZERS
+        .write()
+        .unwrap()
+        .insert(name.to_string(), Arc::new(func));
+}
+
+/// Applies the normalizers with the given names in order.
+///
+/// Panics if a normalizer was not registered.
+pub(crate) fn apply_normalizers(mut content: Content, names: &[String]) -> Content {
+    for name in names {
+        let func = NORMALIZERS
+            .read()
+            .unwrap()
+            .get(name)
+            .cloned()
+            .unwrap_or_else(|| panic!("normalizer '{}' is not registered", name));
+        content = func(content);
+    }
+    content
+}
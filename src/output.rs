@@ -1,7 +1,16 @@
-use std::{path::Path, time::Duration};
+use std::{
+    env, fs,
+    io::{self, Write},
+    ops::{Range, RangeInclusive},
+    path::Path,
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
 
-use similar::{Algorithm, ChangeTag, TextDiff};
+use similar::{group_diff_ops, Algorithm, ChangeTag, DiffOp, DiffTag, TextDiff};
 
+use crate::env::{get_diff_style, get_diff_tool, get_max_width, DiffStyle};
 use crate::snapshot::Snapshot;
 use crate::utils::{format_rust_expression, style, term_width};
 
@@ -30,6 +39,10 @@ pub fn print_snapshot_summary(
         println!("Snapshot: {}", style("<inline>").dim());
     }
 
+    if let Some(description) = snapshot.metadata().description() {
+        println!("Description: {}", style(description).cyan());
+    }
+
     if let Some(ref value) = snapshot.metadata().get_relative_source(workspace_root) {
         println!(
             "Source: {}{}",
@@ -45,6 +58,34 @@ pub fn print_snapshot_summary(
     if let Some(ref value) = snapshot.metadata().input_file() {
         println!("Input file: {}", style(value).cyan());
     }
+
+    if let Some(tags) = snapshot.metadata().tags() {
+        for (key, value) in tags {
+            println!("Tag: {} = {}", style(key).yellow(), style(value).cyan());
+        }
+    }
+}
+
+/// Prints a snapshot together with its metadata.
+pub fn print_snapshot(workspace_root: &Path, snapshot: &Snapshot, snapshot_file: Option<&Path>) {
+    print_snapshot_summary(workspace_root, snapshot, snapshot_file, None);
+    if snapshot.metadata().expected_failure() {
+        println!("Expected failure: {}", style("yes").yellow());
+    }
+    if snapshot.metadata().frozen() {
+        println!("Frozen: {}", style("yes").yellow());
+    }
+
+    let width = term_width();
+    if let Some(expr) = snapshot.metadata().expression() {
+        println!("{:─^1$}", "", width);
+        println!("{}", style(format_rust_expression(expr)));
+    }
+    println!("──────┬{:─^1$}", "", width.saturating_sub(7));
+    for (idx, line) in snapshot.contents_str().lines().enumerate() {
+        println!("{:>5} │ {}", style(idx + 1).cyan().dim(), line);
+    }
+    println!("──────┴{:─^1$}", "", width.saturating_sub(7));
 }
 
 /// Prints a diff against an old snapshot.
@@ -54,6 +95,7 @@ pub fn print_snapshot_diff(
     old_snapshot: Option<&Snapshot>,
     snapshot_file: Option<&Path>,
     line: Option<u32>,
+    diff_style: DiffStyle,
 ) {
     print_snapshot_summary(workspace_root, new, snapshot_file, line);
     let old_contents = old_snapshot.as_ref().map_or("", |x| x.contents_str());
@@ -64,11 +106,399 @@ pub fn print_snapshot_diff(
     } else {
         println!("{}", style("+new results").green());
     }
+    print_diff_stats(old_contents, new_contents);
+    if let Some(tool) = get_diff_tool() {
+        if run_diff_tool(&tool, old_contents, new_contents).is_ok() {
+            return;
+        }
+    }
+    #[cfg(feature = "csv")]
+    {
+        if new.metadata().content_type() == Some("csv") {
+            if let Some((old_contents, new_contents)) =
+                align_csv_columns(old_contents, new_contents)
+            {
+                print_changeset(
+                    &old_contents,
+                    &new_contents,
+                    new.metadata().expression.as_deref(),
+                    diff_style,
+                );
+                return;
+            }
+        }
+    }
     print_changeset(
         old_contents,
         new_contents,
         new.metadata().expression.as_deref(),
+        diff_style,
+    );
+}
+
+/// Shows the diff of two texts with an external tool.
+///
+/// The texts are written to temporary files whose paths are passed to the
+/// tool after the arguments given in `INSTA_DIFF_TOOL`.  Fails if the tool
+/// cannot be started.
+fn run_diff_tool(tool: &str, old: &str, new: &str) -> io::Result<()> {
+    static DIFF_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut args = tool.split_whitespace();
+    let program = args.next().unwrap_or_default();
+    let prefix = format!(
+        "insta-{}-{}",
+        process::id(),
+        DIFF_COUNT.fetch_add(1, Ordering::Relaxed)
     );
+    let old_path = env::temp_dir().join(format!("{}-old.snap", prefix));
+    let new_path = env::temp_dir().join(format!("{}-new.snap", prefix));
+    fs::write(&old_path, old)?;
+    fs::write(&new_path, new)?;
+
+    io::stdout().flush().ok();
+    // diff tools report differences with their exit code which is ignored
+    let rv = process::Command::new(program)
+        .args(args)
+        .arg(&old_path)
+        .arg(&new_path)
+        .status()
+        .map(|_| ());
+    fs::remove_file(&old_path).ok();
+    fs::remove_file(&new_path).ok();
+    rv
+}
+
+/// Lines up the columns of two CSV texts.
+///
+/// Every column gets the same width in both texts so that changed cells are
+/// easy to spot in the diff.  Returns `None` if either text is not valid CSV.
+#[cfg(feature = "csv")]
+fn align_csv_columns(old: &str, new: &str) -> Option<(String, String)> {
+    let parse = |contents: &str| -> Option<Vec<Vec<String>>> {
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .from_reader(contents.as_bytes())
+            .records()
+            .map(|record| {
+                record
+                    .ok()
+                    .map(|record| record.iter().map(|x| x.to_string()).collect())
+            })
+            .collect()
+    };
+    let old_rows = parse(old)?;
+    let new_rows = parse(new)?;
+
+    let mut widths: Vec<usize> = vec![];
+    for row in old_rows.iter().chain(new_rows.iter()) {
+        for (idx, field) in row.iter().enumerate() {
+            let width = field.chars().count();
+            match widths.get_mut(idx) {
+                Some(max_width) => *max_width = (*max_width).max(width),
+                None => widths.push(width),
+            }
+        }
+    }
+
+    let render = |rows: &[Vec<String>]| {
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(widths.iter())
+                    .map(|(field, width)| format!("{:width$}", field, width = width))
+                    .collect::<Vec<_>>()
+                    .join(" | ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Some((render(&old_rows), render(&new_rows)))
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_align_csv_columns() {
+    let (old, new) = align_csv_columns("id,name\n1,Alice", "id,name\n10,Bob").unwrap();
+    assert_eq!(old, "id | name\n1  | Alice");
+    assert_eq!(new, "id | name\n10 | Bob");
+}
+
+/// The kind of a [`DiffHunk`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffHunkKind {
+    /// The lines are the same in both texts.
+    Equal,
+    /// The lines were added to the new text.
+    Insert,
+    /// The lines were removed from the old text.
+    Delete,
+}
+
+/// A range of lines that were added, removed or left unchanged.
+///
+/// Line numbers start at zero.  For inserted lines the old range is empty
+/// and marks where the lines were added, for removed lines the same is true
+/// for the new range.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffHunk {
+    /// What happened to the lines.
+    pub kind: DiffHunkKind,
+    /// The lines in the old text.
+    pub old_range: Range<usize>,
+    /// The lines in the new text.
+    pub new_range: Range<usize>,
+}
+
+impl DiffHunk {
+    /// Returns `true` if the lines were added or removed.
+    pub fn is_change(&self) -> bool {
+        self.kind != DiffHunkKind::Equal
+    }
+}
+
+/// Diffs two texts line by line.
+///
+/// This is the diff that `cargo insta review` renders.  A replaced block of
+/// lines is reported as removed lines followed by added lines.
+pub fn diff_lines(old: &str, new: &str) -> Vec<DiffHunk> {
+    diff_hunks(&make_diff(old, new))
+}
+
+/// Splits a line diff into hunks.
+fn diff_hunks(diff: &TextDiff<'_, '_, '_, str>) -> Vec<DiffHunk> {
+    let mut rv = Vec::new();
+    for op in diff.ops() {
+        let (tag, old_range, new_range) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => rv.push(DiffHunk {
+                kind: DiffHunkKind::Equal,
+                old_range,
+                new_range,
+            }),
+            DiffTag::Insert => rv.push(DiffHunk {
+                kind: DiffHunkKind::Insert,
+                old_range,
+                new_range,
+            }),
+            DiffTag::Delete => rv.push(DiffHunk {
+                kind: DiffHunkKind::Delete,
+                old_range,
+                new_range,
+            }),
+            DiffTag::Replace => {
+                rv.push(DiffHunk {
+                    kind: DiffHunkKind::Delete,
+                    old_range: old_range.clone(),
+                    new_range: new_range.start..new_range.start,
+                });
+                rv.push(DiffHunk {
+                    kind: DiffHunkKind::Insert,
+                    old_range: old_range.end..old_range.end,
+                    new_range,
+                });
+            }
+        }
+    }
+    rv
+}
+
+/// Turns hunks back into diff operations for rendering.
+///
+/// Removed lines that are followed by added lines become a replacement so
+/// that the changed parts of the lines can be highlighted.
+fn hunks_to_ops(hunks: &[DiffHunk]) -> Vec<DiffOp> {
+    let mut rv = Vec::new();
+    let mut iter = hunks.iter().peekable();
+    while let Some(hunk) = iter.next() {
+        rv.push(match hunk.kind {
+            DiffHunkKind::Equal => DiffOp::Equal {
+                old_index: hunk.old_range.start,
+                new_index: hunk.new_range.start,
+                len: hunk.old_range.len(),
+            },
+            DiffHunkKind::Delete => match iter.peek() {
+                Some(next) if next.kind == DiffHunkKind::Insert => {
+                    let next = iter.next().unwrap();
+                    DiffOp::Replace {
+                        old_index: hunk.old_range.start,
+                        old_len: hunk.old_range.len(),
+                        new_index: next.new_range.start,
+                        new_len: next.new_range.len(),
+                    }
+                }
+                _ => DiffOp::Delete {
+                    old_index: hunk.old_range.start,
+                    old_len: hunk.old_range.len(),
+                    new_index: hunk.new_range.start,
+                },
+            },
+            DiffHunkKind::Insert => DiffOp::Insert {
+                old_index: hunk.old_range.start,
+                new_index: hunk.new_range.start,
+                new_len: hunk.new_range.len(),
+            },
+        });
+    }
+    rv
+}
+
+#[test]
+fn test_diff_lines() {
+    let hunks = diff_lines("a\nb\nc\n", "a\nB\nc\nd\n");
+    assert_eq!(
+        hunks,
+        vec![
+            DiffHunk {
+                kind: DiffHunkKind::Equal,
+                old_range: 0..1,
+                new_range: 0..1,
+            },
+            DiffHunk {
+                kind: DiffHunkKind::Delete,
+                old_range: 1..2,
+                new_range: 1..1,
+            },
+            DiffHunk {
+                kind: DiffHunkKind::Insert,
+                old_range: 2..2,
+                new_range: 1..2,
+            },
+            DiffHunk {
+                kind: DiffHunkKind::Equal,
+                old_range: 2..3,
+                new_range: 2..3,
+            },
+            DiffHunk {
+                kind: DiffHunkKind::Insert,
+                old_range: 3..3,
+                new_range: 3..4,
+            },
+        ]
+    );
+    assert!(diff_lines("a\n", "a\n").iter().all(|x| !x.is_change()));
+}
+
+#[test]
+fn test_hunks_to_ops() {
+    let diff = make_diff("a\nb\nc\n", "a\nB\nc\nd\n");
+    assert_eq!(hunks_to_ops(&diff_hunks(&diff)), diff.ops());
+}
+
+/// Counts the added and removed lines between two texts.
+pub fn count_changed_lines(old: &str, new: &str) -> (usize, usize) {
+    let mut added = 0;
+    let mut removed = 0;
+    for hunk in diff_lines(old, new) {
+        match hunk.kind {
+            DiffHunkKind::Insert => added += hunk.new_range.len(),
+            DiffHunkKind::Delete => removed += hunk.old_range.len(),
+            DiffHunkKind::Equal => {}
+        }
+    }
+    (added, removed)
+}
+
+/// Prints how many lines and bytes were added and removed.
+fn print_diff_stats(old: &str, new: &str) {
+    let (added, removed) = count_changed_lines(old, new);
+    let bytes = new.len() as i64 - old.len() as i64;
+    let bytes = if bytes > 0 {
+        style(format!("+{} bytes", bytes)).green()
+    } else if bytes < 0 {
+        style(format!("{} bytes", bytes)).red()
+    } else {
+        style("0 bytes".to_string())
+    };
+    println!(
+        "{}, {}, {}",
+        style(format!("+{} lines", added)).green(),
+        style(format!("-{} lines", removed)).red(),
+        bytes
+    );
+}
+
+/// The kind of changes between an old and a new snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffKind {
+    /// Lines were only added.
+    Additive,
+    /// Lines were only removed.
+    Removed,
+    /// Lines were both added and removed.
+    Mixed,
+}
+
+impl DiffKind {
+    /// Returns `true` if no lines were removed.
+    pub fn is_additive(self) -> bool {
+        self == DiffKind::Additive
+    }
+}
+
+/// Classifies the diff between an old and a new snapshot.
+pub fn classify_snapshot_diff(new: &Snapshot, old_snapshot: Option<&Snapshot>) -> DiffKind {
+    let old_contents = old_snapshot.as_ref().map_or("", |x| x.contents_str());
+    let (added, removed) = count_changed_lines(old_contents, new.contents_str());
+    match (added > 0, removed > 0) {
+        (_, false) => DiffKind::Additive,
+        (false, true) => DiffKind::Removed,
+        (true, true) => DiffKind::Mixed,
+    }
+}
+
+/// Merges two texts by only taking the changes within a range of lines.
+///
+/// The range refers to the lines of the new text as they are numbered in
+/// diffs.  Changes outside of the range are reverted to the old text.
+pub fn merge_line_range(old: &str, new: &str, lines: RangeInclusive<usize>) -> String {
+    let diff = make_diff(old, new);
+    let mut rv = String::new();
+    let mut new_line = 0;
+    for change in diff.iter_all_changes() {
+        let keep = match change.tag() {
+            ChangeTag::Equal => true,
+            ChangeTag::Insert => lines.contains(&new_line),
+            // removed lines are positioned before the next line of the new text
+            ChangeTag::Delete => !lines.contains(&new_line),
+        };
+        if keep {
+            rv.push_str(change.value());
+            // the last line might lack a newline but no longer be last
+            if !rv.ends_with('\n') {
+                rv.push('\n');
+            }
+        }
+        if change.tag() != ChangeTag::Delete {
+            new_line += 1;
+        }
+    }
+    if !new.ends_with('\n') && rv.ends_with('\n') {
+        rv.pop();
+    }
+    rv
+}
+
+#[test]
+fn test_merge_line_range() {
+    let old = "a\nb\nc\nd\ne";
+    let new = "a\nB\nc\nD\ne\nf";
+    assert_eq!(merge_line_range(old, new, 0..=2), "a\nB\nc\nd\ne");
+    assert_eq!(merge_line_range(old, new, 3..=5), "a\nb\nc\nD\ne\nf");
+    assert_eq!(merge_line_range(old, new, 0..=5), new);
+    assert_eq!(merge_line_range(old, new, 6..=6), old);
+}
+
+/// Formats the changes between two texts as a unified diff.
+pub fn format_unified_diff(old: &str, new: &str, old_header: &str, new_header: &str) -> String {
+    make_diff(old, new)
+        .unified_diff()
+        .context_radius(3)
+        .header(old_header, new_header)
+        .to_string()
 }
 
 pub fn print_snapshot_diff_with_title(
@@ -90,6 +520,7 @@ pub fn print_snapshot_diff_with_title(
         old_snapshot,
         snapshot_file,
         Some(line),
+        get_diff_style(),
     );
 }
 
@@ -111,28 +542,203 @@ pub fn print_snapshot_summary_with_title(
     println!("{title:━^width$}", title = "", width = width);
 }
 
-pub fn print_changeset(old: &str, new: &str, expr: Option<&str>) {
-    let width = term_width();
-    let diff = TextDiff::configure()
+fn make_diff<'a>(old: &'a str, new: &'a str) -> TextDiff<'a, 'a, 'a, str> {
+    TextDiff::configure()
         .algorithm(Algorithm::Patience)
         .timeout(Duration::from_millis(500))
-        .diff_lines(old, new);
+        .diff_lines(old, new)
+}
+
+/// Prints a part of a line of a diff.
+///
+/// If a limit is given the text is wrapped onto continuation lines once the
+/// column reaches the limit.
+fn print_wrapped<F: Fn(&str) -> String>(
+    text: &str,
+    column: &mut usize,
+    limit: Option<usize>,
+    styled: F,
+) {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => {
+            print!("{}", styled(text));
+            return;
+        }
+    };
+    let mut start = 0;
+    for (idx, c) in text.char_indices() {
+        if c == '\n' {
+            continue;
+        }
+        if *column >= limit {
+            print!("{}\n{:>5} {:>5} │ ", styled(&text[start..idx]), "", "");
+            start = idx;
+            *column = 0;
+        }
+        *column += 1;
+    }
+    print!("{}", styled(&text[start..]));
+}
+
+/// Below this width side-by-side diffs are rendered as unified diffs.
+const MIN_SIDE_BY_SIDE_WIDTH: usize = 80;
+
+/// Splits a line into chunks of at most `width` characters.
+fn split_columns(text: &str, width: usize) -> Vec<String> {
+    let chars: Vec<char> = text.replace('\t', "    ").chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars.chunks(width).map(|x| x.iter().collect()).collect()
+}
+
+#[test]
+fn test_split_columns() {
+    assert_eq!(split_columns("", 3), vec![""]);
+    assert_eq!(split_columns("abcdefg", 3), vec!["abc", "def", "g"]);
+    assert_eq!(split_columns("a\tb", 4), vec!["a   ", " b"]);
+}
+
+/// Prints a diff with the old lines on the left and the new ones on the right.
+///
+/// Lines longer than a column are wrapped onto continuation rows.
+fn print_side_by_side(old: &str, new: &str, hunks: &[DiffHunk], width: usize) {
+    let has_changes = hunks.iter().any(DiffHunk::is_change);
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let column = (width - 17) / 2;
+    let rule = |left: char, cross: char| {
+        let bar = left.to_string().repeat(column + 1);
+        let gutter = left.to_string().repeat(6);
+        format!(
+            "{}{}{}{}{}{}{}",
+            gutter, cross, bar, cross, gutter, cross, bar
+        )
+    };
+
+    // each side of a row is the line number, the tag and the text of a line
+    let print_row = |left: Option<(usize, ChangeTag)>, right: Option<(usize, ChangeTag)>| {
+        let left_chunks = left.map_or_else(Vec::new, |(idx, _)| {
+            split_columns(old_lines.get(idx).copied().unwrap_or(""), column)
+        });
+        let right_chunks = right.map_or_else(Vec::new, |(idx, _)| {
+            split_columns(new_lines.get(idx).copied().unwrap_or(""), column)
+        });
+        for row in 0..left_chunks.len().max(right_chunks.len()) {
+            let mut line = String::new();
+            for (side, chunks, is_new) in
+                &[(left, &left_chunks, false), (right, &right_chunks, true)]
+            {
+                let (number, marker, text) = match (side, chunks.get(row)) {
+                    (Some((idx, tag)), Some(text)) => {
+                        let number = if row == 0 {
+                            style(format!("{:>5}", idx)).cyan().dim().to_string()
+                        } else {
+                            " ".repeat(5)
+                        };
+                        let padded = format!("{:<1$}", text, column);
+                        match tag {
+                            ChangeTag::Delete => (
+                                number,
+                                style("-").red().to_string(),
+                                style(padded).red().to_string(),
+                            ),
+                            ChangeTag::Insert => (
+                                number,
+                                style("+").green().to_string(),
+                                style(padded).green().to_string(),
+                            ),
+                            ChangeTag::Equal => {
+                                (number, " ".to_string(), style(padded).dim().to_string())
+                            }
+                        }
+                    }
+                    _ => (" ".repeat(5), " ".to_string(), " ".repeat(column)),
+                };
+                if *is_new {
+                    line.push('│');
+                }
+                line.push_str(&format!("{} │{}{}", number, marker, text));
+            }
+            println!("{}", line.trim_end());
+        }
+    };
+
+    println!("{}", rule('─', '┬'));
+    for (idx, group) in group_diff_ops(hunks_to_ops(hunks), 4).iter().enumerate() {
+        if idx > 0 {
+            println!("{}", rule('┈', '┼'));
+        }
+        for op in group {
+            let (tag, old_range, new_range) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal => {
+                    for (old_idx, new_idx) in old_range.zip(new_range) {
+                        print_row(
+                            Some((old_idx, ChangeTag::Equal)),
+                            Some((new_idx, ChangeTag::Equal)),
+                        );
+                    }
+                }
+                // removed and added lines are paired up next to each other
+                DiffTag::Delete | DiffTag::Insert | DiffTag::Replace => {
+                    let rows = old_range.len().max(new_range.len());
+                    for row in 0..rows {
+                        print_row(
+                            Some(old_range.start + row)
+                                .filter(|x| old_range.contains(x))
+                                .map(|x| (x, ChangeTag::Delete)),
+                            Some(new_range.start + row)
+                                .filter(|x| new_range.contains(x))
+                                .map(|x| (x, ChangeTag::Insert)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    if !has_changes {
+        println!(
+            "{:>5} │{}",
+            style("-").dim(),
+            style(" snapshots are matching").cyan(),
+        );
+    }
+
+    println!("{}", rule('─', '┴'));
+}
+
+pub fn print_changeset(old: &str, new: &str, expr: Option<&str>, diff_style: DiffStyle) {
+    let width = term_width();
+    let diff = make_diff(old, new);
+    let hunks = diff_hunks(&diff);
+    let has_changes = hunks.iter().any(DiffHunk::is_change);
+    // long lines are only wrapped if a maximum width was requested, otherwise
+    // the terminal takes care of it.
+    let wrap_at = get_max_width().map(|_| width.saturating_sub(14).max(10));
 
     if let Some(expr) = expr {
         println!("{:─^1$}", "", width,);
         println!("{}", style(format_rust_expression(expr)));
     }
+
+    // narrow terminals fall back to unified diffs
+    if diff_style == DiffStyle::SideBySide && width >= MIN_SIDE_BY_SIDE_WIDTH {
+        print_side_by_side(old, new, &hunks, width);
+        return;
+    }
     println!("────────────┬{:─^1$}", "", width.saturating_sub(13));
-    let mut has_changes = false;
-    for (idx, group) in diff.grouped_ops(4).iter().enumerate() {
+    for (idx, group) in group_diff_ops(hunks_to_ops(&hunks), 4).iter().enumerate() {
         if idx > 0 {
             println!("┈┈┈┈┈┈┈┈┈┈┈┈┼{:┈^1$}", "", width.saturating_sub(13));
         }
         for op in group {
             for change in diff.iter_inline_changes(&op) {
+                let mut column = 0;
                 match change.tag() {
                     ChangeTag::Insert => {
-                        has_changes = true;
                         print!(
                             "{:>5} {:>5} │{}",
                             "",
@@ -140,15 +746,16 @@ pub fn print_changeset(old: &str, new: &str, expr: Option<&str>) {
                             style("+").green(),
                         );
                         for &(emphasized, change) in change.values() {
-                            if emphasized {
-                                print!("{}", style(change).green().underlined());
-                            } else {
-                                print!("{}", style(change).green());
-                            }
+                            print_wrapped(change, &mut column, wrap_at, |x| {
+                                if emphasized {
+                                    style(x).green().underlined().to_string()
+                                } else {
+                                    style(x).green().to_string()
+                                }
+                            });
                         }
                     }
                     ChangeTag::Delete => {
-                        has_changes = true;
                         print!(
                             "{:>5} {:>5} │{}",
                             style(change.old_index().unwrap()).cyan().dim(),
@@ -156,11 +763,13 @@ pub fn print_changeset(old: &str, new: &str, expr: Option<&str>) {
                             style("-").red(),
                         );
                         for &(emphasized, change) in change.values() {
-                            if emphasized {
-                                print!("{}", style(change).red().underlined());
-                            } else {
-                                print!("{}", style(change).red());
-                            }
+                            print_wrapped(change, &mut column, wrap_at, |x| {
+                                if emphasized {
+                                    style(x).red().underlined().to_string()
+                                } else {
+                                    style(x).red().to_string()
+                                }
+                            });
                         }
                     }
                     ChangeTag::Equal => {
@@ -170,7 +779,9 @@ pub fn print_changeset(old: &str, new: &str, expr: Option<&str>) {
                             style(change.new_index().unwrap()).cyan().dim().bold(),
                         );
                         for &(_, change) in change.values() {
-                            print!("{}", style(change).dim());
+                            print_wrapped(change, &mut column, wrap_at, |x| {
+                                style(x).dim().to_string()
+                            });
                         }
                     }
                 }
@@ -0,0 +1,152 @@
+use std::fmt::Write;
+
+use crate::content::Content;
+
+/// Renders a message in the protobuf text format.
+///
+/// Unset optional fields are omitted, repeated fields repeat the field name
+/// and the entries of map fields are sorted by key so that the output is
+/// deterministic.
+pub fn to_proto_text(content: &Content) -> String {
+    let mut rv = String::new();
+    write_fields(&mut rv, content, 0);
+    if rv.ends_with('\n') {
+        rv.truncate(rv.len() - 1);
+    }
+    rv
+}
+
+fn write_fields(out: &mut String, content: &Content, indent: usize) {
+    match content.resolve_inner() {
+        Content::Struct(_, fields) | Content::StructVariant(_, _, _, fields) => {
+            for (name, value) in fields {
+                write_field(out, name, value, indent);
+            }
+        }
+        Content::Map(entries) => {
+            for (key, value) in entries {
+                let name = key
+                    .as_str()
+                    .map(|x| x.to_string())
+                    .unwrap_or_else(|| format_scalar(key));
+                write_field(out, &name, value, indent);
+            }
+        }
+        other => write_field(out, "value", other, indent),
+    }
+}
+
+fn write_field(out: &mut String, name: &str, value: &Content, indent: usize) {
+    match value {
+        // oneof fields are enums wrapping the value of the set field
+        Content::NewtypeVariant(_, _, variant, inner) => {
+            return write_field(out, variant, inner, indent);
+        }
+        Content::Some(inner) | Content::NewtypeStruct(_, inner) => {
+            return write_field(out, name, inner, indent);
+        }
+        _ => {}
+    }
+
+    match value {
+        Content::None | Content::Unit => {}
+        Content::Seq(items) | Content::Tuple(items) | Content::TupleStruct(_, items) => {
+            for item in items {
+                write_field(out, name, item, indent);
+            }
+        }
+        Content::TupleVariant(_, _, variant, items) => {
+            for item in items {
+                write_field(out, variant, item, indent);
+            }
+        }
+        Content::Map(entries) => {
+            let mut entries = entries.iter().collect::<Vec<_>>();
+            entries.sort_by(|a, b| a.0.as_key().cmp(&b.0.as_key()));
+            for (key, value) in entries {
+                writeln!(out, "{:indent$}{} {{", "", name, indent = indent * 2).unwrap();
+                write_field(out, "key", key, indent + 1);
+                write_field(out, "value", value, indent + 1);
+                writeln!(out, "{:indent$}}}", "", indent = indent * 2).unwrap();
+            }
+        }
+        Content::Struct(..) | Content::StructVariant(..) => {
+            writeln!(out, "{:indent$}{} {{", "", name, indent = indent * 2).unwrap();
+            write_fields(out, value, indent + 1);
+            writeln!(out, "{:indent$}}}", "", indent = indent * 2).unwrap();
+        }
+        scalar => {
+            writeln!(
+                out,
+                "{:indent$}{}: {}",
+                "",
+                name,
+                format_scalar(scalar),
+                indent = indent * 2
+            )
+            .unwrap();
+        }
+    }
+}
+
+fn format_scalar(value: &Content) -> String {
+    match value.resolve_inner() {
+        Content::Bool(val) => val.to_string(),
+        Content::U8(val) => val.to_string(),
+        Content::U16(val) => val.to_string(),
+        Content::U32(val) => val.to_string(),
+        Content::U64(val) => val.to_string(),
+        Content::U128(val) => val.to_string(),
+        Content::I8(val) => val.to_string(),
+        Content::I16(val) => val.to_string(),
+        Content::I32(val) => val.to_string(),
+        Content::I64(val) => val.to_string(),
+        Content::I128(val) => val.to_string(),
+        Content::F32(val) => val.to_string(),
+        Content::F64(val) => val.to_string(),
+        Content::Char(val) => quote_string(&val.to_string()),
+        Content::String(val) => quote_string(val),
+        Content::Bytes(val) => quote_bytes(val),
+        Content::UnitStruct(name) | Content::UnitVariant(_, _, name) => name.to_string(),
+        _ => "{}".to_string(),
+    }
+}
+
+fn quote_string(s: &str) -> String {
+    let mut rv = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => rv.push_str("\\\""),
+            '\\' => rv.push_str("\\\\"),
+            '\n' => rv.push_str("\\n"),
+            '\r' => rv.push_str("\\r"),
+            '\t' => rv.push_str("\\t"),
+            c if c.is_control() && c.is_ascii() => write!(rv, "\\{:03o}", c as u8).unwrap(),
+            c => rv.push(c),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+fn quote_bytes(bytes: &[u8]) -> String {
+    let mut rv = String::from("\"");
+    for &b in bytes {
+        match b {
+            b'"' => rv.push_str("\\\""),
+            b'\\' => rv.push_str("\\\\"),
+            b'\n' => rv.push_str("\\n"),
+            b'\r' => rv.push_str("\\r"),
+            b'\t' => rv.push_str("\\t"),
+            0x20..=0x7e => rv.push(b as char),
+            b => write!(rv, "\\{:03o}", b).unwrap(),
+        }
+    }
+    rv.push('"');
+    rv
+}
+
+/// Ensures that the value passed to `assert_proto_snapshot!` is a message.
+pub fn proto_message<T: prost::Message + serde::Serialize>(msg: &T) -> &T {
+    msg
+}
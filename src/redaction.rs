@@ -1,5 +1,6 @@
 use pest::Parser;
 use pest_derive::Parser;
+use regex::Regex;
 use std::borrow::Cow;
 use std::fmt;
 
@@ -129,6 +130,103 @@ where
     Redaction::Dynamic(Box::new(move |c, p| func(c, p).into()))
 }
 
+/// Creates a redaction that sorts a sequence.
+///
+/// This is useful for values where the order of the items is not stable,
+/// for instance because they were collected from multiple threads.  Numbers
+/// are sorted by their value and strings alphabetically.  Sequences holding
+/// other or mixed types are sorted by the serialized form of the items.  The
+/// sort is stable and values that are not sequences are left unchanged.
+///
+/// Example:
+///
+/// ```rust
+/// # use insta::{Settings, sorted_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".results", sorted_redaction());
+/// ```
+pub fn sorted_redaction() -> Redaction {
+    fn sort(value: Content) -> Content {
+        match value {
+            Content::Seq(mut items) => {
+                sort_items(&mut items);
+                Content::Seq(items)
+            }
+            Content::Tuple(mut items) => {
+                sort_items(&mut items);
+                Content::Tuple(items)
+            }
+            Content::Some(inner) => Content::Some(Box::new(sort(*inner))),
+            Content::NewtypeStruct(name, inner) => {
+                Content::NewtypeStruct(name, Box::new(sort(*inner)))
+            }
+            other => other,
+        }
+    }
+    dynamic_redaction(|value, _path| sort(value))
+}
+
+/// Creates a redaction that rounds floating point numbers.
+///
+/// The selected value is rounded to the given number of decimal places which
+/// hides differences in the last digits between platforms.  Both `f32` and
+/// `f64` values are rounded, all other values are left unchanged.
+///
+/// Example:
+///
+/// ```rust
+/// # use insta::{Settings, rounded_redaction};
+/// # let mut settings = Settings::new();
+/// settings.add_redaction(".velocity", rounded_redaction(4));
+/// ```
+pub fn rounded_redaction(decimals: usize) -> Redaction {
+    fn round(value: Content, factor: f64) -> Content {
+        match value {
+            Content::F32(f) => Content::F32(((f64::from(f) * factor).round() / factor) as f32),
+            Content::F64(f) => Content::F64((f * factor).round() / factor),
+            Content::Some(inner) => Content::Some(Box::new(round(*inner, factor))),
+            Content::NewtypeStruct(name, inner) => {
+                Content::NewtypeStruct(name, Box::new(round(*inner, factor)))
+            }
+            other => other,
+        }
+    }
+    let factor = 10f64.powi(decimals as i32);
+    dynamic_redaction(move |value, _path| round(value, factor))
+}
+
+/// Returns the value of a number as float for comparisons.
+fn as_number(value: &Content) -> Option<f64> {
+    value
+        .as_f64()
+        .or_else(|| value.as_i128().map(|x| x as f64))
+        .or_else(|| value.as_u128().map(|x| x as f64))
+}
+
+fn sort_items(items: &mut Vec<Content>) {
+    if items.iter().all(|x| as_number(x).is_some()) {
+        items.sort_by(|a, b| {
+            as_number(a)
+                .partial_cmp(&as_number(b))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    } else if items.iter().all(|x| x.as_str().is_some()) {
+        items.sort_by(|a, b| a.as_str().cmp(&b.as_str()));
+    } else {
+        let mut keyed = items
+            .drain(..)
+            .map(|x| {
+                (
+                    serde_json::to_string(&x).unwrap_or_else(|_| format!("{:?}", x)),
+                    x,
+                )
+            })
+            .collect::<Vec<_>>();
+        keyed.sort_by(|a, b| a.0.cmp(&b.0));
+        items.extend(keyed.into_iter().map(|x| x.1));
+    }
+}
+
 impl Redaction {
     /// Performs the redaction of the value at the given path.
     fn redact(&self, value: Content, path: &[PathItem]) -> Content {
@@ -190,16 +288,27 @@ impl PathItem {
     }
 }
 
+/// A regular expression that is matched against keys.
+#[derive(Debug, Clone)]
+pub struct KeyPattern(Regex);
+
+impl PartialEq for KeyPattern {
+    fn eq(&self, other: &KeyPattern) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Segment<'a> {
     DeepWildcard,
     Wildcard,
     Key(Cow<'a, str>),
+    KeyPattern(KeyPattern),
     Index(u64),
     Range(Option<i64>, Option<i64>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Selector<'a> {
     selectors: Vec<Vec<Segment<'a>>>,
 }
@@ -291,6 +400,27 @@ impl<'a> Selector<'a> {
         Ok(Selector { selectors: rv })
     }
 
+    /// Creates a selector that matches the values of a key at any depth.
+    pub fn for_key(key: &str) -> Selector<'static> {
+        Selector {
+            selectors: vec![vec![
+                Segment::DeepWildcard,
+                Segment::Key(Cow::Owned(key.to_string())),
+            ]],
+        }
+    }
+
+    /// Creates a selector that matches the values of all keys matching a
+    /// regular expression at any depth.
+    pub fn for_key_pattern(pattern: &str) -> Result<Selector<'static>, regex::Error> {
+        Ok(Selector {
+            selectors: vec![vec![
+                Segment::DeepWildcard,
+                Segment::KeyPattern(KeyPattern(Regex::new(pattern)?)),
+            ]],
+        })
+    }
+
     pub fn make_static(self) -> Selector<'static> {
         Selector {
             selectors: self
@@ -301,6 +431,7 @@ impl<'a> Selector<'a> {
                         .into_iter()
                         .map(|x| match x {
                             Segment::Key(x) => Segment::Key(Cow::Owned(x.into_owned())),
+                            Segment::KeyPattern(x) => Segment::KeyPattern(x),
                             Segment::Index(x) => Segment::Index(x),
                             Segment::Wildcard => Segment::Wildcard,
                             Segment::DeepWildcard => Segment::DeepWildcard,
@@ -317,6 +448,7 @@ impl<'a> Selector<'a> {
             Segment::Wildcard => true,
             Segment::DeepWildcard => true,
             Segment::Key(ref k) => element.as_str() == Some(&k),
+            Segment::KeyPattern(ref p) => element.as_str().map_or(false, |x| p.0.is_match(x)),
             Segment::Index(i) => element.as_u64() == Some(i),
             Segment::Range(start, end) => element.range_check(start, end),
         }
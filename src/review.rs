@@ -0,0 +1,95 @@
+use std::error::Error;
+
+/// The decision for a pending snapshot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Operation {
+    /// The new snapshot replaces the old one.
+    Accept,
+    /// The new snapshot is discarded.
+    Reject,
+    /// The new snapshot stays pending.
+    Skip,
+}
+
+/// A set of pending snapshots that are reviewed and committed together.
+///
+/// For snapshot files this is the pending snapshot of the file, for inline
+/// snapshots all pending snapshots of a source file.
+pub trait ReviewContainer {
+    /// The type of the pending snapshots.
+    type Snapshot;
+
+    /// Returns the pending snapshots.
+    fn snapshots(&self) -> &[Self::Snapshot];
+
+    /// Records the decision for the pending snapshot at an index.
+    fn set_operation(&mut self, idx: usize, op: Operation);
+
+    /// Applies the recorded decisions.
+    fn commit(&mut self) -> Result<(), Box<dyn Error>>;
+}
+
+/// Reviews all snapshots of a container and commits the decisions.
+///
+/// The `decide` callback is invoked once for every pending snapshot and
+/// returns the operation to apply to it.  This keeps the iteration and
+/// commit logic independent of how decisions are made so that it can be
+/// driven by an interactive prompt as well as by other frontends.
+pub fn review_container<C, F>(container: &mut C, decide: F) -> Result<(), Box<dyn Error>>
+where
+    C: ReviewContainer,
+    F: FnMut(&C::Snapshot) -> Result<Operation, Box<dyn Error>>,
+{
+    decide_snapshots(container, decide)?;
+    container.commit()
+}
+
+/// Like [`review_container`] but does not commit the decisions.
+pub fn decide_snapshots<C, F>(container: &mut C, mut decide: F) -> Result<(), Box<dyn Error>>
+where
+    C: ReviewContainer,
+    F: FnMut(&C::Snapshot) -> Result<Operation, Box<dyn Error>>,
+{
+    for idx in 0..container.snapshots().len() {
+        let op = decide(&container.snapshots()[idx])?;
+        container.set_operation(idx, op);
+    }
+    Ok(())
+}
+
+#[test]
+fn test_review_container() {
+    struct Container(Vec<(&'static str, Option<Operation>)>, bool);
+
+    impl ReviewContainer for Container {
+        type Snapshot = (&'static str, Option<Operation>);
+
+        fn snapshots(&self) -> &[Self::Snapshot] {
+            &self.0
+        }
+
+        fn set_operation(&mut self, idx: usize, op: Operation) {
+            self.0[idx].1 = Some(op);
+        }
+
+        fn commit(&mut self) -> Result<(), Box<dyn Error>> {
+            self.1 = true;
+            Ok(())
+        }
+    }
+
+    let mut container = Container(vec![("a", None), ("b", None)], false);
+    review_container(&mut container, |snapshot| {
+        Ok(if snapshot.0 == "a" {
+            Operation::Accept
+        } else {
+            Operation::Skip
+        })
+    })
+    .unwrap();
+    assert_eq!(
+        container.0,
+        vec![("a", Some(Operation::Accept)), ("b", Some(Operation::Skip))]
+    );
+    assert!(container.1);
+}
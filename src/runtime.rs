@@ -1,4 +1,5 @@
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
@@ -11,27 +12,33 @@ use std::thread;
 use lazy_static::lazy_static;
 
 use crate::env::{
-    force_pass, force_update_snapshots, get_cargo_workspace, get_output_behavior,
-    get_snapshot_update_behavior, memoize_snapshot_file, OutputBehavior, SnapshotUpdate,
+    force_pass, force_update_snapshots, get_cargo_workspace, get_output_behavior, get_platform,
+    get_rust_version, get_snapshot_update_behavior, memoize_snapshot_file, require_full_match,
+    OutputBehavior, SnapshotUpdate,
 };
 use crate::output::{print_snapshot_diff_with_title, print_snapshot_summary_with_title};
+use crate::serialization::{SerializationFormat, SerializedValue};
 use crate::settings::Settings;
-use crate::snapshot::{MetaData, PendingInlineSnapshot, Snapshot, SnapshotContents};
+use crate::snapshot::{
+    binary_file_path, data_file_path, golden_file_snapshot, golden_pending_file_path, hash_bytes,
+    load_golden_file, pending_file_path, save_golden_file, MetaData, PendingInlineSnapshot,
+    Snapshot, SnapshotContents,
+};
 use crate::utils::style;
 
 lazy_static! {
     static ref TEST_NAME_COUNTERS: Mutex<BTreeMap<String, usize>> = Mutex::new(BTreeMap::new());
     static ref TEST_NAME_CLASH_DETECTION: Mutex<BTreeMap<String, bool>> =
         Mutex::new(BTreeMap::new());
+    static ref MEMORY_SNAPSHOTS: Mutex<BTreeMap<PathBuf, Snapshot>> = Mutex::new(BTreeMap::new());
 }
 
-// This macro is basically eprintln but without being captured and
-// hidden by the test runner.
-macro_rules! elog {
-    () => (write!(std::io::stderr()).ok());
-    ($($arg:tt)*) => ({
-        writeln!(std::io::stderr(), $($arg)*).ok();
-    })
+thread_local!(static ASSERTION_COUNT: Cell<usize> = Cell::new(0));
+
+/// Returns the number of snapshot assertions run on the current thread.
+#[cfg(feature = "glob")]
+pub fn assertion_count() -> usize {
+    ASSERTION_COUNT.with(|x| x.get())
 }
 
 /// Special marker to use an automatic name.
@@ -139,8 +146,9 @@ fn generate_snapshot_name_for_thread(module_path: &str) -> Result<String, &'stat
         test_prefixed = true;
     }
 
-    // next check if we need to add a suffix
-    let name = add_suffix_to_snapshot_name(Cow::Borrowed(name));
+    // next check if we need to add a namespace and a suffix
+    let name = add_namespace_to_snapshot_name(Cow::Borrowed(name));
+    let name = add_suffix_to_snapshot_name(name);
     let key = format!("{}::{}", module_path.replace("::", "__"), name);
 
     // because fn foo and fn test_foo end up with the same snapshot name we
@@ -177,6 +185,16 @@ fn generate_snapshot_name_for_thread(module_path: &str) -> Result<String, &'stat
     Ok(rv)
 }
 
+/// If there is a namespace on the settings, prepend it to the snapshot name.
+fn add_namespace_to_snapshot_name(name: Cow<'_, str>) -> Cow<'_, str> {
+    Settings::with(|settings| {
+        settings
+            .snapshot_namespace()
+            .map(|namespace| Cow::Owned(format!("{}__{}", namespace, name)))
+            .unwrap_or_else(|| name)
+    })
+}
+
 /// If there is a suffix on the settings, append it to the snapshot name.
 fn add_suffix_to_snapshot_name(name: Cow<'_, str>) -> Cow<'_, str> {
     Settings::with(|settings| {
@@ -206,8 +224,9 @@ fn get_snapshot_filename(
                 }
                 write!(
                     &mut f,
-                    "{}.snap",
-                    snapshot_name.replace("/", "__").replace("\\", "__")
+                    "{}.{}",
+                    snapshot_name.replace("/", "__").replace("\\", "__"),
+                    settings.snapshot_extension()
                 )
                 .unwrap();
                 f
@@ -225,6 +244,10 @@ struct SnapshotAssertionContext<'a> {
     pending_snapshots_path: Option<PathBuf>,
     assertion_file: &'a str,
     assertion_line: u32,
+    raw_hash: Option<String>,
+    /// The format the value was serialized with.
+    format: Option<SerializationFormat>,
+    binary: Option<&'a [u8]>,
 }
 
 impl<'a> SnapshotAssertionContext<'a> {
@@ -251,7 +274,14 @@ impl<'a> SnapshotAssertionContext<'a> {
                 };
                 let file =
                     get_snapshot_filename(module_path, &name, &cargo_workspace, assertion_file);
-                if fs::metadata(&file).is_ok() {
+                let memory_snapshot = if Settings::with(|settings| settings.in_memory()) {
+                    MEMORY_SNAPSHOTS.lock().unwrap().get(&file).cloned()
+                } else {
+                    None
+                };
+                if memory_snapshot.is_some() {
+                    old_snapshot = memory_snapshot;
+                } else if fs::metadata(&file).is_ok() {
                     old_snapshot = Some(Snapshot::from_file(&file)?);
                 }
                 snapshot_name = Some(name);
@@ -289,6 +319,9 @@ impl<'a> SnapshotAssertionContext<'a> {
             pending_snapshots_path,
             assertion_file,
             assertion_line,
+            raw_hash: None,
+            format: None,
+            binary: None,
         })
     }
 
@@ -305,27 +338,113 @@ impl<'a> SnapshotAssertionContext<'a> {
             })
     }
 
+    /// Returns the path to store in the snapshot metadata.
+    ///
+    /// Paths within the workspace are stored relative to the workspace root
+    /// so that they are the same on every machine.  Absolute paths outside
+    /// of the workspace are kept as they are.
+    fn metadata_path(&self, p: &Path) -> Option<PathBuf> {
+        self.localize_path(p).or_else(|| {
+            if p.is_absolute() {
+                Some(p.to_path_buf())
+            } else {
+                None
+            }
+        })
+    }
+
     /// Creates the new snapshot from input values.
     pub fn new_snapshot(&self, contents: SnapshotContents, expr: &str) -> Snapshot {
+        let mut metadata = Settings::with(|settings| {
+            if settings.omit_metadata() {
+                MetaData::default()
+            } else {
+                let source = Path::new(self.assertion_file);
+                let mut metadata = MetaData::new(
+                    &self
+                        .metadata_path(source)
+                        .unwrap_or_else(|| source.to_path_buf()),
+                    expr,
+                    settings.input_file().and_then(|x| self.metadata_path(x)),
+                );
+                if settings.omit_expression() {
+                    metadata.expression = None;
+                }
+                metadata.description = settings.description().map(|x| x.to_string());
+                if !settings.snapshot_tags().is_empty() {
+                    metadata.tags = Some(settings.snapshot_tags().clone());
+                }
+                if settings.record_environment() {
+                    metadata.rust_version = get_rust_version();
+                    metadata.platform = Some(get_platform());
+                }
+                if self.snapshot_file.is_some() && settings.snapshot_extension() != "snap" {
+                    metadata.extension = Some(settings.snapshot_extension().to_string());
+                }
+                metadata.content_type = self
+                    .format
+                    .and_then(|x| x.content_type())
+                    .map(|x| x.to_string());
+                if let Some(ref raw_hash) = self.raw_hash {
+                    metadata.raw_hash = Some(raw_hash.clone());
+                    metadata.redacted_hash = Some(contents.hash());
+                }
+                metadata
+            }
+        });
+        // the expected failure marker is maintained by hand so carry it over
+        if self.is_expected_failure() {
+            metadata.expected_failure = Some(true);
+        }
+        if self.is_frozen() {
+            metadata.frozen = Some(true);
+        }
+        if let Some(binary) = self.binary {
+            metadata.binary_hash = Some(hash_bytes(binary));
+        }
+        // only snapshots in files can be moved into a separate data file
+        if self.snapshot_file.is_some() {
+            let limit = Settings::with(|settings| settings.externalize_over_bytes());
+            if limit.map_or(false, |limit| contents.len() > limit) {
+                metadata.data_hash = Some(contents.hash());
+            }
+        }
         Snapshot::from_components(
             self.module_path.replace("::", "__"),
             self.snapshot_name.as_ref().map(|x| x.to_string()),
-            MetaData::new(
-                self.assertion_file,
-                expr,
-                Settings::with(|s| s.input_file().and_then(|x| self.localize_path(x))),
-            ),
+            metadata,
             contents,
         )
     }
 
+    /// Returns `true` if the old snapshot is marked as an expected failure.
+    pub fn is_expected_failure(&self) -> bool {
+        self.old_snapshot
+            .as_ref()
+            .map_or(false, |x| x.metadata().expected_failure())
+    }
+
+    /// Returns `true` if the snapshot must not be updated.
+    pub fn is_frozen(&self) -> bool {
+        Settings::with(|settings| settings.frozen())
+            || self
+                .old_snapshot
+                .as_ref()
+                .map_or(false, |x| x.metadata().frozen())
+    }
+
     /// Cleanup logic for passing snapshots.
     pub fn cleanup_passing(&self) -> Result<(), Box<dyn Error>> {
+        if Settings::with(|settings| settings.in_memory()) {
+            return Ok(());
+        }
+
         // let's just make sure there are no more pending files lingering
         // around.
         if let Some(ref snapshot_file) = self.snapshot_file {
-            let mut snapshot_file = snapshot_file.clone();
-            snapshot_file.set_extension("snap.new");
+            let snapshot_file = pending_file_path(snapshot_file);
+            fs::remove_file(data_file_path(&snapshot_file)).ok();
+            fs::remove_file(binary_file_path(&snapshot_file)).ok();
             fs::remove_file(snapshot_file).ok();
         }
 
@@ -339,6 +458,14 @@ impl<'a> SnapshotAssertionContext<'a> {
         Ok(())
     }
 
+    /// Writes the bytes of a binary snapshot next to the snapshot file.
+    fn save_binary(&self, snapshot_file: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(binary) = self.binary {
+            fs::write(binary_file_path(snapshot_file), binary)?;
+        }
+        Ok(())
+    }
+
     /// Writes the changes of the snapshot back.
     pub fn update_snapshot(
         &self,
@@ -351,10 +478,32 @@ impl<'a> SnapshotAssertionContext<'a> {
         let should_print = get_output_behavior() != OutputBehavior::Nothing;
         let snapshot_update = get_snapshot_update_behavior(unseen);
 
+        // nothing is written to disk, snapshots updated in-place are only
+        // remembered for later assertions
+        if Settings::with(|settings| settings.in_memory()) {
+            if let (SnapshotUpdate::InPlace, Some(snapshot_file)) =
+                (snapshot_update, self.snapshot_file.as_ref())
+            {
+                if should_print {
+                    elog!(
+                        "{} {}",
+                        style("updated snapshot in memory").green(),
+                        style(snapshot_file.display()).cyan().underlined(),
+                    );
+                }
+                MEMORY_SNAPSHOTS
+                    .lock()
+                    .unwrap()
+                    .insert(snapshot_file.clone(), new_snapshot);
+            }
+            return Ok(snapshot_update);
+        }
+
         match snapshot_update {
             SnapshotUpdate::InPlace => {
                 if let Some(ref snapshot_file) = self.snapshot_file {
                     new_snapshot.save(snapshot_file)?;
+                    self.save_binary(snapshot_file)?;
                     if should_print {
                         elog!(
                             "{} {}",
@@ -377,9 +526,9 @@ impl<'a> SnapshotAssertionContext<'a> {
             }
             SnapshotUpdate::NewFile => {
                 if let Some(ref snapshot_file) = self.snapshot_file {
-                    let mut new_path = snapshot_file.to_path_buf();
-                    new_path.set_extension("snap.new");
+                    let new_path = pending_file_path(snapshot_file);
                     new_snapshot.save(&new_path)?;
+                    self.save_binary(&new_path)?;
                     if should_print {
                         elog!(
                             "{} {}",
@@ -430,7 +579,10 @@ fn print_snapshot_info(ctx: &SnapshotAssertionContext, new_snapshot: &Snapshot)
 
 /// Finalizes the assertion based on the update result.
 fn finalize_assertion(ctx: &SnapshotAssertionContext, update_result: SnapshotUpdate) {
-    if update_result == SnapshotUpdate::NewFile && get_output_behavior() != OutputBehavior::Nothing
+    // there is nothing to review if the snapshot was not written
+    if update_result == SnapshotUpdate::NewFile
+        && get_output_behavior() != OutputBehavior::Nothing
+        && !Settings::with(|settings| settings.in_memory())
     {
         println!(
             "{hint}",
@@ -449,6 +601,39 @@ fn finalize_assertion(ctx: &SnapshotAssertionContext, update_result: SnapshotUpd
     }
 }
 
+/// Replaces the absolute path of the workspace root with a placeholder.
+///
+/// The root is only replaced where it is a whole path or followed by a path
+/// separator so that siblings such as `/path/to/crate-old` are kept.
+fn substitute_workspace_root<'a>(value: &'a str, workspace: &Path) -> Cow<'a, str> {
+    let root = workspace.to_string_lossy();
+    let root = root.trim_end_matches(|c| c == '/' || c == '\\');
+    if root.is_empty() || !value.contains(root) {
+        return Cow::Borrowed(value);
+    }
+    let is_path_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+    let mut rv = String::with_capacity(value.len());
+    let mut last = 0;
+    for (idx, _) in value.match_indices(root) {
+        let end = idx + root.len();
+        let is_whole = value[..idx]
+            .chars()
+            .next_back()
+            .map_or(true, |c| !is_path_char(c) && c != '/' && c != '\\')
+            && value[end..]
+                .chars()
+                .next()
+                .map_or(true, |c| !is_path_char(c));
+        if is_whole {
+            rv.push_str(&value[last..idx]);
+            rv.push_str("[WORKSPACE_ROOT]");
+            last = end;
+        }
+    }
+    rv.push_str(&value[last..]);
+    Cow::Owned(rv)
+}
+
 /// This function is invoked from the macros to run the main assertion logic.
 ///
 /// This will create the assertion context, run the main logic to assert
@@ -465,6 +650,130 @@ pub fn assert_snapshot(
     assertion_line: u32,
     expr: &str,
 ) -> Result<(), Box<dyn Error>> {
+    assert_snapshot_impl(
+        refval,
+        new_snapshot_value,
+        None,
+        None,
+        None,
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+        expr,
+    )
+}
+
+/// Asserts a snapshot of a value serialized by one of the serialization
+/// macros.
+#[allow(clippy::too_many_arguments)]
+pub fn assert_serialized_snapshot(
+    refval: ReferenceValue<'_>,
+    new_snapshot_value: SerializedValue,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    assert_snapshot_impl(
+        refval,
+        &new_snapshot_value.value,
+        new_snapshot_value.raw_value.as_deref(),
+        Some(new_snapshot_value.format),
+        None,
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+        expr,
+    )
+}
+
+/// Asserts a snapshot whose value is computed by a closure.
+///
+/// The closure is not invoked if the outcome of the assertion cannot matter
+/// because assertions are forced to pass (`INSTA_FORCE_PASS`) and no
+/// snapshots are written (`INSTA_UPDATE=no`).  The assertion is still
+/// counted and its snapshot file is still recorded as referenced.
+#[allow(clippy::too_many_arguments)]
+pub fn assert_snapshot_lazy<F: FnOnce() -> String>(
+    refval: ReferenceValue<'_>,
+    new_snapshot_value: F,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    if needs_lazy_value() {
+        return assert_snapshot(
+            refval,
+            &new_snapshot_value(),
+            manifest_dir,
+            module_path,
+            assertion_file,
+            assertion_line,
+            expr,
+        );
+    }
+    skip_lazy_assertion(
+        refval,
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+    )
+}
+
+/// Like [`assert_snapshot_lazy`] for values serialized by one of the
+/// serialization macros.
+#[allow(clippy::too_many_arguments)]
+pub fn assert_serialized_snapshot_lazy<F: FnOnce() -> SerializedValue>(
+    refval: ReferenceValue<'_>,
+    new_snapshot_value: F,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    if needs_lazy_value() {
+        return assert_serialized_snapshot(
+            refval,
+            new_snapshot_value(),
+            manifest_dir,
+            module_path,
+            assertion_file,
+            assertion_line,
+            expr,
+        );
+    }
+    skip_lazy_assertion(
+        refval,
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+    )
+}
+
+/// Returns `true` if the outcome of an assertion can matter.
+fn needs_lazy_value() -> bool {
+    !force_pass()
+        || force_update_snapshots()
+        || get_snapshot_update_behavior(false) != SnapshotUpdate::NoUpdate
+}
+
+/// Counts an assertion whose value is not computed.
+fn skip_lazy_assertion(
+    refval: ReferenceValue<'_>,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+) -> Result<(), Box<dyn Error>> {
+    ASSERTION_COUNT.with(|x| x.set(x.get() + 1));
     let ctx = SnapshotAssertionContext::prepare(
         refval,
         manifest_dir,
@@ -472,8 +781,106 @@ pub fn assert_snapshot(
         assertion_file,
         assertion_line,
     )?;
+    if let Some(ref snapshot_file) = ctx.snapshot_file {
+        memoize_snapshot_file(snapshot_file);
+    }
+    Ok(())
+}
 
-    let new_snapshot = ctx.new_snapshot(new_snapshot_value.into(), expr);
+/// Asserts a binary snapshot.
+///
+/// The snapshot file only holds the length and hash of the bytes which are
+/// compared like the contents of any other snapshot.  The bytes themselves
+/// are written into a `.bin` file next to the snapshot file.
+#[allow(clippy::too_many_arguments)]
+pub fn assert_binary_snapshot(
+    refval: ReferenceValue<'_>,
+    bytes: &[u8],
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    if let ReferenceValue::Inline(_) = refval {
+        panic!("binary snapshots cannot be inline");
+    }
+    let description = format!("binary: {} bytes, hash {}", bytes.len(), hash_bytes(bytes));
+    assert_snapshot_impl(
+        refval,
+        &description,
+        None,
+        None,
+        Some(bytes),
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+        expr,
+    )
+}
+
+#[allow(clippy::too_many_arguments)]
+fn assert_snapshot_impl(
+    refval: ReferenceValue<'_>,
+    new_snapshot_value: &str,
+    raw_value: Option<&str>,
+    format: Option<SerializationFormat>,
+    binary: Option<&[u8]>,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    ASSERTION_COUNT.with(|x| x.set(x.get() + 1));
+
+    let mut ctx = SnapshotAssertionContext::prepare(
+        refval,
+        manifest_dir,
+        module_path,
+        assertion_file,
+        assertion_line,
+    )?;
+    ctx.binary = binary;
+    ctx.format = format;
+
+    // the raw value is only of interest if full matches are required
+    let full_match = require_full_match() || Settings::with(|x| x.require_full_match());
+    let raw_value = if full_match {
+        Some(raw_value.unwrap_or(new_snapshot_value))
+    } else {
+        None
+    };
+    let new_snapshot_value = if Settings::with(|settings| settings.substitute_workspace_root()) {
+        substitute_workspace_root(new_snapshot_value, &get_cargo_workspace(manifest_dir))
+    } else {
+        Cow::Borrowed(new_snapshot_value)
+    };
+    #[cfg(feature = "filters")]
+    let new_snapshot_value = Settings::with(|settings| {
+        if settings.filters().is_empty() {
+            new_snapshot_value
+        } else {
+            Cow::Owned(
+                settings
+                    .filters()
+                    .apply_to(&new_snapshot_value)
+                    .into_owned(),
+            )
+        }
+    });
+
+    // only the contents are needed to compare against the old snapshot.  The
+    // full snapshot with its metadata is created lazily when it gets written.
+    let new_snapshot_contents = SnapshotContents::from(&*new_snapshot_value);
+
+    // the hash of the raw value is only recorded if redactions or filters
+    // changed the value.
+    let raw_hash = raw_value.map(|x| SnapshotContents::from(x).hash());
+    if raw_hash.is_some() && raw_hash != Some(new_snapshot_contents.hash()) {
+        ctx.raw_hash = raw_hash.clone();
+    }
 
     // memoize the snapshot file if requested.
     if let Some(ref snapshot_file) = ctx.snapshot_file {
@@ -481,14 +888,102 @@ pub fn assert_snapshot(
     }
 
     // pass if the snapshots are missing
-    if ctx.old_snapshot.as_ref().map(|x| x.contents()) == Some(new_snapshot.contents()) {
+    let matches = ctx.old_snapshot.as_ref().map_or(false, |old| {
+        Settings::with(|settings| {
+            if settings.structural_comparison() {
+                old.contents().matches_structurally(
+                    &new_snapshot_contents,
+                    ctx.format,
+                    settings.unordered_sequences(),
+                )
+            } else if settings.min_similarity() < 1.0 {
+                old.contents().similarity(&new_snapshot_contents) >= settings.min_similarity()
+            } else {
+                old.contents() == &new_snapshot_contents
+            }
+        })
+    });
+
+    if matches {
         ctx.cleanup_passing()?;
 
-        if force_update_snapshots() {
-            ctx.update_snapshot(new_snapshot)?;
+        if force_update_snapshots() && !ctx.is_frozen() {
+            ctx.update_snapshot(ctx.new_snapshot(new_snapshot_contents, expr))?;
         }
+
+        // a changed raw value is only detected if full matches are required
+        if let Some(raw_hash) = raw_hash.filter(|_| !force_update_snapshots()) {
+            let old_raw_hash = ctx
+                .old_snapshot
+                .as_ref()
+                .and_then(|x| x.metadata().raw_hash());
+            if old_raw_hash.map_or(false, |x| x != raw_hash) {
+                if get_output_behavior() != OutputBehavior::Nothing {
+                    elog!(
+                        "{} {}",
+                        style("snapshot only matches because of redactions:").yellow(),
+                        style(
+                            ctx.snapshot_name
+                                .as_ref()
+                                .map_or("unnamed snapshot", |x| x.as_ref())
+                        )
+                        .cyan()
+                    );
+                }
+                panic!(
+                    "snapshot assertion for '{}' in line {} only matches after \
+                     redactions and filters were applied.  Update the snapshot with \
+                     `cargo insta test --force-update-snapshots` if the change is \
+                     expected",
+                    ctx.snapshot_name
+                        .as_ref()
+                        .map_or("unnamed snapshot", |x| x.as_ref()),
+                    ctx.assertion_line
+                );
+            }
+        }
+
+        if ctx.is_expected_failure() && !force_pass() {
+            panic!(
+                "snapshot assertion for '{}' in line {} is unexpectedly passing, \
+                 please remove `expected_failure` from its metadata",
+                ctx.snapshot_name
+                    .as_ref()
+                    .map_or("unnamed snapshot", |x| x.as_ref()),
+                ctx.assertion_line
+            );
+        }
+    // known bad snapshots are expected not to match
+    } else if ctx.is_expected_failure() {
+        ctx.cleanup_passing()?;
+
+        if get_output_behavior() != OutputBehavior::Nothing {
+            elog!(
+                "{} {}",
+                style("snapshot still failing as expected:").yellow(),
+                style(
+                    ctx.snapshot_name
+                        .as_ref()
+                        .map_or("unnamed snapshot", |x| x.as_ref())
+                )
+                .cyan()
+            );
+        }
+    // frozen snapshots are never updated, so fail regardless of update mode
+    } else if ctx.old_snapshot.is_some() && ctx.is_frozen() {
+        let new_snapshot = ctx.new_snapshot(new_snapshot_contents, expr);
+        print_snapshot_info(&ctx, &new_snapshot);
+        panic!(
+            "snapshot assertion for '{}' failed in line {}. The snapshot is frozen \
+             and will not be updated",
+            ctx.snapshot_name
+                .as_ref()
+                .map_or("unnamed snapshot", |x| x.as_ref()),
+            ctx.assertion_line
+        );
     // otherwise print information and update snapshots.
     } else {
+        let new_snapshot = ctx.new_snapshot(new_snapshot_contents, expr);
         print_snapshot_info(&ctx, &new_snapshot);
         let update_result = ctx.update_snapshot(new_snapshot)?;
         finalize_assertion(&ctx, update_result);
@@ -496,3 +991,227 @@ pub fn assert_snapshot(
 
     Ok(())
 }
+
+/// Compares a value against a golden file included with `include_str!`.
+///
+/// The golden file is resolved relative to the file of the assertion like
+/// `include_str!` does and the included string is compared like the file
+/// contents in [`assert_matches_golden_file`].
+#[allow(clippy::too_many_arguments)]
+pub fn assert_golden_file(
+    expected: &str,
+    golden_file: &str,
+    new_value: &str,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    let cargo_workspace = get_cargo_workspace(manifest_dir);
+    let golden_path = cargo_workspace
+        .join(assertion_file)
+        .parent()
+        .unwrap()
+        .join(golden_file);
+    let old = golden_file_snapshot(&golden_path, expected.to_string());
+    assert_golden(
+        &cargo_workspace,
+        golden_file,
+        &golden_path,
+        Some(old),
+        new_value,
+        module_path,
+        assertion_file,
+        assertion_line,
+        expr,
+    )
+}
+
+/// Compares a value against a golden file read at runtime.
+///
+/// The golden file is resolved relative to the file of the assertion.  On a
+/// mismatch the new value is written next to the golden file as a pending
+/// snapshot which can be reviewed with `cargo insta review`, accepting it
+/// rewrites the golden file.  With in-place updates the golden file is
+/// rewritten directly.
+#[allow(clippy::too_many_arguments)]
+pub fn assert_matches_golden_file(
+    golden_file: &str,
+    new_value: &str,
+    manifest_dir: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    let cargo_workspace = get_cargo_workspace(manifest_dir);
+    let golden_path = cargo_workspace
+        .join(assertion_file)
+        .parent()
+        .unwrap()
+        .join(golden_file);
+    let old = if golden_path.is_file() {
+        Some(load_golden_file(&golden_path)?)
+    } else {
+        None
+    };
+    assert_golden(
+        &cargo_workspace,
+        golden_file,
+        &golden_path,
+        old,
+        new_value,
+        module_path,
+        assertion_file,
+        assertion_line,
+        expr,
+    )
+}
+
+/// Compares a value against the old contents of a golden file.
+///
+/// With in-memory snapshots nothing is written and golden files updated
+/// in-place are only remembered for later assertions.
+#[allow(clippy::too_many_arguments)]
+fn assert_golden(
+    cargo_workspace: &Path,
+    golden_file: &str,
+    golden_path: &Path,
+    old: Option<Snapshot>,
+    new_value: &str,
+    module_path: &str,
+    assertion_file: &str,
+    assertion_line: u32,
+    expr: &str,
+) -> Result<(), Box<dyn Error>> {
+    ASSERTION_COUNT.with(|x| x.set(x.get() + 1));
+
+    let in_memory = Settings::with(|settings| settings.in_memory());
+    let old = if in_memory {
+        MEMORY_SNAPSHOTS
+            .lock()
+            .unwrap()
+            .get(golden_path)
+            .cloned()
+            .or(old)
+    } else {
+        old
+    };
+    let pending_path = golden_pending_file_path(golden_path);
+
+    let contents = SnapshotContents::from(new_value);
+    if old.as_ref().map_or(false, |x| x.contents() == &contents) {
+        if !in_memory {
+            fs::remove_file(&pending_path).ok();
+        }
+        return Ok(());
+    }
+
+    let mut metadata = MetaData::new(Path::new(assertion_file), expr, None);
+    metadata.golden_file = golden_path
+        .file_name()
+        .map(|x| x.to_string_lossy().into_owned());
+    let new = Snapshot::from_components(
+        module_path.replace("::", "__"),
+        metadata.golden_file.clone(),
+        metadata,
+        contents,
+    );
+
+    match get_output_behavior() {
+        OutputBehavior::Summary => {
+            print_snapshot_summary_with_title(
+                cargo_workspace,
+                &new,
+                old.as_ref(),
+                assertion_line,
+                Some(golden_path),
+            );
+        }
+        OutputBehavior::Diff => {
+            print_snapshot_diff_with_title(
+                cargo_workspace,
+                &new,
+                old.as_ref(),
+                assertion_line,
+                Some(golden_path),
+            );
+        }
+        _ => {}
+    }
+
+    let should_print = get_output_behavior() != OutputBehavior::Nothing;
+    let update_result = get_snapshot_update_behavior(old.is_none());
+    match update_result {
+        SnapshotUpdate::InPlace if in_memory => {
+            if should_print {
+                elog!(
+                    "{} {}",
+                    style("updated golden file in memory").green(),
+                    style(golden_path.display()).cyan().underlined(),
+                );
+            }
+            MEMORY_SNAPSHOTS
+                .lock()
+                .unwrap()
+                .insert(golden_path.to_path_buf(), new);
+        }
+        SnapshotUpdate::InPlace => {
+            save_golden_file(golden_path, new.contents())?;
+            fs::remove_file(&pending_path).ok();
+            if should_print {
+                elog!(
+                    "{} {}",
+                    style("updated golden file").green(),
+                    style(golden_path.display()).cyan().underlined(),
+                );
+            }
+        }
+        SnapshotUpdate::NewFile if !in_memory => {
+            new.save(&pending_path)?;
+            if should_print {
+                println!(
+                    "{hint}",
+                    hint = style("To update golden files run `cargo insta review`").dim(),
+                );
+            }
+        }
+        SnapshotUpdate::NewFile | SnapshotUpdate::NoUpdate => {}
+    }
+
+    if update_result != SnapshotUpdate::InPlace && !force_pass() {
+        panic!(
+            "golden file assertion for '{}' failed in line {}",
+            golden_file, assertion_line
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_substitute_workspace_root() {
+    let root = Path::new("/path").join("to").join("crate");
+    let value = format!(
+        "{} {} {}-old {}",
+        root.join("src").join("lib.rs").display(),
+        root.display(),
+        root.display(),
+        Path::new("/other")
+            .join(&root.to_string_lossy()[1..])
+            .display(),
+    );
+    let expected = format!(
+        "{} [WORKSPACE_ROOT] {}-old {}",
+        Path::new("[WORKSPACE_ROOT]")
+            .join("src")
+            .join("lib.rs")
+            .display(),
+        root.display(),
+        Path::new("/other")
+            .join(&root.to_string_lossy()[1..])
+            .display(),
+    );
+    assert_eq!(substitute_workspace_root(&value, &root), expected);
+}
@@ -1,18 +1,79 @@
+use std::error::Error;
+use std::fmt::{self, Write};
+
 use serde::de::value::Error as ValueError;
 use serde::Serialize;
 
 use crate::content::{Content, ContentSerializer};
+use crate::env::{get_default_format, require_full_match};
+use crate::normalizer::apply_normalizers;
 use crate::settings::Settings;
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum SerializationFormat {
     #[cfg(feature = "csv")]
     Csv,
+    #[cfg(feature = "prost")]
+    Proto,
     #[cfg(feature = "ron")]
     Ron,
     #[cfg(feature = "toml")]
     Toml,
     Yaml,
     Json,
+    /// The format from the settings or the `INSTA_DEFAULT_FORMAT` variable.
+    Default,
+}
+
+impl SerializationFormat {
+    fn from_name(name: &str) -> Option<SerializationFormat> {
+        Some(match name {
+            #[cfg(feature = "csv")]
+            "csv" => SerializationFormat::Csv,
+            #[cfg(feature = "ron")]
+            "ron" => SerializationFormat::Ron,
+            #[cfg(feature = "toml")]
+            "toml" => SerializationFormat::Toml,
+            "yaml" => SerializationFormat::Yaml,
+            "json" => SerializationFormat::Json,
+            _ => return None,
+        })
+    }
+
+    /// Resolves the default format to the configured format.
+    fn resolve(self) -> SerializationFormat {
+        match self {
+            SerializationFormat::Default => {
+                if let Some(name) =
+                    Settings::with(|settings| settings.default_format().map(|x| x.to_string()))
+                {
+                    SerializationFormat::from_name(&name)
+                        .unwrap_or_else(|| panic!("unsupported default format '{}'", name))
+                } else if let Some(name) = get_default_format() {
+                    SerializationFormat::from_name(&name)
+                        .unwrap_or_else(|| panic!("invalid value for INSTA_DEFAULT_FORMAT"))
+                } else {
+                    SerializationFormat::Yaml
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Returns the content type recorded in the metadata of snapshots.
+    ///
+    /// Only formats that cannot be told apart by their contents are recorded.
+    pub(crate) fn content_type(self) -> Option<&'static str> {
+        match self {
+            #[cfg(feature = "csv")]
+            SerializationFormat::Csv => Some("csv"),
+            #[cfg(feature = "ron")]
+            SerializationFormat::Ron => Some("ron"),
+            #[cfg(feature = "toml")]
+            SerializationFormat::Toml => Some("toml"),
+            _ => None,
+        }
+    }
 }
 
 pub enum SnapshotLocation {
@@ -20,25 +81,101 @@ pub enum SnapshotLocation {
     File,
 }
 
+/// A value serialized for a snapshot assertion.
+pub struct SerializedValue {
+    /// The serialized value with redactions and normalizers applied.
+    pub value: String,
+    /// The serialized value before redactions and normalizers were applied.
+    ///
+    /// This is only kept if full matches are required and redactions or
+    /// normalizers are in effect.
+    pub raw_value: Option<String>,
+    /// The format the value was serialized with.
+    pub format: SerializationFormat,
+}
+
 pub fn serialize_content(
+    content: Content,
+    format: SerializationFormat,
+    location: SnapshotLocation,
+) -> SerializedValue {
+    serialize_content_with_redactions(
+        content,
+        #[cfg(feature = "redactions")]
+        &[],
+        format,
+        location,
+    )
+}
+
+/// Serializes the content and the raw content if full matches are required.
+///
+/// The raw content is the content before any redactions or normalizers are
+/// applied.  The redactions of the settings are applied first and the
+/// redactions passed to the assertion after them so that the latter take
+/// precedence.  Redactions of the settings with the same selector as one
+/// passed to the assertion are skipped entirely.
+fn serialize_content_with_redactions(
     mut content: Content,
+    #[cfg(feature = "redactions")] redactions: &[(
+        crate::redaction::Selector,
+        crate::redaction::Redaction,
+    )],
     format: SerializationFormat,
     location: SnapshotLocation,
-) -> String {
+) -> SerializedValue {
+    let mut raw = None;
     content = Settings::with(|settings| {
         if settings.sort_maps() {
-            content.sort_maps();
+            content.sort_maps(settings.sort_maps_depth());
+        }
+        if settings.skip_none_fields() {
+            content.remove_none_fields();
+        }
+        #[cfg(feature = "redactions")]
+        let has_redactions = settings.iter_redactions().next().is_some() || !redactions.is_empty();
+        #[cfg(not(feature = "redactions"))]
+        let has_redactions = false;
+        if (settings.require_full_match() || require_full_match())
+            && (has_redactions
+                || settings.normalize_path_separators()
+                || !settings.normalizers().is_empty())
+        {
+            raw = Some(content.clone());
+        }
+        if settings.normalize_path_separators() {
+            content.normalize_path_separators();
         }
         #[cfg(feature = "redactions")]
         {
             for (selector, redaction) in settings.iter_redactions() {
+                if redactions.iter().all(|x| x.0 != *selector) {
+                    content = selector.redact(content, redaction);
+                }
+            }
+            for (selector, redaction) in redactions {
                 content = selector.redact(content, redaction);
             }
         }
-        content
+        apply_normalizers(content, settings.normalizers())
     });
 
-    match format {
+    let format = format.resolve();
+    let rv = render_content(&content, &format, &location);
+    SerializedValue {
+        value: rv,
+        raw_value: raw.map(|raw| render_content(&raw, &format, &location)),
+        format,
+    }
+}
+
+fn render_content(
+    content: &Content,
+    format: &SerializationFormat,
+    location: &SnapshotLocation,
+) -> String {
+    match *format {
+        SerializationFormat::Default => unreachable!(),
         SerializationFormat::Yaml => {
             let serialized = serde_yaml::to_string(&content).unwrap();
             match location {
@@ -59,7 +196,7 @@ pub fn serialize_content(
                         writer.serialize(content).unwrap();
                     }
                 } else {
-                    writer.serialize(&content).unwrap();
+                    writer.serialize(content).unwrap();
                 }
                 writer.flush().unwrap();
             }
@@ -68,6 +205,8 @@ pub fn serialize_content(
             }
             String::from_utf8(buf).unwrap()
         }
+        #[cfg(feature = "prost")]
+        SerializationFormat::Proto => crate::proto::to_proto_text(content),
         #[cfg(feature = "ron")]
         SerializationFormat::Ron => {
             let mut buf = Vec::new();
@@ -80,6 +219,8 @@ pub fn serialize_content(
         }
         #[cfg(feature = "toml")]
         SerializationFormat::Toml => {
+            let mut content = content.clone();
+            content.move_toml_tables_last();
             let mut rv = toml::to_string_pretty(&content).unwrap();
             if rv.ends_with('\n') {
                 rv.truncate(rv.len() - 1);
@@ -93,23 +234,105 @@ pub fn serialize_value<S: Serialize>(
     s: &S,
     format: SerializationFormat,
     location: SnapshotLocation,
-) -> String {
+) -> SerializedValue {
     let serializer = ContentSerializer::<ValueError>::new();
     let content = Serialize::serialize(s, serializer).unwrap();
     serialize_content(content, format, location)
 }
 
+/// Serializes a value with all maps sorted independent of the settings.
+#[cfg(feature = "schemars")]
+pub fn serialize_value_sorted<S: Serialize>(
+    s: &S,
+    format: SerializationFormat,
+    location: SnapshotLocation,
+) -> SerializedValue {
+    let serializer = ContentSerializer::<ValueError>::new();
+    let mut content = Serialize::serialize(s, serializer).unwrap();
+    content.sort_maps(None);
+    serialize_content(content, format, location)
+}
+
 #[cfg(feature = "redactions")]
 pub fn serialize_value_redacted<S: Serialize>(
     s: &S,
     redactions: &[(crate::redaction::Selector, crate::redaction::Redaction)],
     format: SerializationFormat,
     location: SnapshotLocation,
-) -> String {
+) -> SerializedValue {
     let serializer = ContentSerializer::<ValueError>::new();
-    let mut content = Serialize::serialize(s, serializer).unwrap();
-    for (selector, redaction) in redactions {
-        content = selector.redact(content, &redaction);
+    let content = Serialize::serialize(s, serializer).unwrap();
+    serialize_content_with_redactions(content, redactions, format, location)
+}
+
+/// Renders an error and its chain of sources as numbered lines.
+///
+/// Hexadecimal numbers that look like memory addresses are replaced with
+/// a placeholder so that the output is stable across runs.
+pub fn format_error_chain(err: &dyn Error) -> String {
+    let mut rv = String::new();
+    let mut current = Some(err);
+    let mut idx = 0;
+    while let Some(err) = current {
+        if idx == 0 {
+            write!(rv, "{}: {}", idx, err).unwrap();
+        } else {
+            write!(rv, "\n{}: caused by {}", idx, err).unwrap();
+        }
+        current = err.source();
+        idx += 1;
     }
-    serialize_content(content, format, location)
+    normalize_addresses(&rv)
+}
+
+/// Renders a value with the `Debug` trait prefixed by the name of its type.
+pub fn format_typed_value<T: fmt::Debug + ?Sized>(value: &T) -> String {
+    format!(
+        "{}: {:#?}",
+        normalize_type_name(std::any::type_name::<T>()),
+        value
+    )
+}
+
+/// Removes symbol hashes (`::h` followed by 16 hex digits) from type names.
+fn normalize_type_name(name: &str) -> String {
+    let mut rv = String::with_capacity(name.len());
+    let mut rest = name;
+    while let Some(pos) = rest.find("::h") {
+        let (before, after) = rest.split_at(pos);
+        rv.push_str(before);
+        let digits = after[3..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+        if digits == 16 {
+            rest = &after[3 + digits..];
+        } else {
+            rv.push_str(&after[..3]);
+            rest = &after[3..];
+        }
+    }
+    rv.push_str(rest);
+    rv
+}
+
+fn normalize_addresses(s: &str) -> String {
+    let mut rv = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find("0x") {
+        let (before, after) = rest.split_at(pos);
+        rv.push_str(before);
+        let digits = after[2..]
+            .chars()
+            .take_while(|c| c.is_ascii_hexdigit())
+            .count();
+        if digits >= 8 {
+            rv.push_str("0x[addr]");
+        } else {
+            rv.push_str(&after[..2 + digits]);
+        }
+        rest = &after[2 + digits..];
+    }
+    rv.push_str(rest);
+    rv
 }
@@ -1,10 +1,19 @@
 use lazy_static::lazy_static;
+use serde::Deserialize;
 use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::error::Error;
 use std::future::Future;
+use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::pin::Pin;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::{env, fs};
+
+use crate::env::{get_cargo_workspace, get_output_behavior, OutputBehavior};
+#[cfg(feature = "filters")]
+use crate::filters::Filters;
 
 #[cfg(feature = "redactions")]
 use crate::{
@@ -13,17 +22,49 @@ use crate::{
 };
 
 lazy_static! {
-    static ref DEFAULT_SETTINGS: Arc<ActualSettings> = Arc::new(ActualSettings {
-        sort_maps: false,
-        snapshot_path: "snapshots".into(),
-        snapshot_suffix: "".into(),
-        input_file: None,
-        prepend_module_to_snapshot: true,
-        #[cfg(feature = "redactions")]
-        redactions: Redactions::default(),
-        #[cfg(feature = "glob")]
-        allow_empty_glob: false,
-    });
+    static ref DEFAULT_SETTINGS: Arc<ActualSettings> = {
+        let mut settings = ActualSettings {
+            sort_maps: false,
+            sort_maps_depth: None,
+            normalize_path_separators: false,
+            skip_none_fields: false,
+            structural_comparison: false,
+            unordered_sequences: false,
+            min_similarity: 1.0,
+            substitute_workspace_root: false,
+            strip_ansi_escapes: true,
+            redact_temp_dir: true,
+            snapshot_path: "snapshots".into(),
+            snapshot_suffix: "".into(),
+            snapshot_extension: "snap".into(),
+            snapshot_namespace: "".into(),
+            default_format: "".into(),
+            input_file: None,
+            description: "".into(),
+            snapshot_tags: BTreeMap::new(),
+            prepend_module_to_snapshot: true,
+            omit_metadata: false,
+            omit_expression: false,
+            externalize_over_bytes: 0,
+            frozen: false,
+            in_memory: false,
+            record_environment: false,
+            require_full_match: false,
+            normalizers: Normalizers::default(),
+            #[cfg(feature = "redactions")]
+            redactions: Redactions::default(),
+            #[cfg(feature = "filters")]
+            filters: Filters::default(),
+            #[cfg(feature = "glob")]
+            allow_empty_glob: false,
+            #[cfg(feature = "glob")]
+            require_glob_assertions: false,
+        };
+        for config in load_workspace_configs() {
+            settings.apply_config(config);
+        }
+        Arc::new(settings)
+    };
 }
 thread_local!(static CURRENT_SETTINGS: RefCell<Settings> = RefCell::new(Settings::new()));
 
@@ -44,18 +85,223 @@ impl<'a> From<Vec<(&'a str, Redaction)>> for Redactions {
     }
 }
 
+/// Represents the names of the normalizers to apply.
+#[derive(Clone, Default)]
+pub struct Normalizers(Vec<String>);
+
+impl<'a> From<Vec<&'a str>> for Normalizers {
+    fn from(value: Vec<&'a str>) -> Normalizers {
+        Normalizers(value.into_iter().map(|x| x.to_string()).collect())
+    }
+}
+
+impl From<Vec<String>> for Normalizers {
+    fn from(value: Vec<String>) -> Normalizers {
+        Normalizers(value)
+    }
+}
+
 #[derive(Clone)]
 #[doc(hidden)]
 pub struct ActualSettings {
     pub sort_maps: bool,
+    pub sort_maps_depth: Option<usize>,
+    pub normalize_path_separators: bool,
+    pub skip_none_fields: bool,
+    pub structural_comparison: bool,
+    pub unordered_sequences: bool,
+    pub min_similarity: f64,
+    pub substitute_workspace_root: bool,
+    pub strip_ansi_escapes: bool,
+    pub redact_temp_dir: bool,
     pub snapshot_path: PathBuf,
     pub snapshot_suffix: String,
+    pub snapshot_extension: String,
+    pub snapshot_namespace: String,
+    pub default_format: String,
     pub input_file: Option<PathBuf>,
+    pub description: String,
+    pub snapshot_tags: BTreeMap<String, String>,
     pub prepend_module_to_snapshot: bool,
+    pub omit_metadata: bool,
+    pub omit_expression: bool,
+    pub externalize_over_bytes: usize,
+    pub frozen: bool,
+    pub in_memory: bool,
+    pub record_environment: bool,
+    pub require_full_match: bool,
+    pub normalizers: Normalizers,
     #[cfg(feature = "redactions")]
     pub redactions: Redactions,
+    #[cfg(feature = "filters")]
+    pub filters: Filters,
     #[cfg(feature = "glob")]
     pub allow_empty_glob: bool,
+    #[cfg(feature = "glob")]
+    pub require_glob_assertions: bool,
+}
+
+/// Default settings loaded from the `insta.yaml` file in the workspace root.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct Config {
+    settings: ConfigSettings,
+}
+
+/// Default settings from a workspace config.
+///
+/// These are the `settings` of `insta.yaml` and the `[tool.insta]` or
+/// `[workspace.metadata.insta]` table of the workspace manifest.  Unknown
+/// keys are ignored as the manifest tables can be shared with other tools.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigSettings {
+    sort_maps: Option<bool>,
+    #[serde(alias = "strip_ansi")]
+    strip_ansi_escapes: Option<bool>,
+    #[cfg(feature = "filters")]
+    filters: Option<Vec<(String, String)>>,
+    normalize_path_separators: Option<bool>,
+    skip_none_fields: Option<bool>,
+    structural_comparison: Option<bool>,
+    unordered_sequences: Option<bool>,
+    min_similarity: Option<f64>,
+    substitute_workspace_root: Option<bool>,
+    snapshot_path: Option<PathBuf>,
+    snapshot_namespace: Option<String>,
+    default_format: Option<String>,
+    prepend_module_to_snapshot: Option<bool>,
+    omit_metadata: Option<bool>,
+    omit_expression: Option<bool>,
+    externalize_over_bytes: Option<usize>,
+}
+
+/// Loads the configs of the workspace the tests are run from.
+///
+/// The tables of the workspace manifest are returned before `insta.yaml`
+/// which takes precedence.  The manifest is only read with the `toml`
+/// feature.  Configs that cannot be loaded are reported and ignored.
+fn load_workspace_configs() -> Vec<ConfigSettings> {
+    let manifest_dir = match env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) => manifest_dir,
+        Err(_) => return vec![],
+    };
+    let workspace = get_cargo_workspace(&manifest_dir);
+    let mut paths = vec![];
+    if cfg!(feature = "toml") {
+        paths.push(workspace.join("Cargo.toml"));
+    }
+    paths.push(workspace.join("insta.yaml"));
+    let mut rv = vec![];
+    for path in paths {
+        match load_config_file(&path) {
+            Ok(configs) => rv.extend(configs),
+            Err(err) => {
+                if get_output_behavior() != OutputBehavior::Nothing {
+                    elog!(
+                        "warning: ignoring invalid config in {}: {}",
+                        path.display(),
+                        err
+                    );
+                }
+            }
+        }
+    }
+    rv
+}
+
+/// Loads the configs in a workspace manifest or `insta.yaml` file.
+fn load_config_file(path: &Path) -> Result<Vec<ConfigSettings>, Box<dyn Error>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(_) => return Ok(vec![]),
+    };
+    #[cfg(feature = "toml")]
+    let configs = if path.extension().map_or(false, |x| x == "toml") {
+        let manifest: toml::Value = toml::from_str(&contents)?;
+        let mut configs = vec![];
+        for table in &[
+            manifest.get("workspace").and_then(|x| x.get("metadata")),
+            manifest.get("tool"),
+        ] {
+            if let Some(config) = table.and_then(|x| x.get("insta")) {
+                configs.push(config.clone().try_into()?);
+            }
+        }
+        configs
+    } else {
+        vec![serde_yaml::from_str::<Config>(&contents)?.settings]
+    };
+    #[cfg(not(feature = "toml"))]
+    let configs = vec![serde_yaml::from_str::<Config>(&contents)?.settings];
+    // invalid filters would fail every assertion
+    #[cfg(feature = "filters")]
+    {
+        for config in configs.iter() {
+            for (regex, _) in config.filters.iter().flatten() {
+                regex::Regex::new(regex)?;
+            }
+        }
+    }
+    Ok(configs)
+}
+
+impl ActualSettings {
+    fn apply_config(&mut self, config: ConfigSettings) {
+        if let Some(value) = config.sort_maps {
+            self.sort_maps = value;
+        }
+        if let Some(value) = config.strip_ansi_escapes {
+            self.strip_ansi_escapes = value;
+        }
+        #[cfg(feature = "filters")]
+        {
+            if let Some(value) = config.filters {
+                for (regex, replacement) in value {
+                    self.filters.add(&regex, replacement);
+                }
+            }
+        }
+        if let Some(value) = config.normalize_path_separators {
+            self.normalize_path_separators = value;
+        }
+        if let Some(value) = config.skip_none_fields {
+            self.skip_none_fields = value;
+        }
+        if let Some(value) = config.structural_comparison {
+            self.structural_comparison = value;
+        }
+        if let Some(value) = config.unordered_sequences {
+            self.unordered_sequences = value;
+        }
+        if let Some(value) = config.min_similarity {
+            self.min_similarity = value;
+        }
+        if let Some(value) = config.substitute_workspace_root {
+            self.substitute_workspace_root = value;
+        }
+        if let Some(value) = config.snapshot_path {
+            self.snapshot_path = value;
+        }
+        if let Some(value) = config.snapshot_namespace {
+            self.snapshot_namespace = value;
+        }
+        if let Some(value) = config.default_format {
+            self.default_format = value;
+        }
+        if let Some(value) = config.prepend_module_to_snapshot {
+            self.prepend_module_to_snapshot = value;
+        }
+        if let Some(value) = config.omit_metadata {
+            self.omit_metadata = value;
+        }
+        if let Some(value) = config.omit_expression {
+            self.omit_expression = value;
+        }
+        if let Some(value) = config.externalize_over_bytes {
+            self.externalize_over_bytes = value;
+        }
+    }
 }
 
 /// Configures how insta operates at test time.
@@ -74,6 +320,10 @@ pub struct ActualSettings {
 ///
 /// Settings can also be configured with the [`with_settings!`] macro.
 ///
+/// As settings are bound to a thread they do not carry over into threads or
+/// tasks spawned from a test.  For futures that are spawned onto an executor
+/// use [`with_captured_settings`] to take the settings along.
+///
 /// Example:
 ///
 /// ```ignore
@@ -134,6 +384,160 @@ impl Settings {
         self.inner.sort_maps
     }
 
+    /// Limits how deep maps are sorted if map sorting is enabled.
+    ///
+    /// With `Some(1)` only the outermost maps are sorted and the order of
+    /// maps nested in them is kept, `Some(2)` also sorts the maps one level
+    /// below and so on.  `None` sorts maps at any depth.
+    ///
+    /// The default value is `None`.
+    pub fn set_sort_maps_depth(&mut self, value: Option<usize>) {
+        self._private_inner_mut().sort_maps_depth = value;
+    }
+
+    /// Returns the current depth limit for map sorting.
+    pub fn sort_maps_depth(&self) -> Option<usize> {
+        self.inner.sort_maps_depth
+    }
+
+    /// Rewrites backslashes in strings to forward slashes before serialization.
+    ///
+    /// This makes snapshots containing paths identical on Windows and other
+    /// platforms.  As every backslash in a string value is rewritten this
+    /// should only be enabled for snapshots where backslashes are used as
+    /// path separators.  Like map sorting this only applies to snapshots that
+    /// undergo serialization (eg: does not work for `assert_debug_snapshot!`.)
+    ///
+    /// The default value is `false`.
+    pub fn set_normalize_path_separators(&mut self, value: bool) {
+        self._private_inner_mut().normalize_path_separators = value;
+    }
+
+    /// Returns the current value for path separator normalization.
+    pub fn normalize_path_separators(&self) -> bool {
+        self.inner.normalize_path_separators
+    }
+
+    /// Leaves out fields and map entries that are `None`.
+    ///
+    /// When enabled all fields of structs and entries of maps with a value
+    /// of `None` (or `null`) are removed from the value before it is
+    /// serialized, at any depth.  This keeps snapshots of types with many
+    /// optional fields focused on the data that is present.  A field that
+    /// becomes `Some` later shows up as an added line.  Like map sorting
+    /// this only applies to snapshots that undergo serialization.
+    ///
+    /// The default value is `false`.
+    pub fn set_skip_none_fields(&mut self, value: bool) {
+        self._private_inner_mut().skip_none_fields = value;
+    }
+
+    /// Returns the current value for skipping `None` fields.
+    pub fn skip_none_fields(&self) -> bool {
+        self.inner.skip_none_fields
+    }
+
+    /// Compares snapshots as data instead of as text.
+    ///
+    /// When enabled snapshots of values serialized as JSON, YAML or TOML are
+    /// parsed in that format and compared as values so that the order of
+    /// keys in maps does not matter.  Other snapshots, like the strings of
+    /// `assert_snapshot!`, and snapshots that cannot be parsed are compared
+    /// as text.  This is useful if a serializer does not write keys in a
+    /// stable order.
+    ///
+    /// The default value is `false`.
+    pub fn set_structural_comparison(&mut self, value: bool) {
+        self._private_inner_mut().structural_comparison = value;
+    }
+
+    /// Returns the current value for structural comparison.
+    pub fn structural_comparison(&self) -> bool {
+        self.inner.structural_comparison
+    }
+
+    /// Ignores the order of items in sequences for structural comparison.
+    ///
+    /// This only has an effect if [`Settings::set_structural_comparison`]
+    /// is enabled.
+    ///
+    /// The default value is `false`.
+    pub fn set_unordered_sequences(&mut self, value: bool) {
+        self._private_inner_mut().unordered_sequences = value;
+    }
+
+    /// Returns the current value for unordered sequences.
+    pub fn unordered_sequences(&self) -> bool {
+        self.inner.unordered_sequences
+    }
+
+    /// Lets snapshots pass that are similar enough to the old snapshot.
+    ///
+    /// The similarity is the ratio of unchanged lines between the old and
+    /// the new snapshot from `0.0` to `1.0`.  If it is at least the given
+    /// value the assertion passes and the old snapshot is kept, otherwise
+    /// it fails like any other mismatch.  This tolerates small changes in
+    /// generated text while still catching larger regressions.
+    ///
+    /// The default value is `1.0` which requires an exact match.
+    pub fn set_min_similarity(&mut self, value: f64) {
+        self._private_inner_mut().min_similarity = value;
+    }
+
+    /// Returns the current minimum similarity.
+    pub fn min_similarity(&self) -> f64 {
+        self.inner.min_similarity
+    }
+
+    /// Replaces the path of the workspace root in snapshots with a placeholder.
+    ///
+    /// When enabled every occurrence of the absolute path of the workspace
+    /// root in the new snapshot value is replaced with `[WORKSPACE_ROOT]`
+    /// before it is compared and stored.  This keeps snapshots that contain
+    /// paths identical between machines and checkouts.  Unlike
+    /// [`Settings::set_normalize_path_separators`] this applies to all
+    /// snapshots, including `assert_debug_snapshot!`.
+    ///
+    /// The default value is `false`.
+    pub fn set_substitute_workspace_root(&mut self, value: bool) {
+        self._private_inner_mut().substitute_workspace_root = value;
+    }
+
+    /// Returns the current value for workspace root substitution.
+    pub fn substitute_workspace_root(&self) -> bool {
+        self.inner.substitute_workspace_root
+    }
+
+    /// Removes ANSI escape sequences from the output of commands.
+    ///
+    /// This applies to `assert_command_snapshot!` and keeps colored output
+    /// readable in snapshots.
+    ///
+    /// The default value is `true`.
+    pub fn set_strip_ansi_escapes(&mut self, value: bool) {
+        self._private_inner_mut().strip_ansi_escapes = value;
+    }
+
+    /// Returns the current value for ANSI escape stripping.
+    pub fn strip_ansi_escapes(&self) -> bool {
+        self.inner.strip_ansi_escapes
+    }
+
+    /// Replaces the temporary directory in the output of commands.
+    ///
+    /// Every occurrence of the path of the temporary directory in the output
+    /// captured by `assert_command_snapshot!` is replaced with `[TEMP_DIR]`.
+    ///
+    /// The default value is `true`.
+    pub fn set_redact_temp_dir(&mut self, value: bool) {
+        self._private_inner_mut().redact_temp_dir = value;
+    }
+
+    /// Returns the current value for temporary directory redaction.
+    pub fn redact_temp_dir(&self) -> bool {
+        self.inner.redact_temp_dir
+    }
+
     /// Disables prepending of modules to the snapshot filename.
     ///
     /// By default the filename of a snapshot is `<module>__<name>.snap`.
@@ -150,6 +554,141 @@ impl Settings {
         self.inner.prepend_module_to_snapshot
     }
 
+    /// Disables writing of metadata into snapshot files.
+    ///
+    /// By default snapshot files start with a header that records the source
+    /// file, the expression and other information about the snapshot.  When
+    /// this is set to `true` the header is left empty and only the contents
+    /// of the snapshot are written.  This can reduce noise in reviews if the
+    /// metadata is not needed.  Note that tools like `cargo-insta` will then
+    /// not be able to show where a snapshot came from.
+    ///
+    /// The default value is `false`.
+    pub fn set_omit_metadata(&mut self, value: bool) {
+        self._private_inner_mut().omit_metadata = value;
+    }
+
+    /// Returns the current value for metadata omission.
+    pub fn omit_metadata(&self) -> bool {
+        self.inner.omit_metadata
+    }
+
+    /// Disables writing of the expression into the snapshot metadata.
+    ///
+    /// Large expressions or expressions that mention local variables change
+    /// often and make the metadata of snapshots churn.  When this is set to
+    /// `true` the `expression` field is left out while the rest of the
+    /// metadata is still written.
+    ///
+    /// The default value is `false`.
+    pub fn set_omit_expression(&mut self, value: bool) {
+        self._private_inner_mut().omit_expression = value;
+    }
+
+    /// Returns the current value for expression omission.
+    pub fn omit_expression(&self) -> bool {
+        self.inner.omit_expression
+    }
+
+    /// Stores large snapshots in a separate data file.
+    ///
+    /// Snapshots stored in files whose contents are larger than the given
+    /// number of bytes are written to a `.snap.data` file next to the
+    /// `.snap` file.  The `.snap` file then only holds the metadata with a
+    /// hash of the contents in `data_hash`.  This keeps diffs of the `.snap`
+    /// files small while the contents are still compared and reviewed as
+    /// usual.  Inline snapshots are never externalized.
+    ///
+    /// The default value is `0` which disables this.
+    pub fn set_externalize_over_bytes(&mut self, value: usize) {
+        self._private_inner_mut().externalize_over_bytes = value;
+    }
+
+    /// Returns the size over which snapshots are externalized.
+    pub fn externalize_over_bytes(&self) -> Option<usize> {
+        if self.inner.externalize_over_bytes == 0 {
+            None
+        } else {
+            Some(self.inner.externalize_over_bytes)
+        }
+    }
+
+    /// Freezes snapshots so that they are never updated.
+    ///
+    /// A frozen snapshot that does not match fails the assertion regardless
+    /// of `INSTA_UPDATE` and no new snapshot is written for review.  Snapshots
+    /// written while this is enabled are marked with `frozen: true` in their
+    /// metadata which freezes them for all future assertions.  The marker
+    /// can also be added to existing snapshot files by hand.
+    ///
+    /// The default value is `false`.
+    pub fn set_frozen(&mut self, value: bool) {
+        self._private_inner_mut().frozen = value;
+    }
+
+    /// Returns the current value for freezing snapshots.
+    pub fn frozen(&self) -> bool {
+        self.inner.frozen
+    }
+
+    /// Keeps snapshots in memory instead of writing them to disk.
+    ///
+    /// This is useful in sandboxes with a read-only file system.  Existing
+    /// snapshots are still read from disk but new and changed snapshots are
+    /// never written.  Mismatches are reported and fail the assertion as
+    /// usual.  If snapshots are updated in-place (`INSTA_UPDATE=always`) the
+    /// new snapshot is kept for the rest of the process and later assertions
+    /// of the same snapshot compare against it.
+    ///
+    /// The default value is `false`.
+    pub fn set_in_memory(&mut self, value: bool) {
+        self._private_inner_mut().in_memory = value;
+    }
+
+    /// Returns the current value for in-memory snapshots.
+    pub fn in_memory(&self) -> bool {
+        self.inner.in_memory
+    }
+
+    /// Records the Rust version and platform in the snapshot metadata.
+    ///
+    /// When this is set to `true` the version reported by `rustc --version`
+    /// (or `$RUSTC`) and the architecture, operating system and environment
+    /// such as `x86_64-linux-gnu` are written into the metadata as
+    /// `rust_version` and `platform`.  This helps to track down snapshots that
+    /// differ between platforms.  Note that the compiler is run when the tests
+    /// run, so with several toolchains installed the version might not be the
+    /// one that built the tests.  The platform is not a full target triple as
+    /// it lacks the vendor.  As these values change with every toolchain update
+    /// this is off by default.
+    ///
+    /// The default value is `false`.
+    pub fn set_record_environment(&mut self, value: bool) {
+        self._private_inner_mut().record_environment = value;
+    }
+
+    /// Returns the current value for recording the environment.
+    pub fn record_environment(&self) -> bool {
+        self.inner.record_environment
+    }
+
+    /// Fails snapshots that only match because of redactions or filters.
+    ///
+    /// With this set to `true` snapshots record hashes of their value before
+    /// and after redactions and filters were applied.  If the value before
+    /// they were applied changed but the snapshot still matches the assertion
+    /// fails.  This can also be enabled with `INSTA_REQUIRE_FULL_MATCH=1`.
+    ///
+    /// The default value is `false`.
+    pub fn set_require_full_match(&mut self, value: bool) {
+        self._private_inner_mut().require_full_match = value;
+    }
+
+    /// Returns the current value for requiring full matches.
+    pub fn require_full_match(&self) -> bool {
+        self.inner.require_full_match
+    }
+
     /// Allows the [`glob!`] macro to succeed if it matches no files.
     ///
     /// By default the glob macro will fail the test if it does not find
@@ -168,6 +707,24 @@ impl Settings {
         self.inner.allow_empty_glob
     }
 
+    /// Makes the [`glob!`] macro fail for inputs without snapshot assertions.
+    ///
+    /// By default the glob macro only warns about input files for which the
+    /// closure did not run a single snapshot assertion.  When this is enabled
+    /// the test fails instead.
+    ///
+    /// The default value is `false`.
+    #[cfg(feature = "glob")]
+    pub fn set_require_glob_assertions(&mut self, value: bool) {
+        self._private_inner_mut().require_glob_assertions = value;
+    }
+
+    /// Returns the current value for the glob assertions setting.
+    #[cfg(feature = "glob")]
+    pub fn require_glob_assertions(&self) -> bool {
+        self.inner.require_glob_assertions
+    }
+
     /// Sets the snapshot suffix.
     ///
     /// The snapshot suffix is added to all snapshot names with an `@` sign
@@ -193,6 +750,77 @@ impl Settings {
         }
     }
 
+    /// Sets the file extension of snapshot files.
+    ///
+    /// This is the part of the file name after the snapshot name.  Snapshots
+    /// that hold HTML or SQL can for instance use `"snap.html"` so that
+    /// editors pick the right syntax.  Extensions starting with `snap.` are
+    /// picked up by `cargo insta` like regular snapshots, others need to be
+    /// passed with `--extensions`.  Inline snapshots are not affected.
+    ///
+    /// The default value is `"snap"`.
+    pub fn set_snapshot_extension<I: Into<String>>(&mut self, extension: I) {
+        self._private_inner_mut().snapshot_extension = extension.into();
+    }
+
+    /// Returns the current snapshot file extension.
+    pub fn snapshot_extension(&self) -> &str {
+        &self.inner.snapshot_extension
+    }
+
+    /// Sets the snapshot namespace.
+    ///
+    /// The namespace is prepended to automatically generated snapshot names
+    /// with two underscores between.  For instance if the namespace is set to
+    /// `"parser"` the snapshot for `test_expr` is named `"parser__expr"`.
+    /// This is useful to avoid name clashes between modules without having
+    /// to name every snapshot by hand.  Explicitly named snapshots are not
+    /// affected.
+    pub fn set_snapshot_namespace<I: Into<String>>(&mut self, namespace: I) {
+        self._private_inner_mut().snapshot_namespace = namespace.into();
+    }
+
+    /// Removes the snapshot namespace.
+    pub fn remove_snapshot_namespace(&mut self) {
+        self.set_snapshot_namespace("");
+    }
+
+    /// Returns the current snapshot namespace.
+    pub fn snapshot_namespace(&self) -> Option<&str> {
+        if self.inner.snapshot_namespace.is_empty() {
+            None
+        } else {
+            Some(&self.inner.snapshot_namespace)
+        }
+    }
+
+    /// Sets the format for format agnostic assertions.
+    ///
+    /// This changes the format used by [`assert_serialized_snapshot!`] and
+    /// takes precedence over the `INSTA_DEFAULT_FORMAT` environment variable.
+    /// Supported formats are `yaml` and `json` as well as `csv`, `ron` and
+    /// `toml` if the respective features are enabled.  Other assertion macros
+    /// always use their own format.
+    ///
+    /// The default is to use the environment variable or YAML.
+    pub fn set_default_format<I: Into<String>>(&mut self, format: I) {
+        self._private_inner_mut().default_format = format.into();
+    }
+
+    /// Removes the default format.
+    pub fn remove_default_format(&mut self) {
+        self.set_default_format("");
+    }
+
+    /// Returns the current default format.
+    pub fn default_format(&self) -> Option<&str> {
+        if self.inner.default_format.is_empty() {
+            None
+        } else {
+            Some(&self.inner.default_format)
+        }
+    }
+
     /// Sets the input file reference.
     ///
     /// This value is completely unused by the snapshot testing system but
@@ -213,6 +841,80 @@ impl Settings {
         self.inner.input_file.as_deref()
     }
 
+    /// Sets a description that is stored with the snapshot.
+    ///
+    /// The description is free text that is shown when the snapshot is
+    /// reviewed.  It is meant to give context about a snapshot whose purpose
+    /// is not obvious from its name.  An empty description is not stored.
+    pub fn set_description<S: Into<String>>(&mut self, value: S) {
+        self._private_inner_mut().description = value.into();
+    }
+
+    /// Removes the description.
+    pub fn remove_description(&mut self) {
+        self._private_inner_mut().description = "".into();
+    }
+
+    /// Returns the current description.
+    pub fn description(&self) -> Option<&str> {
+        if self.inner.description.is_empty() {
+            None
+        } else {
+            Some(&self.inner.description)
+        }
+    }
+
+    /// Sets a tag that is stored with the snapshot.
+    ///
+    /// Tags are arbitrary key/value pairs in the metadata of a snapshot that
+    /// are not compared.  They can record context that is needed to reproduce
+    /// a snapshot such as the seed of a random number generator.  Setting a
+    /// tag again replaces its value.
+    pub fn set_snapshot_tag<K: Into<String>, V: Into<String>>(&mut self, key: K, value: V) {
+        self._private_inner_mut()
+            .snapshot_tags
+            .insert(key.into(), value.into());
+    }
+
+    /// Removes a snapshot tag.
+    pub fn remove_snapshot_tag(&mut self, key: &str) {
+        self._private_inner_mut().snapshot_tags.remove(key);
+    }
+
+    /// Returns the current snapshot tags.
+    pub fn snapshot_tags(&self) -> &BTreeMap<String, String> {
+        &self.inner.snapshot_tags
+    }
+
+    /// Enables a normalizer registered with [`register_normalizer`](crate::register_normalizer).
+    ///
+    /// Normalizers are applied in the order they were added after the
+    /// redactions.  An assertion panics if a normalizer with the name was
+    /// not registered.
+    ///
+    /// Note that this only applies to snapshots that undergo serialization
+    /// (eg: does not work for `assert_debug_snapshot!`.)
+    pub fn add_normalizer<I: Into<String>>(&mut self, name: I) {
+        self._private_inner_mut().normalizers.0.push(name.into());
+    }
+
+    /// Replaces the currently enabled normalizers.
+    ///
+    /// The default set is empty.
+    pub fn set_normalizers<N: Into<Normalizers>>(&mut self, normalizers: N) {
+        self._private_inner_mut().normalizers = normalizers.into();
+    }
+
+    /// Disables all normalizers.
+    pub fn clear_normalizers(&mut self) {
+        self._private_inner_mut().normalizers.0.clear();
+    }
+
+    /// Returns the names of the enabled normalizers.
+    pub fn normalizers(&self) -> &[String] {
+        &self.inner.normalizers.0
+    }
+
     /// Registers redactions that should be applied.
     ///
     /// This can be useful if redactions must be shared across multiple
@@ -220,6 +922,10 @@ impl Settings {
     ///
     /// Note that this only applies to snapshots that undergo serialization
     /// (eg: does not work for `assert_debug_snapshot!`.)
+    ///
+    /// Redactions passed to an assertion are applied after the ones of the
+    /// settings and replace a redaction of the settings with the same
+    /// selector.
     #[cfg(feature = "redactions")]
     pub fn add_redaction<R: Into<Redaction>>(&mut self, selector: &str, replacement: R) {
         self._private_inner_mut().redactions.0.push((
@@ -228,6 +934,35 @@ impl Settings {
         ));
     }
 
+    /// Registers a redaction for the values of a key at any depth.
+    ///
+    /// Every value stored under a map key or struct field with the given
+    /// name is replaced, no matter where it appears in the tree.  This is
+    /// a shortcut for the `.**.key` selector.
+    ///
+    /// Note that this only applies to snapshots that undergo serialization
+    /// (eg: does not work for `assert_debug_snapshot!`.)
+    #[cfg(feature = "redactions")]
+    pub fn add_key_redaction<R: Into<Redaction>>(&mut self, key: &str, replacement: R) {
+        self._private_inner_mut()
+            .redactions
+            .0
+            .push((Selector::for_key(key), Arc::new(replacement.into())));
+    }
+
+    /// Registers a redaction for the values of keys matching a regex.
+    ///
+    /// This works like [`Settings::add_key_redaction`] but the name of the
+    /// key is matched against a regular expression.  Use anchors (`^` and
+    /// `$`) to match entire keys.
+    #[cfg(feature = "redactions")]
+    pub fn add_key_regex_redaction<R: Into<Redaction>>(&mut self, pattern: &str, replacement: R) {
+        self._private_inner_mut().redactions.0.push((
+            Selector::for_key_pattern(pattern).unwrap(),
+            Arc::new(replacement.into()),
+        ));
+    }
+
     /// Registers a replacement callback.
     ///
     /// This works similar to a redaction but instead of changing the value it
@@ -268,6 +1003,46 @@ impl Settings {
             .map(|&(ref a, ref b)| (a, &**b))
     }
 
+    /// Registers a regex filter for the contents of snapshots.
+    ///
+    /// All matches of the regex in the serialized snapshot are replaced with
+    /// the replacement before the snapshot is compared.  Unlike redactions
+    /// this works for all snapshots including `assert_snapshot!` and
+    /// `assert_debug_snapshot!`.  The replacement can refer to groups of the
+    /// regex (eg: `$1`).  Filters are applied in the order they were added.
+    ///
+    /// ```rust
+    /// # use insta::Settings;
+    /// # let mut settings = Settings::new();
+    /// settings.add_filter(r"/Users/\w+", "[HOME]");
+    /// ```
+    ///
+    /// Panics if the regex is invalid.
+    #[cfg(feature = "filters")]
+    pub fn add_filter<S: Into<String>>(&mut self, regex: &str, replacement: S) {
+        self._private_inner_mut().filters.add(regex, replacement);
+    }
+
+    /// Replaces the currently set filters.
+    ///
+    /// The default set is empty.
+    #[cfg(feature = "filters")]
+    pub fn set_filters<F: Into<Filters>>(&mut self, filters: F) {
+        self._private_inner_mut().filters = filters.into();
+    }
+
+    /// Removes all filters.
+    #[cfg(feature = "filters")]
+    pub fn clear_filters(&mut self) {
+        self._private_inner_mut().filters.clear();
+    }
+
+    /// Returns the current filters.
+    #[cfg(feature = "filters")]
+    pub(crate) fn filters(&self) -> &Filters {
+        &self.inner.filters
+    }
+
     /// Sets the snapshot path.
     ///
     /// If not absolute it's relative to where the test is in.
@@ -349,3 +1124,81 @@ impl Settings {
         CURRENT_SETTINGS.with(|x| f(&*x.borrow()))
     }
 }
+
+/// Binds the settings of the current thread to a future.
+///
+/// Settings are bound to a thread so they are not available in tasks that
+/// are spawned onto an executor, like with `tokio::spawn`.  This captures the
+/// current settings when it is called and binds them whenever the future is
+/// polled which makes redactions and other settings of the test work within
+/// the task:
+///
+/// ```rust
+/// # async fn foo() {
+/// tokio::spawn(insta::with_captured_settings(async {
+///     // run snapshot test here
+/// }))
+/// .await
+/// .unwrap();
+/// # }
+/// ```
+///
+/// This is a shortcut for `Settings::clone_current().bind_async(future)`.
+pub fn with_captured_settings<F: Future<Output = T>, T>(future: F) -> impl Future<Output = T> {
+    Settings::clone_current().bind_async(future)
+}
+
+#[test]
+fn test_apply_config() {
+    let config: Config = serde_yaml::from_str(
+        "settings:\n  sort_maps: true\n  snapshot_path: custom\n  default_format: json\n  strip_ansi: false\n",
+    )
+    .unwrap();
+    let mut settings = Settings::new();
+    settings._private_inner_mut().apply_config(config.settings);
+    assert!(settings.sort_maps());
+    assert_eq!(settings.snapshot_path(), Path::new("custom"));
+    assert_eq!(settings.default_format(), Some("json"));
+    assert!(!settings.strip_ansi_escapes());
+    assert!(settings.prepend_module_to_snapshot());
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn test_load_config_file() {
+    let dir = env::temp_dir().join(format!("insta-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("Cargo.toml");
+    fs::write(
+        &path,
+        "[package]\nname = \"x\"\n\n[tool.insta]\nsort_maps = true\nsnapshot_path = \"custom\"\n\
+         other_tool_key = 1\n",
+    )
+    .unwrap();
+    let mut configs = load_config_file(&path).unwrap();
+    assert_eq!(configs.len(), 1);
+    let mut settings = Settings::new();
+    settings
+        ._private_inner_mut()
+        .apply_config(configs.pop().unwrap());
+    assert!(settings.sort_maps());
+    assert_eq!(settings.snapshot_path(), Path::new("custom"));
+
+    // errors name the problem instead of panicking
+    fs::write(&path, "[tool.insta]\nsort_maps = 1\n").unwrap();
+    assert!(load_config_file(&path).is_err());
+    assert!(load_config_file(&dir.join("missing.yaml"))
+        .unwrap()
+        .is_empty());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+#[cfg(feature = "filters")]
+fn test_config_filters() {
+    let config: Config =
+        serde_yaml::from_str("settings:\n  filters:\n    - [\"\\\\d+\", \"[N]\"]\n").unwrap();
+    let mut settings = Settings::new();
+    settings._private_inner_mut().apply_config(config.settings);
+    assert_eq!(settings.filters().apply_to("id 42"), "id [N]");
+}
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::fs;
 use std::io::{BufRead, BufReader, Write};
@@ -6,7 +7,10 @@ use std::time::{SystemTime, UNIX_EPOCH};
 
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
+use similar::TextDiff;
 
+use crate::output::{diff_lines, DiffHunk};
+use crate::serialization::{serialize_value, SerializationFormat, SnapshotLocation};
 use crate::utils::path_to_storage;
 
 lazy_static! {
@@ -73,21 +77,74 @@ pub struct MetaData {
     /// The source file (relative to workspace root).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) source: Option<String>,
+    /// A description of what the snapshot is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) description: Option<String>,
     /// Optionally the expression that created the snapshot.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) expression: Option<String>,
     /// Reference to the input file.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) input_file: Option<String>,
+    /// Marks the snapshot as known to be wrong.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) expected_failure: Option<bool>,
+    /// Marks the snapshot as never to be updated.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) frozen: Option<bool>,
+    /// Additional information that is not compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) tags: Option<BTreeMap<String, String>>,
+    /// Hash of the value before redactions and filters were applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) raw_hash: Option<String>,
+    /// Hash of the value after redactions and filters were applied.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) redacted_hash: Option<String>,
+    /// Hash of the contents if they are stored in a separate data file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) data_hash: Option<String>,
+    /// The file extension if it is not the default `snap`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) extension: Option<String>,
+    /// Hash of the bytes of a binary snapshot stored in a separate file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) binary_hash: Option<String>,
+    /// The version reported by `rustc` when the snapshot was created.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) rust_version: Option<String>,
+    /// The architecture, operating system and environment of the tests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) platform: Option<String>,
+    /// The type of the contents if it cannot be told from the contents.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) content_type: Option<String>,
+    /// The name of the golden file next to the pending snapshot that is
+    /// compared against instead of a snapshot file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(crate) golden_file: Option<String>,
 }
 
 impl MetaData {
     /// Creates a new metadata from the given inputs.
-    pub(crate) fn new(source: &str, expr: &str, input_file: Option<PathBuf>) -> MetaData {
+    pub(crate) fn new(source: &Path, expr: &str, input_file: Option<PathBuf>) -> MetaData {
         MetaData {
             source: Some(path_to_storage(source)),
+            description: None,
             expression: Some(expr.to_string()),
             input_file: input_file.map(path_to_storage),
+            expected_failure: None,
+            frozen: None,
+            tags: None,
+            raw_hash: None,
+            redacted_hash: None,
+            data_hash: None,
+            extension: None,
+            binary_hash: None,
+            rust_version: None,
+            platform: None,
+            content_type: None,
+            golden_file: None,
         }
     }
 
@@ -96,19 +153,26 @@ impl MetaData {
         self.source.as_deref()
     }
 
+    /// Returns the description of the snapshot.
+    pub fn description(&self) -> Option<&str> {
+        self.description.as_deref()
+    }
+
     /// Returns the expression that created the snapshot.
     pub fn expression(&self) -> Option<&str> {
         self.expression.as_deref()
     }
 
     /// Returns the relative source path.
+    ///
+    /// Sources outside of the base are returned as they are stored.
     pub fn get_relative_source(&self, base: &Path) -> Option<PathBuf> {
         self.source.as_ref().map(|source| {
             base.join(source)
                 .canonicalize()
                 .ok()
                 .and_then(|s| s.strip_prefix(base).ok().map(|x| x.to_path_buf()))
-                .unwrap_or_else(|| base.to_path_buf())
+                .unwrap_or_else(|| PathBuf::from(source))
         })
     }
 
@@ -116,6 +180,137 @@ impl MetaData {
     pub fn input_file(&self) -> Option<&str> {
         self.input_file.as_deref()
     }
+
+    /// Returns `true` if the snapshot is marked as an expected failure.
+    ///
+    /// Such snapshots are known to be wrong and are expected not to match
+    /// the value they are asserted against.
+    pub fn expected_failure(&self) -> bool {
+        self.expected_failure.unwrap_or(false)
+    }
+
+    /// Returns `true` if the snapshot is frozen.
+    ///
+    /// Frozen snapshots are never updated, not even with
+    /// `INSTA_UPDATE=always` or when accepted in a review.
+    pub fn frozen(&self) -> bool {
+        self.frozen.unwrap_or(false)
+    }
+
+    /// Returns the tags of the snapshot.
+    pub fn tags(&self) -> Option<&BTreeMap<String, String>> {
+        self.tags.as_ref()
+    }
+
+    /// Returns the hash of the value before redactions were applied.
+    pub fn raw_hash(&self) -> Option<&str> {
+        self.raw_hash.as_deref()
+    }
+
+    /// Returns the hash of the value after redactions were applied.
+    pub fn redacted_hash(&self) -> Option<&str> {
+        self.redacted_hash.as_deref()
+    }
+
+    /// Returns the hash of the contents stored in a separate data file.
+    pub fn data_hash(&self) -> Option<&str> {
+        self.data_hash.as_deref()
+    }
+
+    /// Returns the file extension of the snapshot if it is not `snap`.
+    pub fn extension(&self) -> Option<&str> {
+        self.extension.as_deref()
+    }
+
+    /// Returns the hash of the bytes of a binary snapshot.
+    pub fn binary_hash(&self) -> Option<&str> {
+        self.binary_hash.as_deref()
+    }
+
+    /// Returns the version reported by `rustc` when the snapshot was created.
+    ///
+    /// This is the compiler found when the tests ran which is not necessarily
+    /// the one that built them.
+    pub fn rust_version(&self) -> Option<&str> {
+        self.rust_version.as_deref()
+    }
+
+    /// Returns the platform the snapshot was created on.
+    ///
+    /// This is the architecture, operating system and environment such as
+    /// `x86_64-linux-gnu`, not a full target triple.
+    pub fn platform(&self) -> Option<&str> {
+        self.platform.as_deref()
+    }
+
+    /// Returns the type of the contents, for instance `csv`.
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    /// Returns the name of the golden file the snapshot is for.
+    pub fn golden_file(&self) -> Option<&str> {
+        self.golden_file.as_deref()
+    }
+
+    /// Returns `true` if no metadata is set.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.source.is_none()
+            && self.description.is_none()
+            && self.expression.is_none()
+            && self.input_file.is_none()
+            && self.expected_failure.is_none()
+            && self.frozen.is_none()
+            && self.tags.is_none()
+            && self.raw_hash.is_none()
+            && self.redacted_hash.is_none()
+            && self.data_hash.is_none()
+            && self.extension.is_none()
+            && self.binary_hash.is_none()
+            && self.rust_version.is_none()
+            && self.platform.is_none()
+            && self.content_type.is_none()
+            && self.golden_file.is_none()
+    }
+}
+
+/// The format of the contents of a snapshot.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotFormat {
+    /// Plain text such as the output of `assert_snapshot!`.
+    Text,
+    /// JSON as written by `assert_json_snapshot!`.
+    Json,
+    /// YAML as written by `assert_yaml_snapshot!`.
+    Yaml,
+    /// RON as written by `assert_ron_snapshot!`.
+    #[cfg(feature = "ron")]
+    Ron,
+}
+
+impl SnapshotFormat {
+    /// Looks up a format by its name.
+    pub fn from_name(name: &str) -> Option<SnapshotFormat> {
+        Some(match name {
+            "text" => SnapshotFormat::Text,
+            "json" => SnapshotFormat::Json,
+            "yaml" => SnapshotFormat::Yaml,
+            #[cfg(feature = "ron")]
+            "ron" => SnapshotFormat::Ron,
+            _ => return None,
+        })
+    }
+
+    /// Returns the name of the format.
+    pub fn name(self) -> &'static str {
+        match self {
+            SnapshotFormat::Text => "text",
+            SnapshotFormat::Json => "json",
+            SnapshotFormat::Yaml => "yaml",
+            #[cfg(feature = "ron")]
+            SnapshotFormat::Ron => "ron",
+        }
+    }
 }
 
 /// A helper to work with stored snapshots.
@@ -131,7 +326,11 @@ pub struct Snapshot {
 impl Snapshot {
     /// Loads a snapshot from a file.
     pub fn from_file<P: AsRef<Path>>(p: P) -> Result<Snapshot, Box<dyn Error>> {
-        let mut f = BufReader::new(fs::File::open(p.as_ref())?);
+        Snapshot::from_reader(BufReader::new(fs::File::open(p.as_ref())?), p.as_ref())
+    }
+
+    /// Loads a snapshot from a reader as if it was read from the given path.
+    fn from_reader<R: BufRead>(mut f: R, p: &Path) -> Result<Snapshot, Box<dyn Error>> {
         let mut buf = String::new();
 
         f.read_line(&mut buf)?;
@@ -148,7 +347,12 @@ impl Snapshot {
                     break;
                 }
             }
-            serde_yaml::from_str(&buf)?
+            // an empty header is written if metadata was omitted
+            if buf.trim_end() == "---" {
+                MetaData::default()
+            } else {
+                serde_yaml::from_str(&buf)?
+            }
         // legacy format
         } else {
             let mut rv = MetaData::default();
@@ -183,8 +387,20 @@ impl Snapshot {
             buf.push_str(&line);
         }
 
+        // externalized contents are loaded from the data file
+        if let Some(ref data_hash) = metadata.data_hash {
+            let data_path = data_file_path(p);
+            buf = fs::read_to_string(&data_path)?;
+            if &SnapshotContents::from(&*buf).hash() != data_hash {
+                return Err(format!(
+                    "data file {} does not match the hash in its snapshot",
+                    data_path.display()
+                )
+                .into());
+            }
+        }
+
         let module_name = p
-            .as_ref()
             .file_name()
             .unwrap()
             .to_str()
@@ -195,7 +411,6 @@ impl Snapshot {
             .to_string();
 
         let snapshot_name = p
-            .as_ref()
             .file_name()
             .unwrap()
             .to_str()
@@ -230,6 +445,53 @@ impl Snapshot {
         }
     }
 
+    /// Renames a snapshot file to give the snapshot a new name.
+    ///
+    /// The file name has to end in the old name of the snapshot.  The module
+    /// path before it is kept as is since it is separated by `__` as well,
+    /// and so is the extension.  The data file of an externalized snapshot,
+    /// the bytes of a binary snapshot and a pending snapshot are renamed
+    /// along.  Returns the new path of the snapshot file.
+    pub fn rename<P: AsRef<Path>>(
+        path: P,
+        old_name: &str,
+        new_name: &str,
+    ) -> Result<PathBuf, Box<dyn Error>> {
+        let path = path.as_ref();
+        if new_name.is_empty() || new_name.contains(|c| c == '.' || c == '/' || c == '\\') {
+            return Err(format!("invalid snapshot name '{}'", new_name).into());
+        }
+        let file_name = path
+            .file_name()
+            .and_then(|x| x.to_str())
+            .ok_or("invalid snapshot file name")?;
+        let (stem, extension) = match file_name.find('.') {
+            Some(idx) => file_name.split_at(idx),
+            None => (file_name, ""),
+        };
+        let new_file_name = if stem == old_name {
+            format!("{}{}", new_name, extension)
+        } else if stem.ends_with(&format!("__{}", old_name)) {
+            let module_path = &stem[..stem.len() - old_name.len() - 2];
+            format!("{}__{}{}", module_path, new_name, extension)
+        } else {
+            return Err(format!("{} is not named '{}'", path.display(), old_name).into());
+        };
+        let new_path = path.with_file_name(new_file_name);
+        if new_path.exists() {
+            return Err(format!("snapshot file {} already exists", new_path.display()).into());
+        }
+
+        fs::rename(path, &new_path)?;
+        for sidecar_path in &[data_file_path, binary_file_path, pending_file_path] {
+            let old_sidecar = sidecar_path(path);
+            if old_sidecar.is_file() {
+                fs::rename(&old_sidecar, sidecar_path(&new_path))?;
+            }
+        }
+        Ok(new_path)
+    }
+
     /// Returns the module name.
     pub fn module_name(&self) -> &str {
         &self.module_name
@@ -255,18 +517,263 @@ impl Snapshot {
         &self.snapshot.0
     }
 
+    /// Diffs the contents of an old snapshot against this snapshot.
+    ///
+    /// If there is no old snapshot all lines are reported as added.  This is
+    /// the same diff that `cargo insta review` shows and can be used to
+    /// render it in other tools.
+    pub fn diff(&self, old: Option<&Snapshot>) -> Vec<DiffHunk> {
+        diff_lines(old.map_or("", |x| x.contents_str()), self.contents_str())
+    }
+
+    /// Detects the format of the snapshot contents.
+    ///
+    /// Formats that cannot be told from the contents such as RON are recorded
+    /// as `content_type` in the metadata.  Otherwise the format is guessed by
+    /// parsing the contents.  Contents that are neither JSON nor a YAML map or
+    /// sequence are considered text.
+    pub fn format(&self) -> SnapshotFormat {
+        if let Some(content_type) = self.metadata.content_type() {
+            return SnapshotFormat::from_name(content_type).unwrap_or(SnapshotFormat::Text);
+        }
+        let contents = self.contents_str().trim_start();
+        if (contents.starts_with('{') || contents.starts_with('['))
+            && serde_json::from_str::<serde_json::Value>(contents).is_ok()
+        {
+            return SnapshotFormat::Json;
+        }
+        match serde_yaml::from_str::<serde_yaml::Value>(contents) {
+            Ok(serde_yaml::Value::Mapping(_)) | Ok(serde_yaml::Value::Sequence(_)) => {
+                SnapshotFormat::Yaml
+            }
+            _ => SnapshotFormat::Text,
+        }
+    }
+
+    /// Serializes the snapshot contents into another format.
+    ///
+    /// The metadata is kept apart from the hashes of the contents.  Fails for
+    /// text snapshots as they have no structured representation.
+    pub fn convert_to(&self, format: SnapshotFormat) -> Result<Snapshot, Box<dyn Error>> {
+        if self.format() == SnapshotFormat::Text {
+            return Err("text snapshots cannot be converted to another format".into());
+        }
+        let serialization_format = match format {
+            SnapshotFormat::Json => SerializationFormat::Json,
+            SnapshotFormat::Yaml => SerializationFormat::Yaml,
+            #[cfg(feature = "ron")]
+            SnapshotFormat::Ron => SerializationFormat::Ron,
+            SnapshotFormat::Text => {
+                return Err("snapshots cannot be converted to text".into());
+            }
+        };
+        #[cfg(feature = "ron")]
+        {
+            if self.format() == SnapshotFormat::Ron {
+                // RON structs lose their names and maps their order here
+                let value: ron::Value = ron::from_str(self.contents_str())?;
+                return Ok(self.with_contents(
+                    SnapshotContents(
+                        serialize_value(&value, serialization_format, SnapshotLocation::File).value,
+                    ),
+                    format,
+                ));
+            }
+        }
+        // YAML is a superset of JSON and keeps the order of maps
+        let value: serde_yaml::Value = serde_yaml::from_str(self.contents_str())?;
+        Ok(self.with_contents(
+            SnapshotContents(
+                serialize_value(&value, serialization_format, SnapshotLocation::File).value,
+            ),
+            format,
+        ))
+    }
+
+    /// Returns a copy of the snapshot with contents in another format.
+    fn with_contents(&self, contents: SnapshotContents, format: SnapshotFormat) -> Snapshot {
+        let mut metadata = self.metadata.clone();
+        metadata.content_type = match format {
+            #[cfg(feature = "ron")]
+            SnapshotFormat::Ron => Some(format.name().to_string()),
+            _ => None,
+        };
+        metadata.raw_hash = None;
+        metadata.redacted_hash = None;
+        if metadata.data_hash.is_some() {
+            metadata.data_hash = Some(contents.hash());
+        }
+        Snapshot::from_components(
+            self.module_name.clone(),
+            self.snapshot_name.clone(),
+            metadata,
+            contents,
+        )
+    }
+
     pub(crate) fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
         let path = path.as_ref();
         if let Some(folder) = path.parent() {
             fs::create_dir_all(&folder)?;
         }
-        let mut f = fs::File::create(&path)?;
-        serde_yaml::to_writer(&mut f, &self.metadata)?;
-        f.write_all(b"\n---\n")?;
-        f.write_all(self.contents_str().as_bytes())?;
-        f.write_all(b"\n")?;
+        fs::write(path, self.to_file_contents()?)?;
+        let data_path = data_file_path(path);
+        if self.metadata.data_hash.is_some() {
+            fs::write(&data_path, self.contents_str())?;
+        } else {
+            // the contents might have been externalized before
+            fs::remove_file(&data_path).ok();
+        }
         Ok(())
     }
+
+    /// Returns what is written to the snapshot file.
+    ///
+    /// Externalized contents are not included as they go to the data file.
+    fn to_file_contents(&self) -> Result<String, Box<dyn Error>> {
+        let mut rv = if self.metadata.is_empty() {
+            "---\n---\n".to_string()
+        } else {
+            // newer versions of serde_yaml end the document with a newline
+            format!(
+                "{}\n---\n",
+                serde_yaml::to_string(&self.metadata)?.trim_end()
+            )
+        };
+        if self.metadata.data_hash.is_none() {
+            rv.push_str(self.contents_str());
+            rv.push('\n');
+        }
+        Ok(rv)
+    }
+}
+
+/// Returns a stable hash of some bytes.
+pub(crate) fn hash_bytes(bytes: &[u8]) -> String {
+    // 64 bit FNV-1a which does not change between Rust versions
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for &byte in bytes {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    format!("{:016x}", hash)
+}
+
+/// Returns the path of the pending file for a snapshot file.
+///
+/// This is the path of the snapshot file with an added `.new` extension.
+pub fn pending_file_path(path: &Path) -> PathBuf {
+    let mut rv = path.as_os_str().to_owned();
+    rv.push(".new");
+    PathBuf::from(rv)
+}
+
+/// Returns the path of the data file of an externalized snapshot.
+///
+/// This is the path of the snapshot file with an added `.data` extension.
+pub fn data_file_path(path: &Path) -> PathBuf {
+    let mut rv = path.as_os_str().to_owned();
+    rv.push(".data");
+    PathBuf::from(rv)
+}
+
+/// Returns the path of the file with the bytes of a binary snapshot.
+///
+/// This is the path of the snapshot file with an added `.bin` extension.
+pub fn binary_file_path(path: &Path) -> PathBuf {
+    let mut rv = path.as_os_str().to_owned();
+    rv.push(".bin");
+    PathBuf::from(rv)
+}
+
+/// Returns a snapshot with the metadata of another snapshot but new contents.
+pub fn snapshot_with_contents(snapshot: &Snapshot, contents: &str) -> Snapshot {
+    let contents = SnapshotContents(contents.to_string());
+    let mut metadata = snapshot.metadata.clone();
+    if metadata.data_hash.is_some() {
+        metadata.data_hash = Some(contents.hash());
+    }
+    Snapshot::from_components(
+        snapshot.module_name.clone(),
+        snapshot.snapshot_name.clone(),
+        metadata,
+        contents,
+    )
+}
+
+/// Writes a snapshot with the metadata of another snapshot but new contents.
+pub fn save_snapshot_with_contents<P: AsRef<Path>>(
+    snapshot: &Snapshot,
+    contents: &str,
+    path: P,
+) -> Result<(), Box<dyn Error>> {
+    snapshot_with_contents(snapshot, contents).save(path)
+}
+
+/// Loads a snapshot from a string as if it was read from the given path.
+pub fn snapshot_from_str(contents: &str, path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+    Snapshot::from_reader(contents.as_bytes(), path)
+}
+
+/// Returns what saving a snapshot writes to the snapshot file.
+pub fn snapshot_file_contents(snapshot: &Snapshot) -> Result<String, Box<dyn Error>> {
+    snapshot.to_file_contents()
+}
+
+/// Writes a new snapshot file for the given contents.
+///
+/// This is used to move inline snapshots into snapshot files.
+pub fn save_named_snapshot<P: AsRef<Path>>(
+    path: P,
+    module_name: &str,
+    snapshot_name: &str,
+    source: &Path,
+    expression: &str,
+    contents: SnapshotContents,
+) -> Result<(), Box<dyn Error>> {
+    Snapshot::from_components(
+        module_name.to_string(),
+        Some(snapshot_name.to_string()),
+        MetaData::new(source, expression, None),
+        contents,
+    )
+    .save(path)
+}
+
+/// Returns the path of the pending snapshot for a golden file.
+///
+/// This is the path of the golden file with an added `.snap.new` extension.
+pub fn golden_pending_file_path(path: &Path) -> PathBuf {
+    let mut rv = path.as_os_str().to_owned();
+    rv.push(".snap.new");
+    PathBuf::from(rv)
+}
+
+/// Loads a golden file as snapshot.
+///
+/// Golden files have no metadata header, the whole file is the contents.
+pub fn load_golden_file(path: &Path) -> Result<Snapshot, Box<dyn Error>> {
+    Ok(golden_file_snapshot(path, fs::read_to_string(path)?))
+}
+
+/// Returns the contents of a golden file as snapshot.
+pub(crate) fn golden_file_snapshot(path: &Path, contents: String) -> Snapshot {
+    let name = path.file_name().map(|x| x.to_string_lossy().into_owned());
+    let metadata = MetaData {
+        golden_file: name.clone(),
+        ..MetaData::default()
+    };
+    Snapshot::from_components(String::new(), name, metadata, contents.into())
+}
+
+/// Writes the contents of a snapshot to a golden file.
+///
+/// Golden files always end in a single newline.
+pub fn save_golden_file(path: &Path, contents: &SnapshotContents) -> Result<(), Box<dyn Error>> {
+    let mut contents = contents.0.trim_end().to_string();
+    contents.push('\n');
+    fs::write(path, contents)?;
+    Ok(())
 }
 
 /// The contents of a Snapshot
@@ -275,6 +782,54 @@ impl Snapshot {
 pub struct SnapshotContents(String);
 
 impl SnapshotContents {
+    /// Compares the contents as data instead of as text.
+    ///
+    /// Contents serialized as JSON, YAML or TOML are parsed in that format
+    /// and compared as values which makes the order of keys in maps
+    /// irrelevant.  If `unordered_sequences` is set the order of items in
+    /// sequences is ignored as well.  Other contents and contents that cannot
+    /// be parsed are compared as text.
+    pub(crate) fn matches_structurally(
+        &self,
+        other: &SnapshotContents,
+        format: Option<SerializationFormat>,
+        unordered_sequences: bool,
+    ) -> bool {
+        let parse = |contents: &SnapshotContents| {
+            let value: serde_json::Value = match format {
+                // JSON is read as YAML as well
+                Some(SerializationFormat::Json) | Some(SerializationFormat::Yaml) => {
+                    serde_yaml::from_str(&contents.0).ok()?
+                }
+                #[cfg(feature = "toml")]
+                Some(SerializationFormat::Toml) => toml::from_str(&contents.0).ok()?,
+                _ => return None,
+            };
+            Some(normalize_value(value, unordered_sequences))
+        };
+        match (parse(self), parse(other)) {
+            (Some(a), Some(b)) => a == b,
+            _ => self == other,
+        }
+    }
+
+    /// Returns how similar the contents are by the ratio of unchanged lines.
+    pub(crate) fn similarity(&self, other: &SnapshotContents) -> f64 {
+        f64::from(TextDiff::from_lines(self.0.trim_end(), other.0.trim_end()).ratio())
+    }
+
+    /// Returns the length of the contents in bytes.
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns a stable hash of the contents.
+    ///
+    /// Trailing whitespace is ignored like for comparisons.
+    pub fn hash(&self) -> String {
+        hash_bytes(self.0.trim_end().as_bytes())
+    }
+
     pub fn from_inline(value: &str) -> SnapshotContents {
         SnapshotContents(get_inline_snapshot_value(value))
     }
@@ -314,6 +869,33 @@ impl SnapshotContents {
     }
 }
 
+/// Brings a value into a canonical form.
+///
+/// Keys of objects are sorted and if requested so are the items of arrays.
+fn normalize_value(value: serde_json::Value, unordered_sequences: bool) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(map) => {
+            let mut items = map
+                .into_iter()
+                .map(|(key, value)| (key, normalize_value(value, unordered_sequences)))
+                .collect::<Vec<_>>();
+            items.sort_by(|a, b| a.0.cmp(&b.0));
+            serde_json::Value::Object(items.into_iter().collect())
+        }
+        serde_json::Value::Array(items) => {
+            let mut items = items
+                .into_iter()
+                .map(|value| normalize_value(value, unordered_sequences))
+                .collect::<Vec<_>>();
+            if unordered_sequences {
+                items.sort_by_cached_key(|value| value.to_string());
+            }
+            serde_json::Value::Array(items)
+        }
+        other => other,
+    }
+}
+
 impl From<&str> for SnapshotContents {
     fn from(value: &str) -> SnapshotContents {
         // make sure we have unix newlines consistently
@@ -629,3 +1211,144 @@ fn test_inline_snapshot_value_newline() {
     // https://github.com/mitsuhiko/insta/issues/39
     assert_eq!(get_inline_snapshot_value("\n"), "");
 }
+
+#[test]
+fn test_rename() {
+    let dir = std::env::temp_dir().join(format!("insta-rename-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("module__old.snap");
+    fs::write(&path, "---\n---\nvalue\n").unwrap();
+    fs::write(data_file_path(&path), "value").unwrap();
+
+    let new_path = Snapshot::rename(&path, "old", "new").unwrap();
+    assert_eq!(new_path, dir.join("module__new.snap"));
+    assert!(!path.exists());
+    assert!(data_file_path(&new_path).is_file());
+    let snapshot = Snapshot::from_file(&new_path).unwrap();
+    assert_eq!(snapshot.module_name(), "module");
+    assert_eq!(snapshot.snapshot_name(), Some("new"));
+    assert!(Snapshot::rename(&new_path, "new", "a.b").is_err());
+    assert!(Snapshot::rename(&new_path, "other", "b").is_err());
+
+    // the module path of a nested module is kept
+    let path = dir.join("mycrate__tests__old.snap");
+    fs::write(&path, "---\n---\nvalue\n").unwrap();
+    let new_path = Snapshot::rename(&path, "old", "new").unwrap();
+    assert_eq!(new_path, dir.join("mycrate__tests__new.snap"));
+    assert!(!path.exists());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_relative_source() {
+    let base = std::env::current_dir().unwrap();
+    let local = MetaData::new(&base.join("src/lib.rs"), "expr", None);
+    assert_eq!(
+        local.get_relative_source(&base),
+        Some(PathBuf::from("src/lib.rs"))
+    );
+    let external = MetaData::new(Path::new("/nonexistent/lib.rs"), "expr", None);
+    assert_eq!(
+        external.get_relative_source(&base),
+        Some(PathBuf::from("/nonexistent/lib.rs"))
+    );
+}
+
+#[test]
+fn test_file_contents() {
+    let snapshot = Snapshot::from_components(
+        "module".into(),
+        Some("name".into()),
+        MetaData::new(Path::new("src/lib.rs"), "expr", None),
+        SnapshotContents::from("value"),
+    );
+    assert_eq!(
+        snapshot.to_file_contents().unwrap(),
+        "---\nsource: src/lib.rs\nexpression: expr\n---\nvalue\n"
+    );
+}
+
+#[test]
+fn test_similarity() {
+    let a = SnapshotContents::from("a\nb\nc\nd\n");
+    assert_eq!(a.similarity(&SnapshotContents::from("a\nb\nc\nd")), 1.0);
+    assert_eq!(a.similarity(&SnapshotContents::from("a\nb\nx\nd")), 0.75);
+    assert_eq!(a.similarity(&SnapshotContents::from("w\nx\ny\nz")), 0.0);
+}
+
+#[test]
+fn test_matches_structurally() {
+    let a = SnapshotContents::from("---\na: 1\nb:\n  - 1\n  - 2\n");
+    let b = SnapshotContents::from("{\"b\": [1, 2], \"a\": 1}");
+    let c = SnapshotContents::from("{\"b\": [2, 1], \"a\": 1}");
+    let yaml = Some(SerializationFormat::Yaml);
+    assert!(a.matches_structurally(&b, yaml, false));
+    assert!(!a.matches_structurally(&c, yaml, false));
+    assert!(a.matches_structurally(&c, yaml, true));
+    assert!(!a.matches_structurally(&SnapshotContents::from("a: 2\n"), yaml, true));
+
+    // values that were not serialized are compared as text
+    let d = SnapshotContents::from("b: [1, 2]\na: 1");
+    assert!(a.matches_structurally(&d, yaml, false));
+    assert!(!a.matches_structurally(&d, None, false));
+}
+
+#[test]
+#[cfg(feature = "toml")]
+fn test_matches_structurally_toml() {
+    let a = SnapshotContents::from("a = 1\nb = [1, 2]\n");
+    let b = SnapshotContents::from("b = [1, 2]\na = 1\n");
+    assert!(a.matches_structurally(&b, Some(SerializationFormat::Toml), false));
+    assert!(!a.matches_structurally(&b, None, false));
+}
+
+#[test]
+fn test_convert_to() {
+    let snapshot = |contents: &str| {
+        Snapshot::from_components(
+            "test".into(),
+            Some("convert".into()),
+            MetaData::default(),
+            SnapshotContents::from(contents),
+        )
+    };
+    let json = snapshot("{\n  \"b\": [\n    1\n  ],\n  \"a\": true\n}");
+    assert_eq!(json.format(), SnapshotFormat::Json);
+    let yaml = json.convert_to(SnapshotFormat::Yaml).unwrap();
+    assert_eq!(yaml.format(), SnapshotFormat::Yaml);
+    assert_eq!(yaml.contents_str(), "b:\n  - 1\na: true\n");
+    let back = yaml.convert_to(SnapshotFormat::Json).unwrap();
+    assert_eq!(back.contents_str(), json.contents_str());
+
+    let text = snapshot("Hello World!");
+    assert_eq!(text.format(), SnapshotFormat::Text);
+    assert!(text.convert_to(SnapshotFormat::Yaml).is_err());
+}
+
+#[test]
+#[cfg(feature = "ron")]
+fn test_convert_ron() {
+    let snapshot = |contents: &str| {
+        Snapshot::from_components(
+            "test".into(),
+            Some("convert".into()),
+            MetaData::default(),
+            SnapshotContents::from(contents),
+        )
+    };
+    let mut ron = snapshot("Point(\n  x: 1,\n  y: 2,\n)");
+    assert_eq!(ron.format(), SnapshotFormat::Text);
+    ron.metadata.content_type = Some("ron".into());
+    assert_eq!(ron.format(), SnapshotFormat::Ron);
+    // pretty printed debug output looks like RON but is not recorded as such
+    let debug = snapshot("[\n    Point {\n        x: 1,\n    },\n]");
+    assert_eq!(debug.format(), SnapshotFormat::Text);
+    assert_eq!(snapshot("[\n  1,\n  2,\n]").format(), SnapshotFormat::Yaml);
+
+    let json = ron.convert_to(SnapshotFormat::Json).unwrap();
+    assert_eq!(json.contents_str(), "{\n  \"x\": 1,\n  \"y\": 2\n}");
+    assert_eq!(json.metadata().content_type(), None);
+    let back = json.convert_to(SnapshotFormat::Ron).unwrap();
+    assert_eq!(back.contents_str(), "{\n  \"x\": 1,\n  \"y\": 2,\n}");
+    assert_eq!(back.format(), SnapshotFormat::Ron);
+}
@@ -0,0 +1,73 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::unix::io::{AsRawFd, RawFd};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+
+lazy_static! {
+    // stdout is shared by the entire process so only one capture can be
+    // active at any point in time.
+    static ref CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+static CAPTURE_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Points stdout back to the original file descriptor when dropped.
+///
+/// This also happens if the captured closure panics so that the panic
+/// and the output of other tests are not swallowed.
+struct RedirectGuard {
+    saved_fd: RawFd,
+}
+
+impl Drop for RedirectGuard {
+    fn drop(&mut self) {
+        io::stdout().flush().ok();
+        unsafe {
+            libc::dup2(self.saved_fd, libc::STDOUT_FILENO);
+            libc::close(self.saved_fd);
+        }
+    }
+}
+
+/// Runs a closure and returns everything it wrote to the process stdout.
+///
+/// The stdout file descriptor is redirected into a temporary file while the
+/// closure runs.  Line endings are normalized to unix newlines.
+pub fn capture_stdout<F: FnOnce()>(f: F) -> String {
+    let _lock = CAPTURE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+
+    let path = std::env::temp_dir().join(format!(
+        "insta-stdout-{}-{}",
+        process::id(),
+        CAPTURE_COUNTER.fetch_add(1, Ordering::SeqCst)
+    ));
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&path)
+        .expect("failed to create file to capture stdout");
+    fs::remove_file(&path).ok();
+
+    io::stdout().flush().ok();
+    let guard = unsafe {
+        let saved_fd = libc::dup(libc::STDOUT_FILENO);
+        if saved_fd < 0 || libc::dup2(file.as_raw_fd(), libc::STDOUT_FILENO) < 0 {
+            panic!("failed to capture stdout: {}", io::Error::last_os_error());
+        }
+        RedirectGuard { saved_fd }
+    };
+    f();
+    drop(guard);
+
+    let mut rv = String::new();
+    file.seek(SeekFrom::Start(0))
+        .and_then(|_| file.read_to_string(&mut rv))
+        .expect("failed to read captured stdout");
+    rv.replace("\r\n", "\n")
+}
@@ -6,13 +6,29 @@ use std::{
     process::{Command, Stdio},
 };
 
+use crate::env::get_max_width;
+
 /// Are we running in in a CI environment?
 pub fn is_ci() -> bool {
     env::var("CI").is_ok() || env::var("TF_BUILD").is_ok()
 }
 
+/// Are colors disabled with the `NO_COLOR` environment variable?
 #[cfg(feature = "colors")]
-pub use console::style;
+pub fn no_color() -> bool {
+    env::var("NO_COLOR").map_or(false, |x| !x.is_empty())
+}
+
+/// Wraps a value for styling unless colors are disabled with `NO_COLOR`.
+#[cfg(feature = "colors")]
+pub fn style<D>(val: D) -> console::StyledObject<D> {
+    let rv = console::style(val);
+    if no_color() {
+        rv.force_styling(false)
+    } else {
+        rv
+    }
+}
 
 #[cfg(not(feature = "colors"))]
 mod fake_colors {
@@ -46,14 +62,16 @@ mod fake_colors {
 pub use self::fake_colors::*;
 
 /// Returns the term width that insta should use.
+///
+/// The width is capped by `INSTA_MAX_WIDTH` if set.
 pub fn term_width() -> usize {
     #[cfg(feature = "colors")]
-    {
-        console::Term::stdout().size().1 as usize
-    }
+    let width = console::Term::stdout().size().1 as usize;
     #[cfg(not(feature = "colors"))]
-    {
-        74
+    let width = 74;
+    match get_max_width() {
+        Some(max_width) => width.min(max_width),
+        None => width,
     }
 }
 
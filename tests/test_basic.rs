@@ -1,5 +1,8 @@
 use insta::{
-    assert_debug_snapshot, assert_display_snapshot, assert_json_snapshot, assert_yaml_snapshot,
+    assert_binary_snapshot, assert_command_snapshot, assert_debug_snapshot,
+    assert_display_snapshot, assert_error_chain_snapshot, assert_fs_snapshot, assert_json_snapshot,
+    assert_lines_snapshot, assert_snapshot, assert_snapshot_against, assert_snapshot_matches_file,
+    assert_typed_snapshot, assert_yaml_snapshot,
 };
 use std::fmt;
 
@@ -67,8 +70,195 @@ fn test_unnamed_display() {
     assert_display_snapshot!("whatever");
 }
 
+#[test]
+fn test_format_string() {
+    let value = 42;
+    assert_snapshot!(format = "label: {}", value);
+    assert_snapshot!("format_string_named", format = "{}-{}", value, "x");
+}
+
 #[test]
 fn test_u128_json() {
     let x: u128 = 42;
     assert_json_snapshot!(&x, @"42");
 }
+
+#[test]
+fn test_lines() {
+    assert_lines_snapshot!(vec![(1, "one"), (2, "two"), (3, "three")], @r###"
+    (1, "one")
+    (2, "two")
+    (3, "three")
+    "###);
+    assert_lines_snapshot!(["a", "b"].iter().map(|x| x.to_uppercase()), @r###"
+    "A"
+    "B"
+    "###);
+}
+
+#[test]
+fn test_expected_failure() {
+    assert_snapshot!("expected_failure", "the correct output");
+}
+
+#[test]
+#[should_panic(expected = "unexpectedly passing")]
+fn test_expected_failure_unexpectedly_passing() {
+    assert_snapshot!("expected_failure_passing", "the known output");
+}
+
+#[test]
+fn test_frozen() {
+    assert_snapshot!("frozen", "the frozen output");
+}
+
+#[test]
+#[should_panic(expected = "The snapshot is frozen")]
+fn test_frozen_mismatch() {
+    assert_snapshot!("frozen_mismatch", "a changed output");
+}
+
+#[derive(Debug)]
+struct ChainedError(&'static str, Option<Box<ChainedError>>);
+
+impl fmt::Display for ChainedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for ChainedError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.1.as_deref().map(|x| x as _)
+    }
+}
+
+#[test]
+fn test_error_chain() {
+    let err = ChainedError(
+        "failed to load config",
+        Some(Box::new(ChainedError(
+            "failed to read file at 0x7ffd5e8e4a10",
+            Some(Box::new(ChainedError("permission denied", None))),
+        ))),
+    );
+    assert_error_chain_snapshot!(err, @r###"
+    0: failed to load config
+    1: caused by failed to read file at 0x[addr]
+    2: caused by permission denied
+    "###);
+    let boxed: Box<dyn std::error::Error> = Box::new(err);
+    assert_error_chain_snapshot!(&*boxed, @r###"
+    0: failed to load config
+    1: caused by failed to read file at 0x[addr]
+    2: caused by permission denied
+    "###);
+}
+
+#[test]
+fn test_typed_snapshot() {
+    assert_typed_snapshot!(Some(42u8), @r###"
+    core::option::Option<u8>: Some(
+        42,
+    )
+    "###);
+    assert_typed_snapshot!(vec![1u8, 2], @r###"
+    alloc::vec::Vec<u8>: [
+        1,
+        2,
+    ]
+    "###);
+}
+
+#[test]
+fn test_golden_file() {
+    assert_snapshot_against!(include_str!("golden/basic.txt"), "Hello\nWorld!");
+}
+
+#[test]
+fn test_golden_file_at_runtime() {
+    assert_snapshot_matches_file!("Hello\nWorld!", "golden/basic.txt");
+}
+
+#[test]
+fn test_fs_snapshot() {
+    let dir = std::env::temp_dir().join(format!("insta-test-fs-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("unchanged.txt"), "old").unwrap();
+    std::fs::write(dir.join("config.toml"), "debug = false\n").unwrap();
+    assert_fs_snapshot!(&dir, || {
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {\n\n}\n").unwrap();
+        std::fs::write(dir.join("config.toml"), "debug = true\n").unwrap();
+        std::fs::write(dir.join("logo.bin"), [0xffu8, 0xfe, 0x00]).unwrap();
+    }, @r###"
+    modified config.toml
+        debug = true
+    created logo.bin
+        [binary, 3 bytes, hash f994951be4786a10]
+    created src/main.rs
+        fn main() {
+
+        }
+    "###);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(unix)]
+#[test]
+fn test_command_snapshot() {
+    let out = std::env::temp_dir().join("out.txt");
+    assert_command_snapshot!(std::process::Command::new("sh")
+        .arg("-c")
+        .arg("printf '\\033[1;32mwrote\\033[0m %s\\n' \"$0\"; echo oops >&2; exit 3")
+        .arg(&out), @r###"
+    success: false
+    exit_code: 3
+    ----- stdout -----
+    wrote [TEMP_DIR]/out.txt
+    ----- stderr -----
+    oops
+    "###);
+}
+
+#[test]
+fn test_binary_snapshot() {
+    assert_binary_snapshot!("binary", vec![0u8, 159, 146, 150]);
+}
+
+#[test]
+fn test_lazy_snapshot() {
+    let expensive = || (1..=4).map(|x| x * x).collect::<Vec<u64>>();
+    assert_snapshot!(|| format!("{:?}", expensive()), @"[1, 4, 9, 16]");
+    assert_debug_snapshot!(|| expensive().len(), @"4");
+    assert_yaml_snapshot!(|| expensive(), @r###"
+    ---
+    - 1
+    - 4
+    - 9
+    - 16
+    "###);
+    assert_json_snapshot!("lazy_json", || expensive());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn test_csv_content_type() {
+    insta::assert_csv_snapshot!("csv_rows", vec![(1, "Alice"), (2, "Bob")]);
+    let snapshot = insta::Snapshot::from_file("tests/snapshots/test_basic__csv_rows.snap").unwrap();
+    assert_eq!(snapshot.metadata().content_type(), Some("csv"));
+}
+
+#[cfg(feature = "schemars")]
+#[test]
+fn test_schema_snapshot() {
+    #[derive(schemars::JsonSchema)]
+    #[allow(dead_code)]
+    struct User {
+        id: u32,
+        username: String,
+        email: Option<String>,
+    }
+
+    insta::assert_schema_snapshot!("user_schema", User);
+}
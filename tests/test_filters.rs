@@ -0,0 +1,27 @@
+#![cfg(feature = "filters")]
+
+use insta::{assert_debug_snapshot, assert_snapshot, with_settings, Settings};
+
+#[test]
+fn test_basic_filter() {
+    let mut settings = Settings::new();
+    settings.add_filter(r"/Users/\w+", "[HOME]");
+    settings.bind(|| {
+        assert_snapshot!("config at /Users/jane/.config/app.toml", @"config at [HOME]/.config/app.toml");
+    });
+}
+
+#[test]
+fn test_filter_order() {
+    with_settings!({filters => vec![
+        (r"\b[[:xdigit:]]{8}\b", "[ID]"),
+        (r"\[ID\]-(\d+)", "[ID]-[N]"),
+    ]}, {
+        assert_debug_snapshot!(vec!["deadbeef-1", "0badf00d-42"], @r###"
+        [
+            "[ID]-[N]",
+            "[ID]-[N]",
+        ]
+        "###);
+    });
+}
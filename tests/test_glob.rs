@@ -23,3 +23,30 @@ fn test_empty_glob_fails() {
         // nothing
     });
 }
+
+#[test]
+#[should_panic(expected = "the glob! macro ran no snapshot assertions for: inputs/goodbye.txt")]
+fn test_glob_requires_assertions() {
+    let mut settings = insta::Settings::clone_current();
+    settings.set_require_glob_assertions(true);
+    settings.bind(|| {
+        insta::glob!("inputs/*.txt", |path| {
+            if path.ends_with("hello.txt") {
+                insta::assert_snapshot!("hello", @"hello");
+            }
+        });
+    });
+}
+
+#[test]
+fn test_glob_relative_path() {
+    insta::glob!("inputs/nested/*/data.txt", |path, rel_path| {
+        let contents = std::fs::read_to_string(path).unwrap();
+        let rel_path = rel_path
+            .components()
+            .map(|x| x.as_os_str().to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join("/");
+        insta::assert_snapshot!(format!("{}: {}", rel_path, contents));
+    });
+}
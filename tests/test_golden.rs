@@ -0,0 +1,56 @@
+use std::env;
+use std::fs;
+use std::panic::catch_unwind;
+use std::path::Path;
+
+use insta::{assert_snapshot_against, assert_snapshot_matches_file, with_settings, Snapshot};
+
+#[test]
+fn test_golden_file_mismatch() {
+    let old_update_value = env::var("INSTA_UPDATE");
+    let old_force_pass_value = env::var("INSTA_FORCE_PASS");
+    env::set_var("INSTA_UPDATE", "new");
+    env::set_var("INSTA_FORCE_PASS", "0");
+
+    let dir = env::temp_dir().join(format!("insta-test-golden-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let golden_path = dir.join("golden.txt");
+    let pending_path = dir.join("golden.txt.snap.new");
+    fs::write(&golden_path, "Hello\n").unwrap();
+
+    // nothing is written for in-memory snapshots
+    with_settings!({in_memory => true}, {
+        assert!(catch_unwind(|| {
+            assert_snapshot_matches_file!("World", golden_path.to_str().unwrap());
+        })
+        .is_err());
+        assert!(catch_unwind(|| {
+            assert_snapshot_against!(include_str!("golden/basic.txt"), "World");
+        })
+        .is_err());
+    });
+    assert!(!pending_path.exists());
+    assert!(!Path::new("tests/golden/basic.txt.snap.new").exists());
+
+    // otherwise the new value is pending for review
+    let mismatch = catch_unwind(|| {
+        assert_snapshot_matches_file!("World", golden_path.to_str().unwrap());
+    });
+    let pending = Snapshot::from_file(&pending_path).unwrap();
+
+    if let Ok(value) = old_update_value {
+        env::set_var("INSTA_UPDATE", value);
+    } else {
+        env::remove_var("INSTA_UPDATE");
+    }
+    if let Ok(value) = old_force_pass_value {
+        env::set_var("INSTA_FORCE_PASS", value);
+    } else {
+        env::remove_var("INSTA_FORCE_PASS");
+    }
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert!(mismatch.is_err());
+    assert_eq!(pending.contents_str(), "World");
+    assert_eq!(pending.metadata().golden_file(), Some("golden.txt"));
+}
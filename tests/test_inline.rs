@@ -166,6 +166,38 @@ fn test_json_inline() {
     "###);
 }
 
+#[cfg(feature = "redactions")]
+#[test]
+fn test_json_inline_redacted() {
+    #[derive(Serialize)]
+    pub struct User {
+        id: u32,
+        username: String,
+        roles: Vec<&'static str>,
+    }
+
+    assert_json_snapshot!(
+        User {
+            id: 42,
+            username: "peter-pan".into(),
+            roles: vec!["admin", "user"],
+        },
+        {
+            ".id" => "[id]",
+        },
+        @r###"
+        {
+          "id": "[id]",
+          "username": "peter-pan",
+          "roles": [
+            "admin",
+            "user"
+          ]
+        }
+        "###
+    );
+}
+
 #[test]
 fn test_yaml_inline() {
     #[derive(Serialize)]
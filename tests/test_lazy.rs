@@ -0,0 +1,31 @@
+use std::cell::Cell;
+use std::env;
+
+use insta::assert_snapshot;
+
+#[test]
+fn test_lazy_value_skipped() {
+    let old_update_value = env::var("INSTA_UPDATE");
+    let old_force_pass_value = env::var("INSTA_FORCE_PASS");
+    env::set_var("INSTA_UPDATE", "no");
+    env::set_var("INSTA_FORCE_PASS", "1");
+
+    let called = Cell::new(false);
+    assert_snapshot!("lazy_skipped", || {
+        called.set(true);
+        String::from("expensive")
+    });
+
+    if let Ok(value) = old_update_value {
+        env::set_var("INSTA_UPDATE", value);
+    } else {
+        env::remove_var("INSTA_UPDATE");
+    }
+    if let Ok(value) = old_force_pass_value {
+        env::set_var("INSTA_FORCE_PASS", value);
+    } else {
+        env::remove_var("INSTA_FORCE_PASS");
+    }
+
+    assert!(!called.get());
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "log")]
+
+use insta::assert_log_snapshot;
+
+#[test]
+fn test_log() {
+    assert_log_snapshot!(
+        || {
+            log::info!("Hello World!");
+            log::warn!(target: "custom", "value is {}", 42);
+            log::trace!("details");
+        },
+        @r###"
+    INFO  test_log: Hello World!
+    WARN  custom: value is 42
+    TRACE test_log: details
+    "###
+    );
+}
+
+#[test]
+fn test_log_other_thread() {
+    assert_log_snapshot!(
+        || {
+            std::thread::spawn(|| log::error!("not captured"))
+                .join()
+                .unwrap();
+            log::debug!("captured");
+        },
+        @"DEBUG test_log: captured"
+    );
+}
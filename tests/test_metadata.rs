@@ -0,0 +1,93 @@
+use std::env;
+use std::fs;
+use std::sync::Mutex;
+
+use insta::{assert_yaml_snapshot, Settings, Snapshot};
+use lazy_static::lazy_static;
+
+lazy_static! {
+    static ref UPDATE_LOCK: Mutex<()> = Mutex::new(());
+}
+
+/// Writes a snapshot with the given settings and reads it back.
+///
+/// Snapshots that match are not written again so every snapshot goes to a
+/// fresh folder and is written in place with `INSTA_UPDATE=always`.  The
+/// variable is restored afterwards and the tests of this file change it
+/// one after another.
+fn write_snapshot(name: &str, mut settings: Settings) -> Snapshot {
+    let _guard = UPDATE_LOCK.lock().unwrap_or_else(|err| err.into_inner());
+    let old_update_value = env::var("INSTA_UPDATE");
+    env::set_var("INSTA_UPDATE", "always");
+
+    let dir = env::temp_dir().join(format!(
+        "insta-test-metadata-{}-{}",
+        std::process::id(),
+        name
+    ));
+    settings.set_snapshot_path(&dir);
+    settings.bind(|| {
+        assert_yaml_snapshot!(name, vec![1, 2, 3]);
+    });
+
+    if let Ok(value) = old_update_value {
+        env::set_var("INSTA_UPDATE", value);
+    } else {
+        env::remove_var("INSTA_UPDATE");
+    }
+
+    let snapshot = Snapshot::from_file(&dir.join(format!("test_metadata__{}.snap", name))).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+    snapshot
+}
+
+#[test]
+fn test_omit_metadata() {
+    let mut settings = Settings::new();
+    settings.set_omit_metadata(true);
+    let snapshot = write_snapshot("omit_metadata", settings);
+    assert_eq!(snapshot.metadata().source(), None);
+    assert_eq!(snapshot.metadata().expression(), None);
+    assert_eq!(snapshot.contents_str(), "- 1\n- 2\n- 3\n");
+}
+
+#[test]
+fn test_snapshot_tags() {
+    let mut settings = Settings::new();
+    settings.set_snapshot_tag("seed", "42");
+    let snapshot = write_snapshot("snapshot_tags", settings);
+    let tags = snapshot.metadata().tags().unwrap();
+    assert_eq!(tags.get("seed").map(String::as_str), Some("42"));
+}
+
+#[test]
+fn test_snapshot_description() {
+    let mut settings = Settings::new();
+    settings.set_description("The numbers one to three in order");
+    let snapshot = write_snapshot("snapshot_description", settings);
+    assert_eq!(
+        snapshot.metadata().description(),
+        Some("The numbers one to three in order")
+    );
+}
+
+#[test]
+fn test_omit_expression() {
+    let mut settings = Settings::new();
+    settings.set_omit_expression(true);
+    let snapshot = write_snapshot("omit_expression", settings);
+    assert_eq!(snapshot.metadata().expression(), None);
+    assert_eq!(snapshot.metadata().source(), Some("tests/test_metadata.rs"));
+}
+
+#[test]
+fn test_record_environment() {
+    let mut settings = Settings::new();
+    settings.set_record_environment(true);
+    let snapshot = write_snapshot("record_environment", settings);
+    assert!(snapshot.metadata().rust_version().is_some());
+    assert!(snapshot
+        .metadata()
+        .platform()
+        .map_or(false, |x| x.starts_with(env::consts::ARCH)));
+}
@@ -0,0 +1,87 @@
+#![cfg(feature = "prost")]
+
+use std::collections::HashMap;
+
+use insta::assert_proto_snapshot;
+use serde::Serialize;
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct Address {
+    #[prost(string, tag = "1")]
+    pub city: String,
+}
+
+#[derive(Clone, PartialEq, Serialize, prost::Message)]
+pub struct User {
+    #[prost(uint64, tag = "1")]
+    pub id: u64,
+    #[prost(string, tag = "2")]
+    pub name: String,
+    #[prost(string, repeated, tag = "3")]
+    pub tags: Vec<String>,
+    #[prost(message, optional, tag = "4")]
+    pub address: Option<Address>,
+    #[prost(map = "string, string", tag = "5")]
+    pub labels: HashMap<String, String>,
+}
+
+fn make_user() -> User {
+    let mut labels = HashMap::new();
+    labels.insert("team".to_string(), "core".to_string());
+    labels.insert("role".to_string(), "admin".to_string());
+    User {
+        id: 42,
+        name: "john \"doe\"".into(),
+        tags: vec!["a".into(), "b".into()],
+        address: Some(Address {
+            city: "Vienna".into(),
+        }),
+        labels,
+    }
+}
+
+#[test]
+fn test_proto_message() {
+    assert_proto_snapshot!(make_user(), @r###"
+    id: 42
+    name: "john \"doe\""
+    tags: "a"
+    tags: "b"
+    address {
+      city: "Vienna"
+    }
+    labels {
+      key: "role"
+      value: "admin"
+    }
+    labels {
+      key: "team"
+      value: "core"
+    }
+    "###);
+}
+
+#[cfg(feature = "redactions")]
+#[test]
+fn test_proto_redactions() {
+    assert_proto_snapshot!(make_user(), {
+        ".id" => "[id]",
+        ".address.city" => "[city]",
+    }, @r###"
+    id: "[id]"
+    name: "john \"doe\""
+    tags: "a"
+    tags: "b"
+    address {
+      city: "[city]"
+    }
+    labels {
+      key: "role"
+      value: "admin"
+    }
+    labels {
+      key: "team"
+      value: "core"
+    }
+    "###);
+}
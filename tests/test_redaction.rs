@@ -186,6 +186,37 @@ fn test_with_random_value_json_settings2() {
     });
 }
 
+#[test]
+fn test_assertion_redactions_override_settings() {
+    with_settings!({redactions => vec![
+        (".id", "[id]".into()),
+        (".extra", "[extra]".into()),
+    ]}, {
+        assert_json_snapshot!(
+            &User {
+                id: 976,
+                username: "jason_doe".to_string(),
+                email: Email("jason@example.com".to_string()),
+                extra: "ssn goes here".to_string(),
+            },
+            {
+                ".id" => insta::dynamic_redaction(|value, _| {
+                    assert_eq!(value.as_u64(), Some(976));
+                    "[user id]"
+                }),
+            },
+            @r###"
+            {
+              "id": "[user id]",
+              "username": "jason_doe",
+              "email": "jason@example.com",
+              "extra": "[extra]"
+            }
+            "###
+        );
+    });
+}
+
 #[test]
 fn test_redact_newtype_struct() {
     #[derive(Serialize)]
@@ -341,3 +372,205 @@ fn test_map_key_redaction() {
         ".btm.$key" => "[key]",
     });
 }
+
+#[test]
+fn test_key_redaction() {
+    #[derive(Serialize)]
+    pub struct Account {
+        name: &'static str,
+        password: &'static str,
+        api_key: &'static str,
+        children: Vec<Account>,
+    }
+
+    let mut extra = BTreeMap::new();
+    extra.insert("password", "nested secret");
+    extra.insert("public_key", "not a secret");
+
+    let value = (
+        Account {
+            name: "root",
+            password: "hunter2",
+            api_key: "abc",
+            children: vec![Account {
+                name: "child",
+                password: "hunter3",
+                api_key: "def",
+                children: vec![],
+            }],
+        },
+        extra,
+    );
+
+    let mut settings = Settings::new();
+    settings.add_key_redaction("password", "[password]");
+    settings.add_key_regex_redaction("^api_", "[key]");
+    settings.bind(|| {
+        assert_yaml_snapshot!(&value, @r###"
+        ---
+        - name: root
+          password: "[password]"
+          api_key: "[key]"
+          children:
+            - name: child
+              password: "[password]"
+              api_key: "[key]"
+              children: []
+        - password: "[password]"
+          public_key: not a secret
+        "###);
+    });
+}
+
+#[test]
+#[should_panic(expected = "only matches after redactions")]
+fn test_require_full_match() {
+    #[derive(Serialize)]
+    pub struct Session {
+        id: u32,
+        user: &'static str,
+    }
+
+    // the stored snapshot was recorded with a different id
+    let session = Session {
+        id: 2,
+        user: "john",
+    };
+    insta::with_settings!({require_full_match => true}, {
+        assert_yaml_snapshot!("require_full_match", &session, {
+            ".id" => "[id]"
+        });
+    });
+}
+
+#[test]
+fn test_redacted_match_without_full_match() {
+    #[derive(Serialize)]
+    pub struct Session {
+        id: u32,
+        user: &'static str,
+    }
+
+    // the changed raw value is ignored unless full matches are required
+    let session = Session {
+        id: 2,
+        user: "john",
+    };
+    assert_yaml_snapshot!("require_full_match", &session, {
+        ".id" => "[id]"
+    });
+}
+
+#[tokio::test]
+async fn test_redaction_in_spawned_task() {
+    #[derive(Serialize)]
+    pub struct Task {
+        id: u32,
+        name: &'static str,
+    }
+
+    let mut settings = Settings::new();
+    settings.add_redaction(".id", "[id]");
+    settings
+        .bind_async(async {
+            tokio::spawn(insta::with_captured_settings(async {
+                assert_json_snapshot!(&Task { id: 42, name: "task" }, @r###"
+                {
+                  "id": "[id]",
+                  "name": "task"
+                }
+                "###);
+            }))
+            .await
+            .unwrap();
+        })
+        .await;
+}
+
+#[test]
+fn test_sorted_redaction() {
+    #[derive(Serialize)]
+    pub struct Result {
+        id: u32,
+        name: &'static str,
+    }
+
+    #[derive(Serialize)]
+    pub struct Scan {
+        results: Vec<Result>,
+        ids: Vec<u32>,
+        mixed: Vec<serde_json::Value>,
+    }
+
+    assert_json_snapshot!(&Scan {
+        results: vec![
+            Result { id: 2, name: "b" },
+            Result { id: 10, name: "c" },
+            Result { id: 1, name: "a" },
+        ],
+        ids: vec![10, 2, 1],
+        mixed: vec!["b".into(), 1.into(), "a".into(), true.into()],
+    }, {
+        ".results" => insta::sorted_redaction(),
+        ".ids" => insta::sorted_redaction(),
+        ".mixed" => insta::sorted_redaction(),
+    }, @r###"
+    {
+      "results": [
+        {
+          "id": 1,
+          "name": "a"
+        },
+        {
+          "id": 10,
+          "name": "c"
+        },
+        {
+          "id": 2,
+          "name": "b"
+        }
+      ],
+      "ids": [
+        1,
+        2,
+        10
+      ],
+      "mixed": [
+        "a",
+        "b",
+        1,
+        true
+      ]
+    }
+    "###);
+}
+
+#[test]
+fn test_rounded_redaction() {
+    #[derive(Serialize)]
+    pub struct Body {
+        name: &'static str,
+        mass: f32,
+        velocity: f64,
+        energy: Option<f64>,
+    }
+
+    assert_json_snapshot!(&Body {
+        name: "probe",
+        mass: 1.234_567,
+        velocity: 0.1 + 0.2,
+        energy: Some(2.0 / 3.0),
+    }, {
+        ".name" => insta::rounded_redaction(2),
+        ".mass" => insta::rounded_redaction(2),
+        ".velocity" => insta::rounded_redaction(4),
+        ".energy" => insta::rounded_redaction(3),
+    }, @r###"
+    {
+      "name": "probe",
+      "mass": 1.23,
+      "velocity": 0.3,
+      "energy": 0.667
+    }
+    "###);
+}
@@ -1,5 +1,10 @@
-use insta::{assert_yaml_snapshot, with_settings, Settings};
-use std::collections::HashMap;
+use insta::internals::Content;
+use insta::{
+    assert_json_snapshot, assert_serialized_snapshot, assert_snapshot, assert_yaml_snapshot,
+    with_settings, Settings, Snapshot,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
 #[test]
 fn test_simple() {
@@ -61,6 +66,21 @@ fn test_settings_macro() {
     });
 }
 
+#[test]
+fn test_sort_maps_depth() {
+    let inner = Content::Map(vec![("y".into(), 1u8.into()), ("x".into(), 2u8.into())]);
+    let map = Content::Map(vec![("b".into(), inner), ("a".into(), 3u8.into())]);
+    with_settings!({sort_maps => true, sort_maps_depth => Some(1)}, {
+        assert_yaml_snapshot!(&map, @r###"
+        ---
+        a: 3
+        b:
+          y: 1
+          x: 2
+        "###);
+    });
+}
+
 #[test]
 fn test_snapshot_path() {
     with_settings!({snapshot_path => "snapshots2"}, {
@@ -74,3 +94,170 @@ fn test_snapshot_no_module_prepending() {
         assert_yaml_snapshot!(vec![1, 2, 3]);
     });
 }
+
+#[test]
+fn test_in_memory() {
+    with_settings!({in_memory => true}, {
+        // existing snapshots are still read from disk
+        assert_yaml_snapshot!(vec![1, 2, 3]);
+        assert!(std::panic::catch_unwind(|| {
+            assert_snapshot!("in_memory_missing", "Hello World!");
+        })
+        .is_err());
+    });
+    assert!(!Path::new("tests/snapshots/test_settings__in_memory_missing.snap").exists());
+    assert!(!Path::new("tests/snapshots/test_settings__in_memory_missing.snap.new").exists());
+}
+
+#[test]
+fn test_normalize_path_separators() {
+    let paths = vec!["src\\lib.rs", "tests/test_settings.rs"];
+    with_settings!({normalize_path_separators => true}, {
+        assert_yaml_snapshot!(&paths, @r###"
+        ---
+        - src/lib.rs
+        - tests/test_settings.rs
+        "###);
+    });
+}
+
+#[test]
+fn test_skip_none_fields() {
+    let mut inner = BTreeMap::new();
+    inner.insert("present", Some(1));
+    inner.insert("absent", None);
+    let mut map = BTreeMap::new();
+    map.insert("inner", Some(inner));
+    map.insert("missing", None);
+    with_settings!({skip_none_fields => true}, {
+        assert_yaml_snapshot!(&map, @r###"
+        ---
+        inner:
+          present: 1
+        "###);
+    });
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn test_sort_maps_toml() {
+    let mut map = HashMap::new();
+    map.insert("b", Content::from("plain"));
+    map.insert("a", Content::Map(vec![("key".into(), "value".into())]));
+    map.insert("c", Content::from(42u8));
+    with_settings!({sort_maps => true}, {
+        insta::assert_toml_snapshot!(&map, @r###"
+        b = 'plain'
+        c = 42
+
+        [a]
+        key = 'value'
+        "###);
+    });
+}
+
+#[test]
+fn test_normalizers() {
+    insta::register_normalizer("reverse", |content| match content {
+        Content::Seq(mut items) => {
+            items.reverse();
+            Content::Seq(items)
+        }
+        other => other,
+    });
+    insta::register_normalizer("drop-first", |content| match content {
+        Content::Seq(items) => Content::Seq(items.into_iter().skip(1).collect()),
+        other => other,
+    });
+    with_settings!({normalizers => vec!["reverse", "drop-first"]}, {
+        assert_yaml_snapshot!(vec![1, 2, 3], @r###"
+        ---
+        - 2
+        - 1
+        "###);
+    });
+}
+
+#[test]
+fn test_snapshot_namespace() {
+    with_settings!({snapshot_namespace => "ns"}, {
+        assert_yaml_snapshot!(vec![1, 2, 3]);
+    });
+}
+
+#[test]
+fn test_snapshot_extension() {
+    with_settings!({snapshot_extension => "snap.html"}, {
+        assert_snapshot!("extension", "<p>Hello World!</p>");
+    });
+}
+
+#[test]
+fn test_default_format() {
+    assert_serialized_snapshot!(vec![1, 2], @r###"
+    ---
+    - 1
+    - 2
+    "###);
+    with_settings!({default_format => "json"}, {
+        assert_serialized_snapshot!(vec![1, 2], @r###"
+        [
+          1,
+          2
+        ]
+        "###);
+    });
+}
+
+#[test]
+fn test_structural_comparison() {
+    let mut map = BTreeMap::new();
+    map.insert("a", vec![1, 2]);
+    map.insert("b", vec![3]);
+    with_settings!({structural_comparison => true}, {
+        assert_json_snapshot!(&map, @r###"{"b": [3], "a": [1, 2]}"###);
+    });
+    with_settings!({structural_comparison => true, unordered_sequences => true}, {
+        assert_json_snapshot!(&map, @r###"{"b": [3], "a": [2, 1]}"###);
+    });
+}
+
+#[test]
+fn test_substitute_workspace_root() {
+    // the separator after the root is written out so that the snapshot is
+    // the same on all platforms
+    let value = format!("{}/src/lib.rs", env!("CARGO_MANIFEST_DIR"));
+    with_settings!({substitute_workspace_root => true}, {
+        assert_snapshot!(value, @"[WORKSPACE_ROOT]/src/lib.rs");
+    });
+}
+
+#[test]
+fn test_externalize_over_bytes() {
+    let lines = (0..20).map(|x| format!("line {}", x)).collect::<Vec<_>>();
+    with_settings!({externalize_over_bytes => 64usize}, {
+        assert_snapshot!("externalized", lines.join("\n"));
+    });
+    let snapshot = Snapshot::from_file("tests/snapshots/test_settings__externalized.snap").unwrap();
+    assert!(snapshot.metadata().data_hash().is_some());
+    assert_eq!(snapshot.contents_str(), lines.join("\n"));
+}
+
+#[test]
+fn test_min_similarity() {
+    let lines = (0..10).map(|x| format!("line {}", x)).collect::<Vec<_>>();
+    with_settings!({min_similarity => 0.8}, {
+        assert_snapshot!(lines.join("\n").replace("line 5", "changed"), @r###"
+        line 0
+        line 1
+        line 2
+        line 3
+        line 4
+        line 5
+        line 6
+        line 7
+        line 8
+        line 9
+        "###);
+    });
+}
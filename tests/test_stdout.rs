@@ -0,0 +1,44 @@
+#![cfg(all(feature = "stdout", unix))]
+
+use std::io::Write;
+use std::panic;
+
+use insta::assert_stdout_snapshot;
+
+#[test]
+fn test_stdout() {
+    assert_stdout_snapshot!(
+        || {
+            let mut stdout = std::io::stdout();
+            writeln!(stdout, "Hello World!\r").unwrap();
+            writeln!(stdout, "second line").unwrap();
+        },
+        @r###"
+    Hello World!
+    second line
+    "###
+    );
+}
+
+#[test]
+fn test_stdout_panic_restores() {
+    let rv = panic::catch_unwind(|| {
+        insta::_macro_support::capture_stdout(|| panic!("failed"));
+    });
+    assert!(rv.is_err());
+    assert_stdout_snapshot!(
+        || {
+            std::io::stdout().write_all(b"after panic").unwrap();
+        },
+        @"after panic"
+    );
+}
+
+#[test]
+fn test_stdout_println() {
+    let nocapture = std::env::args().any(|x| x == "--nocapture" || x == "--no-capture")
+        || std::env::var("RUST_TEST_NOCAPTURE").map_or(false, |x| x != "0");
+    let output = insta::_macro_support::capture_stdout(|| println!("Hello println!"));
+    // the test harness captures println! unless tests run with --nocapture
+    assert_eq!(output.contains("Hello println!"), nocapture);
+}